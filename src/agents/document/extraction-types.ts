@@ -21,6 +21,9 @@ export type EntityType =
   | 'percentage'
   | 'email'
   | 'phone'
+  | 'url'
+  | 'iban'
+  | 'creditCard'
   | 'custom';
 
 export interface ExtractionResult {
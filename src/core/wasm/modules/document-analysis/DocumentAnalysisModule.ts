@@ -1,4 +1,5 @@
 import { WasmModule } from '../../WasmModule';
+import { TextProcessingModule } from '../text-processing/TextProcessingModule';
 import {
   DocumentAnalysisConfig,
   DocumentAnalysisResult,
@@ -30,7 +31,11 @@ export class DocumentAnalysisModule extends WasmModule {
     maxDepth: 5,
   };
 
-  constructor(config: DocumentAnalysisConfig) {
+  /**
+   * @param textProcessing counts the words of analyzed documents; without it
+   * `statistics.wordCount` stays 0
+   */
+  constructor(config: DocumentAnalysisConfig, private readonly textProcessing?: TextProcessingModule) {
     super({
       ...config,
       exports: [
@@ -60,6 +65,10 @@ export class DocumentAnalysisModule extends WasmModule {
       // Call WASM function
       const resultPtr = this.exports.analyzeDocument(dataPtr, configPtr);
       const result = this.parseResult(resultPtr);
+      if (this.textProcessing) {
+        const text = this.structureText(result.structure);
+        result.statistics.wordCount = (await this.textProcessing.statistics(text)).wordCount;
+      }
 
       // Add processing time
       const endTime = performance.now();
@@ -179,6 +188,13 @@ export class DocumentAnalysisModule extends WasmModule {
     };
   }
 
+  private structureText(structure: DocumentStructure[]): string {
+    return structure
+      .map(node => [node.content, this.structureText(node.children ?? [])].filter(Boolean).join('\n\n'))
+      .filter(Boolean)
+      .join('\n\n');
+  }
+
   private parseOCRResults(ptr: number): OCRResult[] {
     // Implementation depends on WASM memory layout
     // This is a placeholder
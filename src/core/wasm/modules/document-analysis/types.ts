@@ -59,7 +59,7 @@ export interface DocumentAnalysisResult {
     tableCount: number;
     imageCount: number;
     listCount: number;
-    wordCount: number;
+    wordCount: number; // text-processing `statistics` of the structure's content; 0 without a TextProcessingModule
     averageConfidence: number;
   };
 }
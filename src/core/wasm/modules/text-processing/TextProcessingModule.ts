@@ -1,275 +1,753 @@
 import { WasmModule } from '../../WasmModule';
+import { WasmError } from '../../types';
 import {
   TextProcessingConfig,
   TextProcessingResult,
   TextChunk,
+  TextProcessingOptions,
   NLPResult,
   TextProcessingError,
+  TokenizerFormat,
+  Entity,
+  EntityExtractionOptions,
+  GazetteerDictionary,
+  GazetteerOptions,
+  Redaction,
+  RedactionMapping,
+  RedactionPolicy,
+  Keyword,
+  KeywordOptions,
+  CorpusStats,
+  Summary,
+  SummaryOptions,
+  SearchHit,
+  SearchOptions,
+  FuzzyHit,
+  FuzzyOptions,
+  FuzzyTerm,
+  Suggestion,
+  VectorHit,
+  VectorIndexOptions,
+  VectorSearchOptions,
+  Duplicate,
+  DuplicateIndexOptions,
+  Fingerprint,
+  FingerprintOptions,
+  Normalized,
+  NormalizationOptions,
+  Sentence,
+  SentenceOptions,
+  AnalyzerOptions,
+  Term,
+  ReadabilityOptions,
+  TextStatistics,
+  DiffOptions,
+  TextDiff,
+  DecodedText,
+  HtmlOptions,
+  HtmlText,
+  TemporalOptions,
+  TemporalExpression,
+  ClassifierOptions,
+  TrainingExample,
+  TrainingReport,
+  Classification,
+  SentimentOptions,
+  Sentiment,
 } from './types';
 
-interface TextProcessingExports extends WebAssembly.Exports {
-  memory: WebAssembly.Memory;
-  allocate: (size: number) => number;
-  deallocate: (ptr: number, size: number) => void;
-  processText: (textPtr: number) => number;
-  chunkText: (textPtr: number, configPtr: number) => number;
-  tokenizeText: (textPtr: number) => number;
-  detectLanguage: (textPtr: number) => number;
-  extractEntities: (textPtr: number) => number;
+/** Methods of the Rust `TextProcessor` class, as wasm-bindgen exposes them. */
+interface TextProcessingExports {
+  process: (text: string, options?: TextProcessingOptions) => TextProcessingResult;
+  processBytes: (data: Uint8Array, options?: TextProcessingOptions) => TextProcessingResult;
+  decode: (data: Uint8Array, encoding?: string) => DecodedText;
+  chunk: (text: string, options?: TextProcessingOptions) => TextChunk[];
+  stream: (options?: TextProcessingOptions) => void;
+  push: (text: string) => TextChunk[];
+  finish: () => TextChunk[];
+  useTokenizer: (data: string, format: TokenizerFormat) => void;
+  normalize: (
+    text: string,
+    options?: NormalizationOptions & { preserveWhitespace?: boolean; preserveNewlines?: boolean }
+  ) => Normalized;
+  sentences: (text: string, options?: SentenceOptions) => Sentence[];
+  analyze: (text: string, options?: AnalyzerOptions) => Term[];
+  statistics: (text: string, options?: ReadabilityOptions) => TextStatistics;
+  diff: (oldText: string, newText: string, options?: DiffOptions) => TextDiff;
+  htmlToText: (html: string, options?: HtmlOptions) => HtmlText;
+  sentiment: (text: string, options?: SentimentOptions) => Sentiment;
+  extractTemporal: (text: string, options?: TemporalOptions) => TemporalExpression[];
+  extractEntities: (text: string, options?: EntityExtractionOptions) => Entity[];
+  useGazetteer: (dictionaries: GazetteerDictionary[], options?: GazetteerOptions) => void;
+  redact: (text: string, policy?: RedactionPolicy) => Redaction;
+  restore: (text: string, mapping: RedactionMapping) => string;
+  extractKeywords: (text: string, options?: KeywordOptions) => Keyword[];
+  useCorpus: (stats?: CorpusStats) => void;
+  addToCorpus: (text: string) => void;
+  corpusStats: () => CorpusStats | null;
+  summarize: (text: string, options?: SummaryOptions) => Summary;
+  useIndex: (data?: Uint8Array) => void;
+  indexChunks: (chunks: TextChunk[]) => void;
+  removeFromIndex: (id: string) => boolean;
+  search: (query: string, options?: SearchOptions) => SearchHit[];
+  saveIndex: () => Uint8Array;
+  editDistance: (a: string, b: string, transpositions: boolean) => number;
+  fuzzyTerms: (term: string, options?: FuzzyOptions) => FuzzyTerm[];
+  fuzzySearch: (pattern: string, options?: FuzzyOptions) => FuzzyHit[];
+  suggest: (query: string, options?: FuzzyOptions) => Suggestion | null;
+  useVectorIndex: (options?: VectorIndexOptions) => void;
+  loadVectorIndex: (data: Uint8Array) => void;
+  addVector: (id: string, vector: Float32Array, metadata?: Record<string, unknown>) => void;
+  removeVector: (id: string) => boolean;
+  searchVectors: (vector: Float32Array, options?: VectorSearchOptions) => VectorHit[];
+  saveVectorIndex: () => Uint8Array;
+  fingerprint: (text: string, options?: FingerprintOptions) => Fingerprint;
+  simhashDistance: (a: string, b: string) => number;
+  useDuplicateIndex: (options?: DuplicateIndexOptions) => void;
+  addFingerprint: (id: string, fingerprint: Fingerprint) => void;
+  removeFingerprint: (id: string) => boolean;
+  findDuplicates: (fingerprint: Fingerprint) => Duplicate[];
+  trainClassifier: (examples: TrainingExample[], options?: ClassifierOptions) => TrainingReport;
+  loadClassifier: (data: Uint8Array) => void;
+  saveClassifier: () => Uint8Array;
+  classify: (text: string) => Classification;
   cleanup: () => void;
 }
 
+/** The JS glue `wasm-pack build --target web` writes next to the `.wasm` file. */
+interface TextProcessingBindings {
+  default: (module?: string | URL | Response | BufferSource) => Promise<unknown>;
+  TextProcessor: new () => TextProcessingExports;
+}
+
 export class TextProcessingModule extends WasmModule {
-  private readonly defaultConfig: Required<NonNullable<TextProcessingConfig['options']>> = {
+  private readonly bindingsPath: string;
+  private processor?: TextProcessingExports;
+  // `language` stays unset so that it is detected per document
+  private readonly defaultConfig: Required<
+    Omit<TextProcessingOptions, 'language' | 'html' | 'normalization' | 'redaction' | 'sentiment'>
+  > = {
     chunkSize: 1024,
     overlap: 200,
     preserveWhitespace: false,
     preserveNewlines: true,
     trimChunks: true,
-    language: 'en',
-    encoding: 'utf-8',
+    sizeUnit: 'graphemes',
+    strategy: 'fixed',
+    encoding: 'auto',
   };
 
   constructor(config: TextProcessingConfig) {
     super({
       ...config,
       exports: [
-        'allocate',
-        'deallocate',
-        'processText',
-        'chunkText',
-        'tokenizeText',
-        'detectLanguage',
+        'process',
+        'processBytes',
+        'decode',
+        'chunk',
+        'stream',
+        'push',
+        'finish',
+        'useTokenizer',
+        'normalize',
+        'sentences',
+        'analyze',
+        'statistics',
+        'diff',
+        'htmlToText',
+        'sentiment',
+        'extractTemporal',
         'extractEntities',
+        'useGazetteer',
+        'redact',
+        'restore',
+        'extractKeywords',
+        'useCorpus',
+        'addToCorpus',
+        'corpusStats',
+        'summarize',
+        'useIndex',
+        'indexChunks',
+        'removeFromIndex',
+        'search',
+        'saveIndex',
+        'editDistance',
+        'fuzzyTerms',
+        'fuzzySearch',
+        'suggest',
+        'useVectorIndex',
+        'loadVectorIndex',
+        'addVector',
+        'removeVector',
+        'searchVectors',
+        'saveVectorIndex',
+        'fingerprint',
+        'simhashDistance',
+        'useDuplicateIndex',
+        'addFingerprint',
+        'removeFingerprint',
+        'findDuplicates',
+        'trainClassifier',
+        'loadClassifier',
+        'saveClassifier',
+        'classify',
         'cleanup',
       ],
     });
+    this.bindingsPath = config.bindingsPath ?? this.config.wasmPath.replace(/_bg\.wasm$/, '.js');
+  }
+
+  /**
+   * Loads the wasm-bindgen glue, which instantiates the `.wasm` file with the
+   * imports it needs, and creates the one `TextProcessor` all calls go to.
+   */
+  async initialize(): Promise<void> {
+    try {
+      const bindings: TextProcessingBindings = await import(/* webpackIgnore: true */ this.bindingsPath);
+      await bindings.default(this.config.wasmPath);
+      this.processor = new bindings.TextProcessor();
+      this.validateExports();
+      this.logger.info('WASM module initialized successfully', {
+        name: this.config.name,
+        type: this.config.type,
+      });
+    } catch (error) {
+      const wasmError: WasmError = {
+        name: 'WasmInitializationError',
+        message: `Failed to initialize WASM module: ${error.message}`,
+        code: 'WASM_INIT_ERROR',
+        moduleType: this.config.type,
+        operation: 'initialize',
+        cause: error,
+      };
+      this.logger.error('WASM module initialization failed', { error: wasmError });
+      throw wasmError;
+    }
+  }
+
+  protected validateExports(): void {
+    for (const exportName of this.config.exports) {
+      if (typeof this.exports[exportName as keyof TextProcessingExports] !== 'function') {
+        throw new Error(`Required export '${exportName}' not found in WASM module`);
+      }
+    }
   }
 
   protected get exports(): TextProcessingExports {
-    if (!this.instance) {
+    if (!this.processor) {
       throw new Error('WASM module not initialized');
     }
-    return this.instance.exports as TextProcessingExports;
+    return this.processor;
   }
 
   async processText(text: string): Promise<TextProcessingResult> {
     try {
-      const startTime = performance.now();
-      const textPtr = this.allocateString(text);
+      return this.exports.process(text, this.defaultConfig);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
 
-      // Call WASM function
-      const resultPtr = this.exports.processText(textPtr);
-      const result = this.parseResult(resultPtr);
+  /** Processes a file's bytes in `options.encoding`, detecting it by default. */
+  async processBytes(
+    data: ArrayBuffer | Uint8Array,
+    options?: Partial<TextProcessingOptions>
+  ): Promise<TextProcessingResult> {
+    try {
+      return this.exports.processBytes(new Uint8Array(data), { ...this.defaultConfig, ...options });
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
 
-      // Calculate stats
-      const endTime = performance.now();
-      const stats = {
-        inputLength: text.length,
-        chunkCount: result.chunks.length,
-        averageChunkSize: result.chunks.reduce((acc, chunk) => acc + chunk.text.length, 0) / result.chunks.length,
-        processingTime: endTime - startTime,
-        memoryUsed: this.getMemoryStats().usedPages * 64 * 1024,
-      };
+  async decode(data: ArrayBuffer | Uint8Array, encoding?: string): Promise<DecodedText> {
+    try {
+      return this.exports.decode(new Uint8Array(data), encoding);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
 
-      return {
-        ...result,
-        stats,
-      };
+  async chunkText(text: string, options?: Partial<TextProcessingOptions>): Promise<TextChunk[]> {
+    try {
+      return this.exports.chunk(text, { ...this.defaultConfig, ...options });
     } catch (error) {
       this.handleError(error);
-    } finally {
-      this.cleanup();
     }
   }
 
-  async chunkText(text: string, options?: Partial<TextProcessingConfig['options']>): Promise<TextChunk[]> {
+  async loadTokenizer(data: string, format: TokenizerFormat): Promise<void> {
     try {
-      const config = {
-        ...this.defaultConfig,
-        ...options,
-      };
+      this.exports.useTokenizer(data, format);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
 
-      const textPtr = this.allocateString(text);
-      const configPtr = this.allocateConfig(config);
+  async beginStream(options?: Partial<TextProcessingOptions>): Promise<void> {
+    try {
+      this.exports.stream({ ...this.defaultConfig, ...options });
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
 
-      // Call WASM function
-      const resultPtr = this.exports.chunkText(textPtr, configPtr);
-      return this.parseChunks(resultPtr);
+  /**
+   * Feeds the next piece of a streamed text. Returns the chunks completed so
+   * far, with offsets counted from the start of the stream.
+   */
+  async pushText(text: string): Promise<TextChunk[]> {
+    try {
+      return this.exports.push(text);
     } catch (error) {
       this.handleError(error);
-    } finally {
-      this.cleanup();
     }
   }
 
-  async analyzeText(text: string): Promise<NLPResult> {
-    if (!this.instance) {
-      throw new Error('WASM module not initialized');
+  async finishStream(): Promise<TextChunk[]> {
+    try {
+      return this.exports.finish();
+    } catch (error) {
+      this.handleError(error);
     }
+  }
 
+  async normalize(
+    text: string,
+    options?: NormalizationOptions & { preserveWhitespace?: boolean; preserveNewlines?: boolean }
+  ): Promise<Normalized> {
     try {
-      const startTime = performance.now();
-      const textPtr = this.allocateString(text);
+      return this.exports.normalize(text, options);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
 
-      // Call WASM functions
-      const tokensPtr = this.exports.tokenizeText(textPtr);
-      const tokens = this.parseStringArray(tokensPtr);
+  /** Maps a UTF-16 position in normalized text back to the original text. */
+  toOriginalOffset(offsets: [number, number][], position: number): number {
+    let low = 0;
+    let high = offsets.length;
+    while (low < high) {
+      const mid = (low + high) >> 1;
+      if (offsets[mid][0] <= position) {
+        low = mid + 1;
+      } else {
+        high = mid;
+      }
+    }
+    const [normalized, original] = low === 0 ? [0, 0] : offsets[low - 1];
+    const mapped = original + (position - normalized);
+    return low < offsets.length ? Math.min(mapped, offsets[low][1]) : mapped;
+  }
 
-      const languagePtr = this.exports.detectLanguage(textPtr);
-      const language = this.parseString(languagePtr);
+  async sentences(text: string, options?: SentenceOptions): Promise<Sentence[]> {
+    try {
+      return this.exports.sentences(text, options);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
 
-      const entitiesPtr = this.exports.extractEntities(textPtr);
-      const entities = this.parseEntities(entitiesPtr);
+  async analyze(text: string, options?: AnalyzerOptions): Promise<Term[]> {
+    try {
+      return this.exports.analyze(text, options);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
 
-      const endTime = performance.now();
+  /** Analyzes a chunk in the language detected for it, unless `options` names one. */
+  async analyzeChunk(chunk: TextChunk, options?: AnalyzerOptions): Promise<Term[]> {
+    return this.analyze(chunk.text, { language: chunk.metadata?.language, ...options });
+  }
 
-      return {
-        tokens,
-        sentences: this.getSentences(tokens),
-        paragraphs: this.getParagraphs(text),
-        entities,
-        metadata: {
-          language,
-          confidence: 0.95, // TODO: Get from WASM
-          processingTime: endTime - startTime,
-        },
-      };
+  /** Counts and readability scores; the word count is the one `analyzeText` reports. */
+  async statistics(text: string, options?: ReadabilityOptions): Promise<TextStatistics> {
+    try {
+      return this.exports.statistics(text, options);
     } catch (error) {
       this.handleError(error);
-      throw error;
-    } finally {
-      this.cleanup();
     }
   }
 
-  private allocateString(text: string): number {
-    const encoder = new TextEncoder();
-    const bytes = encoder.encode(text);
-    const ptr = this.exports.allocate(bytes.length + 1);
-    
-    const memory = new Uint8Array(this.exports.memory.buffer);
-    memory.set(bytes, ptr);
-    memory[ptr + bytes.length] = 0; // Null terminator
+  async diff(oldText: string, newText: string, options?: DiffOptions): Promise<TextDiff> {
+    try {
+      return this.exports.diff(oldText, newText, options);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
 
-    return ptr;
+  /** Strips markup and boilerplate, keeping headings, lists and tables as Markdown. */
+  async htmlToText(html: string, options?: HtmlOptions): Promise<HtmlText> {
+    try {
+      return this.exports.htmlToText(html, options);
+    } catch (error) {
+      this.handleError(error);
+    }
   }
 
-  private allocateConfig(config: Required<NonNullable<TextProcessingConfig['options']>>): number {
-    const configData = new Int32Array([
-      config.chunkSize,
-      config.overlap,
-      config.preserveWhitespace ? 1 : 0,
-      config.preserveNewlines ? 1 : 0,
-      config.trimChunks ? 1 : 0,
-    ]);
+  /** Scores tone per sentence and for the whole text with a built-in lexicon; no model download. */
+  async sentiment(text: string, options?: SentimentOptions): Promise<Sentiment> {
+    try {
+      return this.exports.sentiment(text, options);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
 
-    const ptr = this.exports.allocate(configData.byteLength);
-    new Int32Array(this.exports.memory.buffer).set(configData, ptr / 4);
+  /** Finds dates, times, ranges and durations, normalized to ISO 8601. */
+  async extractTemporal(text: string, options?: TemporalOptions): Promise<TemporalExpression[]> {
+    try {
+      return this.exports.extractTemporal(text, options);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
 
-    return ptr;
+  async extractEntities(text: string, options?: EntityExtractionOptions): Promise<Entity[]> {
+    try {
+      return this.exports.extractEntities(text, options);
+    } catch (error) {
+      this.handleError(error);
+    }
   }
 
-  private parseResult(ptr: number): Omit<TextProcessingResult, 'stats'> {
-    const view = new DataView(this.exports.memory.buffer);
-    const chunkCount = view.getInt32(ptr, true);
-    const metadataPtr = view.getInt32(ptr + 4, true);
-    const chunksPtr = view.getInt32(ptr + 8, true);
+  /**
+   * Loads dictionaries matched by `extractEntities` alongside its patterns,
+   * replacing any loaded before. An empty list unloads them.
+   */
+  async loadGazetteer(dictionaries: GazetteerDictionary[], options?: GazetteerOptions): Promise<void> {
+    try {
+      this.exports.useGazetteer(dictionaries, options);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
 
-    const chunks = this.parseChunks(chunksPtr, chunkCount);
-    const metadata = this.parseMetadata(metadataPtr);
+  async redact(text: string, policy?: RedactionPolicy): Promise<Redaction> {
+    try {
+      return this.exports.redact(text, policy);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
 
-    // Deallocate memory
-    this.exports.deallocate(ptr, 12);
-    this.exports.deallocate(chunksPtr, chunkCount * 24); // Assuming 24 bytes per chunk
-    this.exports.deallocate(metadataPtr, 16); // Assuming 16 bytes for metadata
+  /**
+   * Puts redacted values back into the redacted text, or into text derived
+   * from it such as a summary (placeholder and pseudonym modes only).
+   */
+  async restore(text: string, mapping: RedactionMapping): Promise<string> {
+    try {
+      return this.exports.restore(text, mapping);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
 
-    return {
-      chunks,
-      metadata,
-    };
+  async extractKeywords(text: string, options?: KeywordOptions): Promise<Keyword[]> {
+    try {
+      return this.exports.extractKeywords(text, options);
+    } catch (error) {
+      this.handleError(error);
+    }
   }
 
-  private parseChunks(ptr: number, count: number = 0): TextChunk[] {
-    const view = new DataView(this.exports.memory.buffer);
-    const chunks: TextChunk[] = [];
-
-    for (let i = 0; i < count; i++) {
-      const offset = ptr + i * 24; // 24 bytes per chunk
-      const id = crypto.randomUUID();
-      const textPtr = view.getInt32(offset, true);
-      const start = view.getInt32(offset + 4, true);
-      const end = view.getInt32(offset + 8, true);
-      const metadataPtr = view.getInt32(offset + 12, true);
-
-      chunks.push({
-        id,
-        text: this.parseString(textPtr),
-        start,
-        end,
-        metadata: this.parseMetadata(metadataPtr),
-      });
+  /**
+   * Loads corpus statistics for TF-IDF keywords, or starts an empty corpus
+   * when none are given. Documents added later update them.
+   */
+  async loadCorpus(stats?: CorpusStats): Promise<void> {
+    try {
+      this.exports.useCorpus(stats);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
 
-      // Deallocate chunk memory
-      this.exports.deallocate(textPtr, end - start);
-      this.exports.deallocate(metadataPtr, 16);
+  async addToCorpus(texts: string[]): Promise<CorpusStats | null> {
+    try {
+      texts.forEach(text => this.exports.addToCorpus(text));
+      return this.exports.corpusStats();
+    } catch (error) {
+      this.handleError(error);
     }
+  }
 
-    return chunks;
+  async summarize(text: string, options?: SummaryOptions): Promise<Summary> {
+    try {
+      return this.exports.summarize(text, options);
+    } catch (error) {
+      this.handleError(error);
+    }
   }
 
-  private parseMetadata(ptr: number): Record<string, unknown> {
-    const view = new DataView(this.exports.memory.buffer);
-    return {
-      language: this.parseString(view.getInt32(ptr, true)),
-      confidence: view.getFloat64(ptr + 8, true),
-    };
+  /**
+   * Loads a search index saved with `saveIndex`, e.g. from IndexedDB, or
+   * starts an empty one.
+   */
+  async loadIndex(data?: Uint8Array): Promise<void> {
+    try {
+      this.exports.useIndex(data);
+    } catch (error) {
+      this.handleError(error);
+    }
   }
 
-  private parseString(ptr: number): string {
-    const memory = new Uint8Array(this.exports.memory.buffer);
-    let length = 0;
-    while (memory[ptr + length] !== 0) length++;
-    
-    const bytes = memory.slice(ptr, ptr + length);
-    const text = new TextDecoder().decode(bytes);
+  /** Adds chunks to the search index, replacing chunks with the same id. */
+  async indexChunks(chunks: TextChunk[]): Promise<void> {
+    try {
+      this.exports.indexChunks(chunks);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
 
-    // Deallocate string memory
-    this.exports.deallocate(ptr, length + 1);
+  async removeFromIndex(id: string): Promise<boolean> {
+    try {
+      return this.exports.removeFromIndex(id);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
 
-    return text;
+  async search(query: string, options?: SearchOptions): Promise<SearchHit[]> {
+    try {
+      return this.exports.search(query, options);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
+
+  async saveIndex(): Promise<Uint8Array> {
+    try {
+      return this.exports.saveIndex();
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
+
+  /** Levenshtein distance, or Damerau-Levenshtein with `transpositions`. */
+  async editDistance(a: string, b: string, transpositions = true): Promise<number> {
+    try {
+      return this.exports.editDistance(a, b, transpositions);
+    } catch (error) {
+      this.handleError(error);
+    }
   }
 
-  private parseStringArray(ptr: number): string[] {
-    // Implementation depends on WASM module's memory structure
-    // This is a placeholder
-    return [];
+  /** Indexed terms within a few edits of `term`, closest first. */
+  async fuzzyTerms(term: string, options?: FuzzyOptions): Promise<FuzzyTerm[]> {
+    try {
+      return this.exports.fuzzyTerms(term, options);
+    } catch (error) {
+      this.handleError(error);
+    }
   }
 
-  private parseEntities(ptr: number): NLPResult['entities'] {
-    // Implementation depends on WASM module's memory structure
-    // This is a placeholder
-    return [];
+  /** Approximate, case-insensitive occurrences of `pattern` in the indexed chunks. */
+  async fuzzySearch(pattern: string, options?: FuzzyOptions): Promise<FuzzyHit[]> {
+    try {
+      return this.exports.fuzzySearch(pattern, options);
+    } catch (error) {
+      this.handleError(error);
+    }
   }
 
-  private getSentences(tokens: string[]): string[] {
-    // Basic sentence detection
-    const sentences: string[] = [];
-    let currentSentence: string[] = [];
+  /** Spelling correction for `query` from the indexed terms, or null if none is needed. */
+  async suggest(query: string, options?: FuzzyOptions): Promise<Suggestion | null> {
+    try {
+      return this.exports.suggest(query, options);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
 
-    for (const token of tokens) {
-      currentSentence.push(token);
-      if (/[.!?]$/.test(token)) {
-        sentences.push(currentSentence.join(' '));
-        currentSentence = [];
-      }
+  /** Starts an empty vector index; the first vector added fixes its dimensions. */
+  async createVectorIndex(options?: VectorIndexOptions): Promise<void> {
+    try {
+      this.exports.useVectorIndex(options);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
+
+  async loadVectorIndex(data: Uint8Array): Promise<void> {
+    try {
+      this.exports.loadVectorIndex(data);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
+
+  /** Adds chunk embeddings, replacing any already stored for the same chunk ids. */
+  async addVectors(
+    items: { id: string; vector: Float32Array | number[]; metadata?: Record<string, unknown> }[]
+  ): Promise<void> {
+    try {
+      items.forEach(({ id, vector, metadata }) =>
+        this.exports.addVector(id, Float32Array.from(vector), metadata)
+      );
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
+
+  async removeVector(id: string): Promise<boolean> {
+    try {
+      return this.exports.removeVector(id);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
+
+  async searchVectors(
+    vector: Float32Array | number[],
+    options?: VectorSearchOptions
+  ): Promise<VectorHit[]> {
+    try {
+      return this.exports.searchVectors(Float32Array.from(vector), options);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
+
+  async saveVectorIndex(): Promise<Uint8Array> {
+    try {
+      return this.exports.saveVectorIndex();
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
+
+  async fingerprint(text: string, options?: FingerprintOptions): Promise<Fingerprint> {
+    try {
+      return this.exports.fingerprint(text, options);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
+
+  async simhashDistance(a: string, b: string): Promise<number> {
+    try {
+      return this.exports.simhashDistance(a, b);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
+
+  /**
+   * Starts an empty near-duplicate index. It is not persisted: rebuild it
+   * from the fingerprints stored with the documents.
+   */
+  async createDuplicateIndex(options?: DuplicateIndexOptions): Promise<void> {
+    try {
+      this.exports.useDuplicateIndex(options);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
+
+  async addFingerprints(items: { id: string; fingerprint: Fingerprint }[]): Promise<void> {
+    try {
+      items.forEach(({ id, fingerprint }) => this.exports.addFingerprint(id, fingerprint));
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
+
+  async removeFingerprint(id: string): Promise<boolean> {
+    try {
+      return this.exports.removeFingerprint(id);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
+
+  async findDuplicates(fingerprint: Fingerprint): Promise<Duplicate[]> {
+    try {
+      return this.exports.findDuplicates(fingerprint);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
+
+  /** Trains the classifier used by `classify`; the documents never leave the worker. */
+  async trainClassifier(examples: TrainingExample[], options?: ClassifierOptions): Promise<TrainingReport> {
+    try {
+      return this.exports.trainClassifier(examples, options);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
+
+  async loadClassifier(data: Uint8Array): Promise<void> {
+    try {
+      this.exports.loadClassifier(data);
+    } catch (error) {
+      this.handleError(error);
     }
+  }
 
-    if (currentSentence.length > 0) {
-      sentences.push(currentSentence.join(' '));
+  async saveClassifier(): Promise<Uint8Array> {
+    try {
+      return this.exports.saveClassifier();
+    } catch (error) {
+      this.handleError(error);
     }
+  }
+
+  async classify(text: string): Promise<Classification> {
+    try {
+      return this.exports.classify(text);
+    } catch (error) {
+      this.handleError(error);
+    }
+  }
+
+  async analyzeText(text: string): Promise<NLPResult> {
+    try {
+      const startTime = performance.now();
+
+      const tokens = this.exports
+        .analyze(text, { lowercase: false, removeStopwords: false, lemmatize: false, stem: false })
+        .map(term => term.text);
+
+      // One chunk for the whole text carries its detected language
+      const { metadata } = this.exports.process(text, { chunkSize: Math.max(text.length, 1), overlap: 0 });
+      const language = metadata.language ?? '';
 
-    return sentences;
+      const entities = this.exports.extractEntities(text).map(entity => ({
+        text: entity.value,
+        type: entity.type,
+        start: entity.position.start,
+        end: entity.position.end,
+        confidence: entity.confidence,
+      }));
+
+      const options = { language: language || undefined };
+      const sentences = this.exports.sentences(text, options).map(sentence => sentence.text);
+      const statistics = this.exports.statistics(text, options);
+
+      const endTime = performance.now();
+
+      return {
+        tokens,
+        sentences,
+        paragraphs: this.getParagraphs(text),
+        entities,
+        statistics,
+        metadata: {
+          language,
+          confidence: metadata.confidence ?? 0,
+          processingTime: endTime - startTime,
+        },
+      };
+    } catch (error) {
+      this.handleError(error);
+    } finally {
+      this.cleanup();
+    }
   }
 
   private getParagraphs(text: string): string[] {
@@ -277,9 +755,7 @@ export class TextProcessingModule extends WasmModule {
   }
 
   private cleanup(): void {
-    if (this.exports.cleanup) {
-      this.exports.cleanup();
-    }
+    this.processor?.cleanup();
   }
 
   private handleError(error: unknown): never {
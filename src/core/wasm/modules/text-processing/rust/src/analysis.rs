@@ -0,0 +1,278 @@
+use std::collections::{HashMap, HashSet};
+use lazy_static::lazy_static;
+use rust_stemmers::{Algorithm, Stemmer};
+use serde::{Serialize, Deserialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::extraction::{Position, Utf16Offsets};
+use crate::language;
+
+// Function words per language, after the Snowball stopword lists.
+const STOPWORDS: &[(&str, &str)] = &[
+    ("en", "a about above after again against all also am an and any are as at be because been before being \
+        below between both but by can could did do does doing down during each either few for from further had \
+        has have having he her here hers herself him himself his how i if in into is it its itself just may me \
+        might more most must my myself no nor not now of off on once only or other our ours ourselves out over \
+        own same shall she should so some such than that the their theirs them themselves then there these they \
+        this those through to too under until up upon us very was we were what when where which while who whom \
+        why will with within without would you your yours yourself yourselves"),
+    ("de", "aber alle allem allen aller alles als also am an ander andere anderem anderen anderer anderes auch auf \
+        aus bei bin bis bist da damit dann das dass dasselbe dazu dein deine deinem deinen deiner dem demselben den \
+        denn derselbe der des desselben dessen dich die dies diese dieselbe dieselben diesem diesen dieser dieses \
+        dir doch dort du durch ein eine einem einen einer eines einig einige einigem einigen einiger einiges \
+        einmal er es etwas euch euer eure eurem euren eurer für gegen gewesen hab habe haben hat hatte hatten \
+        hier hin hinter ich ihm ihn ihnen ihr ihre ihrem ihren ihrer ihres im in indem ins ist jede jedem jeden \
+        jeder jedes jene jenem jenen jener jenes jetzt kann kein keine keinem keinen keiner keines können könnte \
+        machen man manche manchem manchen mancher manches mein meine meinem meinen meiner meines mich mir mit \
+        muss musste nach nicht nichts noch nun nur ob oder ohne sehr sein seine seinem seinen seiner seines \
+        selbst sich sie sind so solche solchem solchen solcher solches soll sollte sondern sonst über um und \
+        uns unser unsere unserem unseren unserer unseres unter viel vom von vor während war waren warst was \
+        weg weil weiter welche welchem welchen welcher welches wenn werde werden wie wieder will wir wird wirst \
+        wo wollen wollte würde würden zu zum zur zwar zwischen"),
+    ("fr", "au aux avec ce ces dans de des du elle en et eux il ils je la le les leur lui ma mais me même mes moi \
+        mon ne nos notre nous on ou par pas pour qu que qui sa se ses son sur ta te tes toi ton tu un une vos \
+        votre vous c d j l à m n s t y été étée étées étés étant suis es est sommes êtes sont serai seras sera \
+        serons serez seront étais était étions étiez étaient fus fut ai as avons avez ont aurai aura avais \
+        avait avaient eu ceci cela celà cet cette ici ils les leurs quel quels quelle quelles sans soi"),
+    ("es", "de la que el en y a los del se las por un para con no una su al lo como más pero sus le ya o este sí \
+        porque esta entre cuando muy sin sobre también me hasta hay donde quien desde todo nos durante todos uno \
+        les ni contra otros ese eso ante ellos e esto mí antes algunos qué unos yo otro otras otra él tanto esa \
+        estos mucho quienes nada muchos cual poco ella estar estas algunas algo nosotros mi mis tú te ti tu tus \
+        ellas nosotras vosotros vosotras os mío mía míos mías tuyo tuya suyo suya nuestro nuestra vuestro \
+        vuestra esos esas estoy estás está estamos estáis están es son fue era eran ser sido he ha han había"),
+    ("it", "ad al allo ai agli all agl alla alle con col coi da dal dallo dai dagli dall dagl dalla dalle di del \
+        dello dei degli dell degl della delle in nel nello nei negli nell negl nella nelle su sul sullo sui sugli \
+        sull sugl sulla sulle per tra contro io tu lui lei noi voi loro mio mia miei mie tuo tua tuoi tue suo sua \
+        suoi sue nostro nostra nostri nostre vostro vostra vostri vostre mi ti ci vi lo la li le gli ne il un uno \
+        una ma ed se perché anche come dov dove che chi cui non più quale quanto quanti quanta quante quello \
+        quelli quella quelle questo questi questa queste si tutto tutti a c e i l o ho hai ha abbiamo avete \
+        hanno è sono era erano essere stato"),
+    ("pt", "de a o que e do da em um para com não uma os no se na por mais as dos como mas ao ele das à seu sua \
+        ou quando muito nos já eu também só pelo pela até isso ela entre depois sem mesmo aos seus quem nas me \
+        esse eles você essa num nem suas meu às minha numa pelos elas qual nós lhe deles essas esses pelas este \
+        dele tu te vocês vos lhes meus minhas teu tua teus tuas nosso nossa nossos nossas dela delas esta estes \
+        estas aquele aquela aqueles aquelas isto aquilo é são foi era eram ser estar está estão tem têm ter"),
+    ("nl", "de en van ik te dat die in een hij het niet zijn is was op aan met als voor had er maar om hem dan zou \
+        of wat mijn men dit zo door over ze zich bij ook tot je mij uit der daar haar naar heb hoe heeft hebben \
+        deze u want nog zal me zij nu ge geen omdat iets worden toch al waren veel meer doen toen moet ben zonder \
+        kan hun dus alles onder ja eens hier wie werd altijd doch wordt wezen kunnen ons zelf tegen na reeds wil \
+        kon niets uw iemand geweest andere"),
+    ("sv", "och det att i en jag hon som han på den med var sig för så till är men ett om hade de av icke mig du \
+        henne då sin nu har inte hans honom skulle hennes där min man ej vid kunde något från ut när efter upp \
+        vi dem vara vad över än dig kan sina här ha mot alla under någon eller allt mycket sedan ju denna själv \
+        detta åt utan varit hur ingen mitt ni bli blev oss din dessa några deras blir mina samma vilken er sådan \
+        vår blivit dess inom mellan sådant varför varje vilka ditt vem vilket sitt sådana vart dina vars vårt \
+        våra ert era vilkas"),
+    ("da", "og i jeg det at en den til er som på de med han af for ikke der var mig sig men et har om vi min havde \
+        ham hun nu over da fra du ud sin dem os op man hans hvor eller hvad skal selv her alle vil blev kunne ind \
+        når være dog noget ville jo deres efter ned skulle denne end dette mit også under have dig anden hende \
+        mine alt meget sit sine vor mod disse hvis din nogle hos blive mange ad bliver hendes været thi jer sådan"),
+    ("no", "og i jeg det at en et den til er som på de med han av ikke ikkje der så var meg seg men ett har om vi \
+        min mitt ha hadde hun nå over da ved fra du ut sin dem oss opp man kan hans hvor eller hva skal selv sjøl \
+        her alle vil bli ble blei blitt kunne inn når være kom noen noe ville dere som deres kun ja etter ned \
+        skulle denne for deg si sine sitt mot å meget hvorfor dette disse uten hvordan ingen din ditt blir samme \
+        hvilken hvilke sånn inni mellom vår hver hvem vors hvis både bare enn fordi før mange også slik vært"),
+    ("fi", "olla olen olet on olemme olette ovat ole oli olisi olisit olisin olisimme olisitte olisivat olit olin \
+        olimme olitte olivat ollut olleet en et ei emme ette eivät minä sinä hän me te he tämä tuo se nämä nuo ne \
+        mikä mitkä kuka ketkä joka jotka että ja jos koska kuin mutta niin sekä sillä tai vaan vai vaikka kanssa \
+        mukaan noin poikki yli kun niin nyt itse"),
+    ("ru", "и в во не что он на я с со как а то все она так его но да ты к у же вы за бы по только ее мне было \
+        вот от меня еще нет о из ему теперь когда даже ну вдруг ли если уже или ни быть был него до вас нибудь \
+        опять уж вам ведь там потом себя ничего ей может они тут где есть надо ней для мы тебя их чем была сам \
+        чтоб без будто чего раз тоже себе под будет ж тогда кто этот того потому этого какой совсем ним здесь \
+        этом один почти мой тем чтобы нее сейчас были куда зачем всех никогда можно при наконец два об другой \
+        хоть после над больше тот через эти нас про всего них какая много разве три эту моя впрочем хорошо \
+        свою этой перед иногда лучше чуть том нельзя такой им более всегда конечно всю между"),
+];
+
+// Common irregular English forms that suffix stripping cannot reduce.
+const ENGLISH_LEMMAS: &[(&str, &str)] = &[
+    ("am", "be"), ("is", "be"), ("are", "be"), ("was", "be"), ("were", "be"), ("been", "be"), ("being", "be"),
+    ("has", "have"), ("had", "have"), ("did", "do"), ("done", "do"), ("does", "do"), ("went", "go"),
+    ("gone", "go"), ("saw", "see"), ("seen", "see"), ("took", "take"), ("taken", "take"), ("gave", "give"),
+    ("given", "give"), ("made", "make"), ("came", "come"), ("got", "get"), ("gotten", "get"), ("knew", "know"),
+    ("known", "know"), ("thought", "think"), ("told", "tell"), ("said", "say"), ("found", "find"),
+    ("bought", "buy"), ("brought", "bring"), ("paid", "pay"), ("sold", "sell"), ("sent", "send"),
+    ("spent", "spend"), ("left", "leave"), ("kept", "keep"), ("held", "hold"), ("began", "begin"),
+    ("begun", "begin"), ("wrote", "write"), ("written", "write"), ("ran", "run"), ("met", "meet"),
+    ("men", "man"), ("women", "woman"), ("children", "child"), ("people", "person"), ("mice", "mouse"),
+    ("feet", "foot"), ("teeth", "tooth"), ("geese", "goose"), ("data", "datum"), ("criteria", "criterion"),
+    ("analyses", "analysis"), ("better", "good"), ("best", "good"), ("worse", "bad"), ("worst", "bad"),
+];
+
+lazy_static! {
+    static ref STOPWORD_SETS: HashMap<&'static str, HashSet<&'static str>> = STOPWORDS
+        .iter()
+        .map(|&(language, words)| (language, words.split_whitespace().collect()))
+        .collect();
+    static ref LEMMAS: HashMap<&'static str, &'static str> = ENGLISH_LEMMAS.iter().copied().collect();
+}
+
+/// Whether `word`, lowercased, is a function word of `language`.
+pub(crate) fn is_stopword(language: &str, word: &str) -> bool {
+    STOPWORD_SETS.get(language).is_some_and(|words| words.contains(word))
+}
+
+pub(crate) fn has_stopwords(language: &str) -> bool {
+    STOPWORD_SETS.contains_key(language)
+}
+
+/// Lowercase ISO 639-1 code of `language`, or of the language detected in
+/// `text` when unset; empty when detection fails.
+pub(crate) fn resolve_language(text: &str, language: Option<&str>) -> String {
+    let language = match language {
+        Some(language) => language.to_string(),
+        None => language::detect(text).map(|d| d.language).unwrap_or_default(),
+    };
+    language.split(['-', '_']).next().unwrap_or_default().to_lowercase()
+}
+
+fn algorithm(language: &str) -> Option<Algorithm> {
+    let algorithm = match language {
+        "ar" => Algorithm::Arabic,
+        "da" => Algorithm::Danish,
+        "nl" => Algorithm::Dutch,
+        "en" => Algorithm::English,
+        "fi" => Algorithm::Finnish,
+        "fr" => Algorithm::French,
+        "de" => Algorithm::German,
+        "el" => Algorithm::Greek,
+        "hu" => Algorithm::Hungarian,
+        "it" => Algorithm::Italian,
+        "no" | "nb" | "nn" => Algorithm::Norwegian,
+        "pt" => Algorithm::Portuguese,
+        "ro" => Algorithm::Romanian,
+        "ru" => Algorithm::Russian,
+        "es" => Algorithm::Spanish,
+        "sv" => Algorithm::Swedish,
+        "ta" => Algorithm::Tamil,
+        "tr" => Algorithm::Turkish,
+        _ => return None,
+    };
+    Some(algorithm)
+}
+
+/// Options in the shape of the TS `AnalyzerOptions`. Each filter is skipped
+/// for languages it has no data for.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AnalyzerOptions {
+    /// ISO 639-1 code, such as a chunk's `metadata.language`; detected when unset.
+    pub language: Option<String>,
+    pub lowercase: bool,
+    pub remove_stopwords: bool,
+    /// Replaces irregular forms by their dictionary form; English only.
+    pub lemmatize: bool,
+    /// Snowball stemming, which expects lowercase input.
+    pub stem: bool,
+}
+
+impl Default for AnalyzerOptions {
+    fn default() -> Self {
+        AnalyzerOptions {
+            language: None,
+            lowercase: true,
+            remove_stopwords: true,
+            lemmatize: true,
+            stem: true,
+        }
+    }
+}
+
+/// A word that passed the filters: its analyzed form, and the original
+/// word with its UTF-16 position.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Term {
+    pub term: String,
+    pub text: String,
+    pub position: Position,
+}
+
+/// The lowercase → stopword → lemma → stem filter chain for one language.
+pub struct Analyzer {
+    language: String,
+    options: AnalyzerOptions,
+    stemmer: Option<Stemmer>,
+}
+
+impl Analyzer {
+    /// Analyzer for `options.language`, or for the language detected in `text` when unset.
+    pub fn for_text(text: &str, options: &AnalyzerOptions) -> Self {
+        let language = resolve_language(text, options.language.as_deref());
+        let stemmer = options.stem.then(|| algorithm(&language)).flatten().map(Stemmer::create);
+        Analyzer { language, options: options.clone(), stemmer }
+    }
+
+    pub fn analyze(&self, text: &str) -> Vec<Term> {
+        let mut utf16 = Utf16Offsets::new(text);
+        text.unicode_word_indices()
+            .filter_map(|(offset, word)| {
+                let position = Position { start: utf16.at(offset), end: utf16.at(offset + word.len()) };
+                let term = self.term(word)?;
+                Some(Term { term, text: word.to_string(), position })
+            })
+            .collect()
+    }
+
+    /// The analyzed form of `word`, or `None` for a stopword.
+    pub fn term(&self, word: &str) -> Option<String> {
+        let lower = word.to_lowercase();
+        if self.options.remove_stopwords && is_stopword(&self.language, &lower) {
+            return None;
+        }
+        let mut term = if self.options.lowercase { lower } else { word.to_string() };
+        if self.options.lemmatize && self.language == "en" {
+            if let Some(&lemma) = LEMMAS.get(term.as_str()) {
+                term = lemma.to_string();
+            }
+        }
+        if let Some(stemmer) = &self.stemmer {
+            term = stemmer.stem(&term).into_owned();
+        }
+        Some(term)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(text: &str, options: &AnalyzerOptions) -> Vec<String> {
+        Analyzer::for_text(text, options).analyze(text).into_iter().map(|t| t.term).collect()
+    }
+
+    fn language(code: &str) -> AnalyzerOptions {
+        AnalyzerOptions { language: Some(code.to_string()), ..Default::default() }
+    }
+
+    #[test]
+    fn test_filter_chain_per_language() {
+        assert_eq!(terms("The runners were running to the Stations", &language("en")), vec!["runner", "run", "station"]);
+        assert_eq!(terms("Die Häuser der Städte", &language("de")), vec!["haus", "stadt"]);
+        assert_eq!(terms("Les chevaux et les maisons", &language("fr")), vec!["cheval", "maison"]);
+        assert_eq!(terms("Las casas de los pueblos", &language("es")), vec!["cas", "puebl"]);
+        // Languages without data pass through lowercased
+        assert_eq!(terms("Ελλάδα και Κύπρος", &language("xx")), vec!["ελλάδα", "και", "κύπρος"]);
+    }
+
+    #[test]
+    fn test_lemmas_and_switches() {
+        assert_eq!(terms("Children went home", &language("en")), vec!["child", "go", "home"]);
+        let options = AnalyzerOptions { stem: false, remove_stopwords: false, lemmatize: false, ..language("en") };
+        assert_eq!(terms("The Mice ran", &options), vec!["the", "mice", "ran"]);
+        let options = AnalyzerOptions { lowercase: false, stem: false, ..language("en") };
+        assert_eq!(terms("The Mice ran", &options), vec!["Mice", "run"]);
+    }
+
+    #[test]
+    fn test_positions_refer_to_original_words() {
+        let text = "🎉 Die Verträge wurden gekündigt.";
+        let found = Analyzer::for_text(text, &AnalyzerOptions::default()).analyze(text);
+        let utf16: Vec<u16> = text.encode_utf16().collect();
+        assert_eq!(found.iter().map(|t| t.text.as_str()).collect::<Vec<_>>(), vec!["Verträge", "wurden", "gekündigt"]);
+        for term in &found {
+            assert_eq!(String::from_utf16(&utf16[term.position.start..term.position.end]).unwrap(), term.text);
+        }
+        assert_eq!(found[0].term, "vertrag");
+    }
+}
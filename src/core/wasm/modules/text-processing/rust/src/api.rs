@@ -0,0 +1,676 @@
+use serde::de::DeserializeOwned;
+use serde::{Serialize, Deserialize};
+use serde_wasm_bindgen::Serializer;
+use unicode_segmentation::UnicodeSegmentation;
+use wasm_bindgen::prelude::*;
+
+use crate::analysis::{Analyzer, AnalyzerOptions};
+use crate::classify::{Classifier, ClassifierOptions, TrainingExample};
+use crate::diff::{self, DiffOptions};
+use crate::encoding;
+use crate::extraction::{self, ExtractionOptions};
+use crate::fingerprint::{self, DuplicateIndex, DuplicateIndexOptions, Fingerprint, FingerprintOptions};
+use crate::fuzzy::{self, FuzzyOptions};
+use crate::gazetteer::{Dictionary, Gazetteer, GazetteerOptions};
+use crate::hnsw::{VectorIndex, VectorIndexOptions, VectorSearchOptions};
+use crate::html::{self, HtmlOptions, HtmlText};
+use crate::normalize::{self, NormalizeOptions, OffsetMap};
+use crate::keywords::{self, CorpusStats, KeywordMethod, KeywordOptions};
+use crate::readability::{self, ReadabilityOptions};
+use crate::redaction::{self, RedactionMapping, RedactionPolicy};
+use crate::search::{SearchIndex, SearchOptions};
+use crate::sentences::{self, SentenceOptions};
+use crate::sentiment::{self, SentimentOptions, SentimentScore};
+use crate::summarize::{self, LengthUnit, SummaryOptions};
+use crate::temporal::{self, TemporalOptions};
+use crate::tokenizer::{Tokenizer, TokenizerFormat};
+use crate::{ChunkMetadata, ProcessingConfig, TextChunk, TextProcessingError, TextProcessor};
+
+/// The TS `TextProcessingResult`.
+#[derive(Serialize, Deserialize)]
+pub struct TextProcessingResult {
+    chunks: Vec<IdentifiedChunk>,
+    stats: ProcessingStats,
+    metadata: DocumentMetadata,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    html: Option<HtmlText>, // when the options asked for HTML conversion
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    normalization: Option<OffsetMap>, // when the options asked for normalization
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    redaction: Option<RedactionMapping>, // when the options asked for redaction
+}
+
+/// Chunks of the text as prepared by the options, with what it takes to
+/// map them back to the input.
+struct PreparedChunks {
+    chunks: Vec<TextChunk>,
+    html: Option<HtmlText>,
+    normalization: Option<OffsetMap>,
+    redaction: Option<RedactionMapping>,
+    sentiment: Option<SentimentScore>,
+}
+
+/// The TS `TextChunk`, which carries an id next to the chunk fields.
+#[derive(Serialize, Deserialize)]
+pub struct IdentifiedChunk {
+    pub(crate) id: String,
+    #[serde(flatten)]
+    pub(crate) chunk: TextChunk,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessingStats {
+    input_length: usize,      // UTF-16 code units, like `String.length`
+    chunk_count: usize,
+    average_chunk_size: f64,  // UTF-16 code units
+    processing_time: f64,     // milliseconds
+    memory_used: usize,       // bytes of linear memory
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DocumentMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    language: Option<String>,
+    encoding: String,
+    confidence: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sentiment: Option<SentimentScore>, // when the options asked for sentiment
+}
+
+impl From<TextChunk> for IdentifiedChunk {
+    fn from(chunk: TextChunk) -> Self {
+        // Chunks of one text never share both offsets
+        IdentifiedChunk {
+            id: format!("chunk-{}-{}", chunk.start, chunk.end),
+            chunk,
+        }
+    }
+}
+
+/// Typed API: text and options cross the boundary as JS values instead of
+/// offsets into `memory`, and results come back as plain objects.
+#[wasm_bindgen]
+impl TextProcessor {
+    /// Chunks `text` and returns a `TextProcessingResult`. `options` has the
+    /// shape of `TextProcessingConfig['options']` and may be omitted.
+    pub fn process(&self, text: &str, options: JsValue) -> Result<JsValue, JsValue> {
+        let started = now();
+        let config = read_options(options)?;
+        let result = self.process_impl(text, &config, started).map_err(to_js_error)?;
+        to_js_value(&result)
+    }
+
+    /// Decodes `data` as `options.encoding`, detected when unset or "auto",
+    /// then works like `process`; chunk offsets refer to the decoded text and
+    /// `metadata.encoding` names the encoding used.
+    #[wasm_bindgen(js_name = processBytes)]
+    pub fn process_bytes(&self, data: &[u8], options: JsValue) -> Result<JsValue, JsValue> {
+        let started = now();
+        let config: ProcessingConfig = read_options(options)?;
+        let decoded = encoding::decode(data, config.encoding.as_deref()).map_err(to_js_error)?;
+        let mut result = self.process_impl(&decoded.text, &config, started).map_err(to_js_error)?;
+        result.metadata.encoding = decoded.encoding;
+        to_js_value(&result)
+    }
+
+    /// Decodes `data` as the encoding labelled `encoding`, or the detected
+    /// one, and returns a `DecodedText`.
+    pub fn decode(&self, data: &[u8], encoding: Option<String>) -> Result<JsValue, JsValue> {
+        let decoded = encoding::decode(data, encoding.as_deref()).map_err(to_js_error)?;
+        to_js_value(&decoded)
+    }
+
+    /// Chunks `text` and returns a `TextChunk[]`. With `options.html`,
+    /// `options.normalization` or `options.redaction` the chunks are of the
+    /// converted, normalized or redacted text; use `process` to get the mappings.
+    pub fn chunk(&self, text: &str, options: JsValue) -> Result<JsValue, JsValue> {
+        let config = read_options(options)?;
+        let prepared = self.prepared_chunks(text, &config).map_err(to_js_error)?;
+        to_js_value(&identify(prepared.chunks))
+    }
+
+    /// Starts a stream fed through `push`, replacing any stream in progress.
+    pub fn stream(&mut self, options: JsValue) -> Result<(), JsValue> {
+        let config = read_options(options)?;
+        self.begin_stream_impl(config).map_err(to_js_error)
+    }
+
+    /// Appends text to the stream and returns the `TextChunk[]` completed so far.
+    pub fn push(&mut self, text: &str) -> Result<JsValue, JsValue> {
+        let chunks = self.push_text_impl(text).map_err(to_js_error)?;
+        to_js_value(&identify(chunks))
+    }
+
+    /// Ends the stream and returns its remaining `TextChunk[]`.
+    pub fn finish(&mut self) -> Result<JsValue, JsValue> {
+        let chunks = self.finish_stream_impl().map_err(to_js_error)?;
+        to_js_value(&identify(chunks))
+    }
+
+    /// Loads a tokenizer; `format` is `'wordpiece'`, `'bpe'` or `'huggingface'`.
+    #[wasm_bindgen(js_name = useTokenizer)]
+    pub fn use_tokenizer(&mut self, data: &str, format: JsValue) -> Result<(), JsValue> {
+        let format: TokenizerFormat = serde_wasm_bindgen::from_value(format)
+            .map_err(|e| to_js_error(TextProcessingError::InvalidOptions(e.to_string())))?;
+        let tokenizer = Tokenizer::load(data, format)
+            .map_err(|e| to_js_error(TextProcessingError::from(e)))?;
+        self.tokenizer = Some(tokenizer);
+        Ok(())
+    }
+
+    /// Runs the normalization pipeline over `text` and returns `{ text, offsets }`,
+    /// the offsets mapping normalized positions back to `text`. `options` has
+    /// the shape of `NormalizationOptions` and may be omitted.
+    pub fn normalize(&self, text: &str, options: JsValue) -> Result<JsValue, JsValue> {
+        let options: NormalizeOptions = read_options(options)?;
+        let normalized = normalize::normalize(text, &options.stages, options.preserve_whitespace, options.preserve_newlines);
+        to_js_value(&normalized)
+    }
+
+    /// Splits `text` into sentences and returns a `Sentence[]` with UTF-16
+    /// offsets. `options` has the shape of `SentenceOptions` and may be omitted.
+    pub fn sentences(&self, text: &str, options: JsValue) -> Result<JsValue, JsValue> {
+        let options: SentenceOptions = read_options(options)?;
+        to_js_value(&sentences::sentences(text, &options))
+    }
+
+    /// Splits `text` into words and runs them through the lowercase, stopword,
+    /// lemma and stem filters; returns a `Term[]` with UTF-16 positions of the
+    /// original words. `options` has the shape of `AnalyzerOptions` and may be omitted.
+    pub fn analyze(&self, text: &str, options: JsValue) -> Result<JsValue, JsValue> {
+        let options: AnalyzerOptions = read_options(options)?;
+        to_js_value(&Analyzer::for_text(text, &options).analyze(text))
+    }
+
+    /// Counts words, sentences, paragraphs and syllables and scores the
+    /// readability of `text`; returns `TextStatistics`. `options` has the
+    /// shape of `ReadabilityOptions` and may be omitted.
+    pub fn statistics(&self, text: &str, options: JsValue) -> Result<JsValue, JsValue> {
+        let options: ReadabilityOptions = read_options(options)?;
+        to_js_value(&readability::statistics(text, &options))
+    }
+
+    /// Compares two versions of a text and returns a `Diff` listing the
+    /// changes with UTF-16 spans in both. `options` has the shape of
+    /// `DiffOptions` and may be omitted.
+    pub fn diff(&self, old: &str, new: &str, options: JsValue) -> Result<JsValue, JsValue> {
+        let options: DiffOptions = read_options(options)?;
+        to_js_value(&diff::diff(old, new, &options))
+    }
+
+    /// Converts an HTML document to Markdown-style text for the structural
+    /// chunker and returns an `HtmlText` mapping its blocks back to their
+    /// elements. `options` has the shape of `HtmlOptions` and may be omitted.
+    #[wasm_bindgen(js_name = htmlToText)]
+    pub fn html_to_text(&self, html: &str, options: JsValue) -> Result<JsValue, JsValue> {
+        let options: HtmlOptions = read_options(options)?;
+        to_js_value(&html::to_text(html, &options))
+    }
+
+    /// Scores the tone of `text` per sentence and as a whole with a
+    /// VADER-style lexicon and returns a `Sentiment`. `options` has the shape
+    /// of `SentimentOptions` and may be omitted.
+    pub fn sentiment(&self, text: &str, options: JsValue) -> Result<JsValue, JsValue> {
+        let options: SentimentOptions = read_options(options)?;
+        to_js_value(&sentiment::sentiment(text, &options))
+    }
+
+    /// Finds dates, times, ranges and durations in `text` and returns a
+    /// `TemporalExpression[]` with ISO 8601 values, resolving relative ones
+    /// against `options.reference` or the current time. `options` has the
+    /// shape of `TemporalOptions` and may be omitted.
+    #[wasm_bindgen(js_name = extractTemporal)]
+    pub fn extract_temporal(&self, text: &str, options: JsValue) -> Result<JsValue, JsValue> {
+        let options: TemporalOptions = read_options(options)?;
+        let found = temporal::extract(text, &options, now()).map_err(to_js_error)?;
+        to_js_value(&found)
+    }
+
+    /// Finds emails, URLs, phone numbers, money, percentages, IBANs, card
+    /// numbers, dates and gazetteer terms; returns an `Entity[]` with UTF-16
+    /// positions. `options` has the shape of `EntityExtractionOptions` and may be omitted.
+    #[wasm_bindgen(js_name = extractEntities)]
+    pub fn extract_entities(&self, text: &str, options: JsValue) -> Result<JsValue, JsValue> {
+        let options: ExtractionOptions = read_options(options)?;
+        to_js_value(&extraction::extract(text, &options, self.gazetteer.as_ref()))
+    }
+
+    /// Replaces the gazetteer with `GazetteerDictionary[]`, matched by
+    /// `extractEntities` from then on; an empty array removes it.
+    #[wasm_bindgen(js_name = useGazetteer)]
+    pub fn use_gazetteer(&mut self, dictionaries: JsValue, options: JsValue) -> Result<(), JsValue> {
+        let dictionaries: Vec<Dictionary> = serde_wasm_bindgen::from_value(dictionaries)
+            .map_err(|e| to_js_error(TextProcessingError::InvalidOptions(e.to_string())))?;
+        let options: GazetteerOptions = read_options(options)?;
+        self.gazetteer = if dictionaries.is_empty() {
+            None
+        } else {
+            Some(Gazetteer::new(dictionaries, options).map_err(|e| to_js_error(TextProcessingError::from(e)))?)
+        };
+        Ok(())
+    }
+
+    /// Redacts `text` and returns a `{ text, mapping }` record. `policy` has
+    /// the shape of `RedactionPolicy` and may be omitted.
+    pub fn redact(&self, text: &str, policy: JsValue) -> Result<JsValue, JsValue> {
+        let policy: RedactionPolicy = read_options(policy)?;
+        let redaction = redaction::redact(text, &policy, self.gazetteer.as_ref()).map_err(to_js_error)?;
+        to_js_value(&redaction)
+    }
+
+    /// Restores the originals in redacted text, or text derived from it,
+    /// given the `RedactionMapping` that `redact` returned.
+    pub fn restore(&self, text: &str, mapping: JsValue) -> Result<String, JsValue> {
+        let mapping: RedactionMapping = serde_wasm_bindgen::from_value(mapping)
+            .map_err(|e| to_js_error(TextProcessingError::InvalidOptions(e.to_string())))?;
+        redaction::restore(text, &mapping).map_err(to_js_error)
+    }
+
+    /// Ranks the key phrases of `text` and returns a `Keyword[]`. `options`
+    /// has the shape of `KeywordOptions`; TF-IDF needs corpus statistics.
+    #[wasm_bindgen(js_name = extractKeywords)]
+    pub fn extract_keywords(&self, text: &str, options: JsValue) -> Result<JsValue, JsValue> {
+        let options: KeywordOptions = read_options(options)?;
+        if options.method == KeywordMethod::TfIdf && self.corpus.is_none() {
+            return Err(to_js_error(TextProcessingError::CorpusMissing));
+        }
+        to_js_value(&keywords::extract(text, &options, self.corpus.as_ref()))
+    }
+
+    /// Loads `CorpusStats` saved from `corpusStats`; without them, starts an
+    /// empty corpus counting phrases of up to `maxWords` words.
+    #[wasm_bindgen(js_name = useCorpus)]
+    pub fn use_corpus(&mut self, stats: JsValue) -> Result<(), JsValue> {
+        self.corpus = Some(if stats.is_undefined() || stats.is_null() {
+            CorpusStats::new(KeywordOptions::default().max_words)
+        } else {
+            serde_wasm_bindgen::from_value(stats)
+                .map_err(|e| to_js_error(TextProcessingError::InvalidOptions(e.to_string())))?
+        });
+        Ok(())
+    }
+
+    /// Counts the phrases of `text` into the corpus statistics.
+    #[wasm_bindgen(js_name = addToCorpus)]
+    pub fn add_to_corpus(&mut self, text: &str) {
+        self.corpus
+            .get_or_insert_with(|| CorpusStats::new(KeywordOptions::default().max_words))
+            .add_document(text);
+    }
+
+    /// The corpus statistics, for the caller to store, or `null`.
+    #[wasm_bindgen(js_name = corpusStats)]
+    pub fn corpus_stats(&self) -> Result<JsValue, JsValue> {
+        to_js_value(&self.corpus)
+    }
+
+    /// Extracts the most representative sentences of `text` within a target
+    /// length and returns a `Summary`. `options` has the shape of `SummaryOptions`.
+    pub fn summarize(&self, text: &str, options: JsValue) -> Result<JsValue, JsValue> {
+        let options: SummaryOptions = read_options(options)?;
+        let converted = options.html.as_ref().map(|html| html::to_text(text, html));
+        let text = converted.as_ref().map_or(text, |c| c.text.as_str());
+        let summary = match (options.unit, self.tokenizer.as_ref()) {
+            (LengthUnit::Words, _) => summarize::summarize(text, &options, &|s| s.unicode_words().count()),
+            (LengthUnit::Tokens, Some(tokenizer)) => summarize::summarize(text, &options, &|s| tokenizer.count(s)),
+            (LengthUnit::Tokens, None) => return Err(to_js_error(TextProcessingError::TokenizerMissing)),
+        };
+        to_js_value(&summary)
+    }
+
+    /// Loads a search index saved with `saveIndex`, or starts an empty one.
+    #[wasm_bindgen(js_name = useIndex)]
+    pub fn use_index(&mut self, data: Option<Vec<u8>>) -> Result<(), JsValue> {
+        self.index = match data {
+            Some(data) => SearchIndex::from_bytes(&data).map_err(to_js_error)?,
+            None => SearchIndex::new(),
+        };
+        Ok(())
+    }
+
+    /// Adds a `TextChunk[]` to the search index; a chunk replaces any indexed
+    /// chunk with the same id.
+    #[wasm_bindgen(js_name = indexChunks)]
+    pub fn index_chunks(&mut self, chunks: JsValue) -> Result<(), JsValue> {
+        let chunks: Vec<IdentifiedChunk> = serde_wasm_bindgen::from_value(chunks)
+            .map_err(|e| to_js_error(TextProcessingError::InvalidOptions(e.to_string())))?;
+        for chunk in chunks {
+            self.index.add(chunk);
+        }
+        Ok(())
+    }
+
+    /// Removes the chunk with `id` from the search index; returns whether it was there.
+    #[wasm_bindgen(js_name = removeFromIndex)]
+    pub fn remove_from_index(&mut self, id: &str) -> bool {
+        self.index.remove(id)
+    }
+
+    /// Ranks the indexed chunks against `query` with BM25 and returns a
+    /// `SearchHit[]`. `options` has the shape of `SearchOptions`.
+    pub fn search(&self, query: &str, options: JsValue) -> Result<JsValue, JsValue> {
+        let options: SearchOptions = read_options(options)?;
+        to_js_value(&self.index.search(query, &options))
+    }
+
+    /// The search index as bytes, for the caller to store.
+    #[wasm_bindgen(js_name = saveIndex)]
+    pub fn save_index(&self) -> Vec<u8> {
+        self.index.to_bytes()
+    }
+
+    /// Edit distance between `a` and `b` in characters; `transpositions`
+    /// counts swapping two adjacent characters as one edit.
+    #[wasm_bindgen(js_name = editDistance)]
+    pub fn edit_distance(&self, a: &str, b: &str, transpositions: bool) -> usize {
+        fuzzy::distance(a, b, transpositions)
+    }
+
+    /// Indexed terms within a few edits of `term` as a `FuzzyTerm[]`, closest
+    /// and then most frequent first. `options` has the shape of `FuzzyOptions`.
+    #[wasm_bindgen(js_name = fuzzyTerms)]
+    pub fn fuzzy_terms(&self, term: &str, options: JsValue) -> Result<JsValue, JsValue> {
+        let options: FuzzyOptions = read_options(options)?;
+        to_js_value(&self.index.dictionary().search(term, &options))
+    }
+
+    /// Approximate occurrences of `pattern` in the indexed chunks as a
+    /// `FuzzyHit[]`. `options` has the shape of `FuzzyOptions`.
+    #[wasm_bindgen(js_name = fuzzySearch)]
+    pub fn fuzzy_search(&self, pattern: &str, options: JsValue) -> Result<JsValue, JsValue> {
+        let options: FuzzyOptions = read_options(options)?;
+        to_js_value(&fuzzy::search(&self.index, pattern, &options).map_err(to_js_error)?)
+    }
+
+    /// "Did you mean": `query` with its unknown words replaced by indexed
+    /// terms, as a `Suggestion`, or `null` when there is nothing to correct.
+    pub fn suggest(&self, query: &str, options: JsValue) -> Result<JsValue, JsValue> {
+        let options: FuzzyOptions = read_options(options)?;
+        to_js_value(&fuzzy::suggest(query, self.index.dictionary(), &options))
+    }
+
+    /// Replaces the vector index with an empty one. `options` has the shape
+    /// of `VectorIndexOptions` and may be omitted.
+    #[wasm_bindgen(js_name = useVectorIndex)]
+    pub fn use_vector_index(&mut self, options: JsValue) -> Result<(), JsValue> {
+        let options: VectorIndexOptions = read_options(options)?;
+        self.vectors = VectorIndex::new(options);
+        Ok(())
+    }
+
+    /// Loads a vector index saved with `saveVectorIndex`.
+    #[wasm_bindgen(js_name = loadVectorIndex)]
+    pub fn load_vector_index(&mut self, data: &[u8]) -> Result<(), JsValue> {
+        self.vectors = VectorIndex::from_bytes(data).map_err(to_js_error)?;
+        Ok(())
+    }
+
+    /// Adds the embedding of chunk `id`, replacing any it had. `metadata` is a
+    /// plain object that searches can filter on and may be omitted.
+    #[wasm_bindgen(js_name = addVector)]
+    pub fn add_vector(&mut self, id: &str, vector: Vec<f32>, metadata: JsValue) -> Result<(), JsValue> {
+        let metadata = read_options(metadata)?;
+        self.vectors.insert(id, vector, metadata).map_err(to_js_error)
+    }
+
+    /// Removes the embedding of chunk `id`; returns whether it was there.
+    #[wasm_bindgen(js_name = removeVector)]
+    pub fn remove_vector(&mut self, id: &str) -> bool {
+        self.vectors.remove(id)
+    }
+
+    /// Finds the chunks whose embeddings are nearest to `vector` and returns a
+    /// `VectorHit[]`. `options` has the shape of `VectorSearchOptions`.
+    #[wasm_bindgen(js_name = searchVectors)]
+    pub fn search_vectors(&self, vector: Vec<f32>, options: JsValue) -> Result<JsValue, JsValue> {
+        let options: VectorSearchOptions = read_options(options)?;
+        to_js_value(&self.vectors.search(&vector, &options).map_err(to_js_error)?)
+    }
+
+    /// The vector index as bytes, for the caller to store.
+    #[wasm_bindgen(js_name = saveVectorIndex)]
+    pub fn save_vector_index(&self) -> Vec<u8> {
+        self.vectors.to_bytes()
+    }
+
+    /// SimHash and MinHash fingerprints of `text`, to store with the
+    /// document. `options` has the shape of `FingerprintOptions`.
+    pub fn fingerprint(&self, text: &str, options: JsValue) -> Result<JsValue, JsValue> {
+        let options: FingerprintOptions = read_options(options)?;
+        to_js_value(&fingerprint::fingerprint(text, &options))
+    }
+
+    /// Number of differing bits between two `Fingerprint.simhash` values.
+    #[wasm_bindgen(js_name = simhashDistance)]
+    pub fn simhash_distance(&self, a: &str, b: &str) -> Result<u32, JsValue> {
+        fingerprint::hamming_distance(a, b)
+            .ok_or_else(|| to_js_error(TextProcessingError::InvalidOptions("SimHash must be 16 hex digits".to_string())))
+    }
+
+    /// Replaces the near-duplicate index with an empty one. `options` has the
+    /// shape of `DuplicateIndexOptions` and may be omitted.
+    #[wasm_bindgen(js_name = useDuplicateIndex)]
+    pub fn use_duplicate_index(&mut self, options: JsValue) -> Result<(), JsValue> {
+        let options: DuplicateIndexOptions = read_options(options)?;
+        self.duplicates = DuplicateIndex::new(&options);
+        Ok(())
+    }
+
+    /// Adds the `Fingerprint` of document `id`, replacing any it had.
+    #[wasm_bindgen(js_name = addFingerprint)]
+    pub fn add_fingerprint(&mut self, id: &str, fingerprint: JsValue) -> Result<(), JsValue> {
+        let fingerprint: Fingerprint = serde_wasm_bindgen::from_value(fingerprint)
+            .map_err(|e| to_js_error(TextProcessingError::InvalidOptions(e.to_string())))?;
+        self.duplicates.add(id, fingerprint).map_err(to_js_error)
+    }
+
+    /// Removes document `id` from the near-duplicate index; returns whether it was there.
+    #[wasm_bindgen(js_name = removeFingerprint)]
+    pub fn remove_fingerprint(&mut self, id: &str) -> bool {
+        self.duplicates.remove(id)
+    }
+
+    /// Indexed documents at least as similar to `fingerprint` as the index
+    /// threshold, as `{ id, similarity }[]`, most similar first.
+    #[wasm_bindgen(js_name = findDuplicates)]
+    pub fn find_duplicates(&self, fingerprint: JsValue) -> Result<JsValue, JsValue> {
+        let fingerprint: Fingerprint = serde_wasm_bindgen::from_value(fingerprint)
+            .map_err(|e| to_js_error(TextProcessingError::InvalidOptions(e.to_string())))?;
+        to_js_value(&self.duplicates.find(&fingerprint).map_err(to_js_error)?)
+    }
+
+    /// Trains a classifier on a `TrainingExample[]` and keeps it for
+    /// `classify`, replacing any other; returns a `TrainingReport`. `options`
+    /// has the shape of `ClassifierOptions` and may be omitted.
+    #[wasm_bindgen(js_name = trainClassifier)]
+    pub fn train_classifier(&mut self, examples: JsValue, options: JsValue) -> Result<JsValue, JsValue> {
+        let examples: Vec<TrainingExample> = serde_wasm_bindgen::from_value(examples)
+            .map_err(|e| to_js_error(TextProcessingError::InvalidOptions(e.to_string())))?;
+        let options: ClassifierOptions = read_options(options)?;
+        let (classifier, report) = Classifier::train(&examples, &options).map_err(to_js_error)?;
+        self.classifier = Some(classifier);
+        to_js_value(&report)
+    }
+
+    /// Loads a classifier saved with `saveClassifier`.
+    #[wasm_bindgen(js_name = loadClassifier)]
+    pub fn load_classifier(&mut self, data: &[u8]) -> Result<(), JsValue> {
+        self.classifier = Some(Classifier::from_bytes(data).map_err(to_js_error)?);
+        Ok(())
+    }
+
+    /// The classifier as bytes, for the caller to store.
+    #[wasm_bindgen(js_name = saveClassifier)]
+    pub fn save_classifier(&self) -> Result<Vec<u8>, JsValue> {
+        let classifier = self.classifier.as_ref().ok_or_else(|| to_js_error(TextProcessingError::ClassifierMissing))?;
+        Ok(classifier.to_bytes())
+    }
+
+    /// Labels `text` with the trained classifier and returns a
+    /// `Classification` with the probability of every label.
+    pub fn classify(&self, text: &str) -> Result<JsValue, JsValue> {
+        let classifier = self.classifier.as_ref().ok_or_else(|| to_js_error(TextProcessingError::ClassifierMissing))?;
+        to_js_value(&classifier.classify(text))
+    }
+
+    /// Chunks `text` after normalizing and then redacting it, if the config
+    /// asks for either, so that chunk offsets refer to the prepared text.
+    fn prepared_chunks(&self, text: &str, config: &ProcessingConfig) -> Result<PreparedChunks, TextProcessingError> {
+        let html = config.html.as_ref().map(|options| html::to_text(text, options));
+        let text = html.as_ref().map_or(text, |h| h.text.as_str());
+        let normalized = config.normalization.as_ref().map(|options| {
+            normalize::normalize(text, options, config.preserve_whitespace, config.preserve_newlines)
+        });
+        let text = normalized.as_ref().map_or(text, |n| n.text.as_str());
+        let redaction = match &config.redaction {
+            Some(policy) => Some(redaction::redact(text, policy, self.gazetteer.as_ref())?),
+            None => None,
+        };
+        let text = redaction.as_ref().map_or(text, |r| r.text.as_str());
+        let chunks = self.chunk_text_impl(text, config)?;
+        let sentiment = config.sentiment.as_ref().map(|options| sentiment::passage(text, options));
+        Ok(PreparedChunks {
+            chunks,
+            html,
+            normalization: normalized.map(|n| n.map),
+            redaction: redaction.map(|r| r.mapping),
+            sentiment,
+        })
+    }
+
+    fn process_impl(&self, text: &str, config: &ProcessingConfig, started: f64) -> Result<TextProcessingResult, TextProcessingError> {
+        let PreparedChunks { chunks, html, normalization, redaction, sentiment } = self.prepared_chunks(text, config)?;
+        let metadata = Self::document_metadata(&chunks);
+
+        let chunk_length: usize = chunks.iter().map(|c| c.text.encode_utf16().count()).sum();
+        let average_chunk_size = if chunks.is_empty() {
+            0.0
+        } else {
+            chunk_length as f64 / chunks.len() as f64
+        };
+
+        Ok(TextProcessingResult {
+            stats: ProcessingStats {
+                input_length: text.encode_utf16().count(),
+                chunk_count: chunks.len(),
+                average_chunk_size,
+                processing_time: now() - started,
+                memory_used: memory_used(),
+            },
+            metadata: document_metadata(metadata, sentiment),
+            chunks: identify(chunks),
+            html,
+            normalization,
+            redaction,
+        })
+    }
+}
+
+fn identify(chunks: Vec<TextChunk>) -> Vec<IdentifiedChunk> {
+    chunks.into_iter().map(IdentifiedChunk::from).collect()
+}
+
+fn document_metadata(metadata: Option<ChunkMetadata>, sentiment: Option<SentimentScore>) -> DocumentMetadata {
+    DocumentMetadata {
+        confidence: metadata.as_ref().map_or(0.0, |m| m.confidence),
+        language: metadata.and_then(|m| m.language),
+        encoding: "utf-8".to_string(),
+        sentiment,
+    }
+}
+
+fn read_options<T: DeserializeOwned + Default>(options: JsValue) -> Result<T, JsValue> {
+    if options.is_undefined() || options.is_null() {
+        return Ok(T::default());
+    }
+    serde_wasm_bindgen::from_value(options)
+        .map_err(|e| to_js_error(TextProcessingError::InvalidOptions(e.to_string())))
+}
+
+fn to_js_value<T: Serialize>(value: &T) -> Result<JsValue, JsValue> {
+    // Plain objects rather than `Map`s for flattened structs
+    Ok(value.serialize(&Serializer::json_compatible())?)
+}
+
+fn to_js_error(error: TextProcessingError) -> JsValue {
+    JsValue::from_str(&error.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn now() -> f64 {
+    js_sys::Date::now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now() -> f64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0.0, |d| d.as_secs_f64() * 1000.0)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn memory_used() -> usize {
+    core::arch::wasm32::memory_size(0) * 64 * 1024
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn memory_used() -> usize {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_result_matches_ts_shape() {
+        let processor = TextProcessor::new();
+        let config: ProcessingConfig = serde_json::from_str(r#"{"chunkSize": 30, "overlap": 0, "language": "en"}"#).unwrap();
+        let text = "Der schnelle braune Fuchs springt. Über den faulen Hund läuft er.";
+        let result = processor.process_impl(text, &config, now()).unwrap();
+
+        let json = serde_json::to_value(&result).unwrap();
+        let chunks = json["chunks"].as_array().unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0]["id"], "chunk-0-30");
+        assert_eq!(chunks[0]["text"], "Der schnelle braune Fuchs spri");
+        assert!(chunks[0].get("tokenCount").is_none());
+        assert!(chunks[0]["metadata"].get("confidence").is_some());
+
+        let stats = &json["stats"];
+        assert_eq!(stats["inputLength"], 65);
+        assert_eq!(stats["chunkCount"], 3);
+        assert!(stats["averageChunkSize"].as_f64().unwrap() > 0.0);
+        assert!(stats["processingTime"].as_f64().unwrap() >= 0.0);
+        assert!(stats.get("memoryUsed").is_some());
+        assert_eq!(json["metadata"]["language"], "de");
+        assert_eq!(json["metadata"]["encoding"], "utf-8");
+    }
+
+    #[test]
+    fn test_process_redacts_before_chunking() {
+        let processor = TextProcessor::new();
+        let config: ProcessingConfig = serde_json::from_str(
+            r#"{"chunkSize": 100, "redaction": {"mode": "placeholder", "entityTypes": ["email"]}}"#,
+        ).unwrap();
+        let text = "Please forward the signed contract to jane.doe@example.com today.";
+        let result = processor.process_impl(text, &config, now()).unwrap();
+
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["chunks"][0]["text"], "Please forward the signed contract to [EMAIL_1] today.");
+        assert_eq!(json["chunks"][0]["end"], 54);
+        assert_eq!(json["redaction"]["replacements"][0]["original"], "jane.doe@example.com");
+    }
+
+    #[test]
+    fn test_options_use_defaults() {
+        let config: ProcessingConfig = serde_json::from_str(r#"{"strategy": "structural", "sizeUnit": "tokens"}"#).unwrap();
+        assert_eq!(config.chunk_size, 1024);
+        assert!(config.preserve_newlines);
+        assert!(config.strategy == crate::ChunkStrategy::Structural);
+        assert!(config.size_unit == crate::SizeUnit::Tokens);
+
+        let format: TokenizerFormat = serde_json::from_str(r#""bpe""#).unwrap();
+        assert_eq!(format, TokenizerFormat::BpeMerges);
+    }
+}
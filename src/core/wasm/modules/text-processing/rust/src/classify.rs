@@ -0,0 +1,552 @@
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::semantic::fnv1a;
+use crate::TextProcessingError;
+
+/// First byte of a saved model, bumped whenever its layout changes.
+const FORMAT_VERSION: u8 = 1;
+/// Starting state of the example shuffle, so training is reproducible.
+const SHUFFLE_SEED: u64 = 0x2545_F491_4F6C_DD1D;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum Algorithm {
+    /// Multinomial Naive Bayes over n-gram counts.
+    #[default]
+    NaiveBayes,
+    /// Softmax regression over normalized, log-scaled n-gram counts.
+    LogisticRegression,
+}
+
+/// Options in the shape of the TS `ClassifierOptions`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ClassifierOptions {
+    pub algorithm: Algorithm,
+    /// Hash buckets the n-grams fall into, rounded up to a power of two.
+    pub features: usize,
+    /// Longest word n-gram; 1 for single words.
+    pub word_ngrams: usize,
+    /// Length of the character n-grams taken inside words; 0 for none.
+    pub char_ngrams: usize,
+    /// Naive Bayes additive smoothing.
+    pub smoothing: f64,
+    /// Logistic regression passes over the examples.
+    pub epochs: usize,
+    pub learning_rate: f64,
+    /// Logistic regression L2 penalty.
+    pub regularization: f64,
+    /// Share of each label's examples held out to calibrate the
+    /// probabilities; 0 leaves them uncalibrated.
+    pub calibration_split: f64,
+}
+
+impl Default for ClassifierOptions {
+    fn default() -> Self {
+        ClassifierOptions {
+            algorithm: Algorithm::NaiveBayes,
+            features: 1 << 18,
+            word_ngrams: 2,
+            char_ngrams: 0,
+            smoothing: 1.0,
+            epochs: 10,
+            learning_rate: 0.5,
+            regularization: 1e-5,
+            calibration_split: 0.2,
+        }
+    }
+}
+
+/// A labelled training document in the shape of the TS `TrainingExample`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TrainingExample {
+    pub text: String,
+    pub label: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LabelCount {
+    pub label: String,
+    pub examples: usize,
+}
+
+/// What training saw, in the shape of the TS `TrainingReport`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TrainingReport {
+    pub labels: Vec<LabelCount>,
+    /// Divides the scores before the softmax; above 1 softens them.
+    pub temperature: f64,
+    /// Accuracy on the held-out examples; absent when none were held out.
+    pub validation_accuracy: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LabelProbability {
+    pub label: String,
+    pub probability: f64,
+}
+
+/// The TS `Classification`: the likeliest label and every label's
+/// probability, most likely first.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Classification {
+    pub label: String,
+    pub probability: f64,
+    pub probabilities: Vec<LabelProbability>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+enum Model {
+    NaiveBayes {
+        log_priors: Vec<f64>,
+        /// Bucket -> count per label, for the buckets seen in training.
+        counts: HashMap<u32, Vec<f32>>,
+        totals: Vec<f64>,
+    },
+    LogisticRegression {
+        bias: Vec<f64>,
+        /// Bucket -> weight per label, for the buckets seen in training.
+        weights: HashMap<u32, Vec<f32>>,
+    },
+}
+
+/// A trained text classifier. It only holds hashed n-grams, never the
+/// training text.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Classifier {
+    options: ClassifierOptions,
+    labels: Vec<String>,
+    temperature: f64,
+    model: Model,
+}
+
+/// Sparse features: (bucket, value), sorted by bucket.
+type Features = Vec<(u32, f64)>;
+
+impl Classifier {
+    /// Trains on `examples`, first on all but a held-out share of each label
+    /// to fit the calibration temperature, then on all of them.
+    pub fn train(examples: &[TrainingExample], options: &ClassifierOptions) -> Result<(Classifier, TrainingReport), TextProcessingError> {
+        check(options)?;
+        let mut labels: Vec<String> = examples.iter().map(|e| e.label.clone()).collect();
+        labels.sort();
+        labels.dedup();
+        if labels.len() < 2 {
+            return Err(TextProcessingError::InvalidOptions("training needs examples of at least two labels".to_string()));
+        }
+        let samples: Vec<(Features, usize)> = examples
+            .iter()
+            .map(|e| (features(&e.text, options), labels.binary_search(&e.label).unwrap()))
+            .collect();
+
+        let mut order: Vec<usize> = (0..samples.len()).collect();
+        shuffle(&mut order, &mut { SHUFFLE_SEED });
+        let mut held_out = vec![false; samples.len()];
+        for label in 0..labels.len() {
+            let of_label: Vec<usize> = order.iter().copied().filter(|&i| samples[i].1 == label).collect();
+            if of_label.len() >= 2 && options.calibration_split > 0.0 {
+                let share = (of_label.len() as f64 * options.calibration_split).round() as usize;
+                for &i in &of_label[..share.clamp(1, of_label.len() - 1)] {
+                    held_out[i] = true;
+                }
+            }
+        }
+
+        let (mut temperature, mut validation_accuracy) = (1.0, None);
+        if held_out.contains(&true) {
+            let training: Vec<(Features, usize)> = samples.iter().zip(&held_out).filter(|(_, &h)| !h).map(|(s, _)| s.clone()).collect();
+            let model = fit(&training, labels.len(), options);
+            let scored: Vec<(Vec<f64>, usize)> = samples
+                .iter()
+                .zip(&held_out)
+                .filter(|(_, &h)| h)
+                .map(|((x, y), _)| (model.scores(x, options), *y))
+                .collect();
+            let correct = scored.iter().filter(|(scores, y)| argmax(scores) == *y).count();
+            validation_accuracy = Some(correct as f64 / scored.len() as f64);
+            temperature = calibrate(&scored);
+        }
+
+        let counts = labels
+            .iter()
+            .enumerate()
+            .map(|(i, label)| LabelCount { label: label.clone(), examples: samples.iter().filter(|s| s.1 == i).count() })
+            .collect();
+        let classifier = Classifier {
+            options: options.clone(),
+            model: fit(&samples, labels.len(), options),
+            labels,
+            temperature,
+        };
+        Ok((classifier, TrainingReport { labels: counts, temperature, validation_accuracy }))
+    }
+
+    pub fn classify(&self, text: &str) -> Classification {
+        let scores = self.model.scores(&features(text, &self.options), &self.options);
+        let probabilities = softmax(&scores.iter().map(|s| s / self.temperature).collect::<Vec<_>>());
+        let mut probabilities: Vec<LabelProbability> = self
+            .labels
+            .iter()
+            .zip(probabilities)
+            .map(|(label, probability)| LabelProbability { label: label.clone(), probability })
+            .collect();
+        probabilities.sort_by(|a, b| b.probability.total_cmp(&a.probability));
+        Classification { label: probabilities[0].label.clone(), probability: probabilities[0].probability, probabilities }
+    }
+
+    /// Reads a model written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TextProcessingError> {
+        let Some((&FORMAT_VERSION, body)) = bytes.split_first() else {
+            return Err(TextProcessingError::InvalidModel("unknown format version".to_string()));
+        };
+        let classifier: Classifier =
+            rmp_serde::from_slice(body).map_err(|e| TextProcessingError::InvalidModel(e.to_string()))?;
+        check(&classifier.options).map_err(|e| TextProcessingError::InvalidModel(e.to_string()))?;
+        classifier.validate()?;
+        Ok(classifier)
+    }
+
+    /// Checks that every per-label vector has one entry per label, so that
+    /// `classify` cannot index out of bounds.
+    fn validate(&self) -> Result<(), TextProcessingError> {
+        let labels = self.labels.len();
+        let sized = match &self.model {
+            Model::NaiveBayes { log_priors, counts, totals } => {
+                log_priors.len() == labels && totals.len() == labels && counts.values().all(|c| c.len() == labels)
+            }
+            Model::LogisticRegression { bias, weights } => {
+                bias.len() == labels && weights.values().all(|w| w.len() == labels)
+            }
+        };
+        let problem = if labels < 2 {
+            "a classifier needs at least two labels"
+        } else if !sized {
+            "model weights do not match the labels"
+        } else if !(self.temperature.is_finite() && self.temperature > 0.0) {
+            "temperature must be positive"
+        } else {
+            return Ok(());
+        };
+        Err(TextProcessingError::InvalidModel(problem.to_string()))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![FORMAT_VERSION];
+        rmp_serde::encode::write_named(&mut bytes, self).expect("writing to a Vec cannot fail");
+        bytes
+    }
+}
+
+impl Model {
+    /// Unnormalized log-probability of each label.
+    fn scores(&self, x: &Features, options: &ClassifierOptions) -> Vec<f64> {
+        match self {
+            Model::NaiveBayes { log_priors, counts, totals } => {
+                let vocabulary = counts.len() as f64;
+                let mut scores = log_priors.clone();
+                // Buckets unseen in training say nothing about any label
+                for (bucket, n) in x {
+                    if let Some(per_label) = counts.get(bucket) {
+                        for ((score, &count), total) in scores.iter_mut().zip(per_label).zip(totals) {
+                            *score += n * ((f64::from(count) + options.smoothing) / (total + options.smoothing * vocabulary)).ln();
+                        }
+                    }
+                }
+                scores
+            }
+            Model::LogisticRegression { bias, weights } => {
+                let mut scores = bias.clone();
+                for (bucket, value) in normalized(x) {
+                    if let Some(per_label) = weights.get(&bucket) {
+                        for (score, &weight) in scores.iter_mut().zip(per_label) {
+                            *score += value * f64::from(weight);
+                        }
+                    }
+                }
+                scores
+            }
+        }
+    }
+}
+
+fn check(options: &ClassifierOptions) -> Result<(), TextProcessingError> {
+    let problem = if options.features < 2 || options.features > 1 << 24 {
+        "features must be between 2 and 2^24"
+    } else if options.word_ngrams == 0 && options.char_ngrams == 0 {
+        "wordNgrams and charNgrams cannot both be 0"
+    } else if !(options.smoothing > 0.0 && options.smoothing.is_finite()) {
+        "smoothing must be positive and finite"
+    } else if options.learning_rate <= 0.0 || !(0.0..1.0).contains(&(options.learning_rate * options.regularization)) {
+        "learningRate must be positive and learningRate × regularization below 1"
+    } else if !(0.0..1.0).contains(&options.calibration_split) {
+        "calibrationSplit must be at least 0 and below 1"
+    } else {
+        return Ok(());
+    };
+    Err(TextProcessingError::InvalidOptions(problem.to_string()))
+}
+
+/// Counts of the hashed word n-grams and in-word character n-grams of
+/// `text`. Words are NFKC-normalized and lowercased.
+fn features(text: &str, options: &ClassifierOptions) -> Features {
+    let mask = options.features.next_power_of_two() as u64 - 1;
+    let words: Vec<String> = text.unicode_words().map(|w| w.nfkc().flat_map(char::to_lowercase).collect()).collect();
+    let mut counts: HashMap<u32, f64> = HashMap::new();
+    let mut add = |gram: &str| *counts.entry((fnv1a(gram.as_bytes()) & mask) as u32).or_insert(0.0) += 1.0;
+    for n in 1..=options.word_ngrams.min(words.len()) {
+        for window in words.windows(n) {
+            add(&window.join(" "));
+        }
+    }
+    if options.char_ngrams > 0 {
+        for word in &words {
+            // Marked so "ing" inside a word never shares a hash with the word "ing"
+            let padded: Vec<char> = format!("\u{1}<{word}>").chars().collect();
+            for window in padded[1..].windows(options.char_ngrams) {
+                add(&std::iter::once('\u{1}').chain(window.iter().copied()).collect::<String>());
+            }
+        }
+    }
+    let mut features: Features = counts.into_iter().collect();
+    features.sort_by_key(|&(bucket, _)| bucket);
+    features
+}
+
+/// Log-scaled counts with unit length.
+fn normalized(x: &Features) -> Features {
+    let scaled: Features = x.iter().map(|&(bucket, n)| (bucket, 1.0 + n.ln())).collect();
+    let norm = scaled.iter().map(|(_, v)| v * v).sum::<f64>().sqrt();
+    if norm == 0.0 {
+        return scaled;
+    }
+    scaled.into_iter().map(|(bucket, v)| (bucket, v / norm)).collect()
+}
+
+fn fit(samples: &[(Features, usize)], labels: usize, options: &ClassifierOptions) -> Model {
+    match options.algorithm {
+        Algorithm::NaiveBayes => {
+            let mut counts: HashMap<u32, Vec<f32>> = HashMap::new();
+            let mut totals = vec![0.0; labels];
+            let mut documents = vec![0.0; labels];
+            for (x, y) in samples {
+                documents[*y] += 1.0;
+                for &(bucket, n) in x {
+                    counts.entry(bucket).or_insert_with(|| vec![0.0; labels])[*y] += n as f32;
+                    totals[*y] += n;
+                }
+            }
+            let log_priors = documents.iter().map(|d: &f64| (d / samples.len() as f64).ln()).collect();
+            Model::NaiveBayes { log_priors, counts, totals }
+        }
+        Algorithm::LogisticRegression => logistic_regression(samples, labels, options),
+    }
+}
+
+/// Softmax regression trained with SGD. The L2 penalty shrinks all weights
+/// through a shared scale, so each step only touches the example's buckets.
+fn logistic_regression(samples: &[(Features, usize)], labels: usize, options: &ClassifierOptions) -> Model {
+    let samples: Vec<(Features, usize)> = samples.iter().map(|(x, y)| (normalized(x), *y)).collect();
+    let mut slots: HashMap<u32, usize> = HashMap::new();
+    for (x, _) in &samples {
+        for &(bucket, _) in x {
+            let next = slots.len();
+            slots.entry(bucket).or_insert(next);
+        }
+    }
+    let mut weights = vec![0.0f64; slots.len() * labels];
+    let mut bias = vec![0.0; labels];
+    let mut scale = 1.0;
+    let mut order: Vec<usize> = (0..samples.len()).collect();
+    let mut seed = SHUFFLE_SEED;
+
+    for epoch in 0..options.epochs {
+        shuffle(&mut order, &mut seed);
+        let rate = options.learning_rate / (1.0 + epoch as f64).sqrt();
+        for &i in &order {
+            let (x, y) = &samples[i];
+            let mut logits = bias.clone();
+            for &(bucket, value) in x {
+                let slot = slots[&bucket] * labels;
+                for (logit, weight) in logits.iter_mut().zip(&weights[slot..slot + labels]) {
+                    *logit += scale * weight * value;
+                }
+            }
+            let probabilities = softmax(&logits);
+            scale *= 1.0 - rate * options.regularization;
+            for (label, probability) in probabilities.into_iter().enumerate() {
+                let gradient = probability - if label == *y { 1.0 } else { 0.0 };
+                bias[label] -= rate * gradient;
+                for &(bucket, value) in x {
+                    weights[slots[&bucket] * labels + label] -= rate * gradient * value / scale;
+                }
+            }
+            if scale < 1e-9 {
+                weights.iter_mut().for_each(|w| *w *= scale);
+                scale = 1.0;
+            }
+        }
+    }
+
+    let weights = slots
+        .into_iter()
+        .map(|(bucket, slot)| (bucket, weights[slot * labels..(slot + 1) * labels].iter().map(|w| (w * scale) as f32).collect()))
+        .collect();
+    Model::LogisticRegression { bias, weights }
+}
+
+/// Temperature minimizing the log loss of the held-out `scored` examples,
+/// found by golden-section search over its logarithm. As in Platt scaling,
+/// the targets are smoothed by the number of examples, so a small cleanly
+/// separated hold-out does not make every prediction certain.
+fn calibrate(scored: &[(Vec<f64>, usize)]) -> f64 {
+    let miss = 1.0 / (scored.len() as f64 + 2.0);
+    let loss = |log_temperature: f64| -> f64 {
+        let temperature = log_temperature.exp();
+        let mut loss = 0.0;
+        for (scores, y) in scored {
+            let probabilities = softmax(&scores.iter().map(|s| s / temperature).collect::<Vec<_>>());
+            for (label, p) in probabilities.into_iter().enumerate() {
+                let target = if label == *y { 1.0 - miss } else { miss / (scores.len() - 1) as f64 };
+                loss -= target * p.max(1e-12).ln();
+            }
+        }
+        loss
+    };
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let (mut low, mut high) = (-5.0f64, 5.0f64);
+    for _ in 0..60 {
+        let a = high - ratio * (high - low);
+        let b = low + ratio * (high - low);
+        if loss(a) < loss(b) {
+            high = b;
+        } else {
+            low = a;
+        }
+    }
+    ((low + high) / 2.0).exp()
+}
+
+fn softmax(scores: &[f64]) -> Vec<f64> {
+    let max = scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let exps: Vec<f64> = scores.iter().map(|s| (s - max).exp()).collect();
+    let sum: f64 = exps.iter().sum();
+    exps.into_iter().map(|e| e / sum).collect()
+}
+
+fn argmax(scores: &[f64]) -> usize {
+    (0..scores.len()).max_by(|&a, &b| scores[a].total_cmp(&scores[b])).unwrap_or(0)
+}
+
+/// Fisher–Yates with an xorshift generator.
+fn shuffle(items: &mut [usize], seed: &mut u64) {
+    for i in (1..items.len()).rev() {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 7;
+        *seed ^= *seed << 17;
+        items.swap(i, (*seed % (i as u64 + 1)) as usize);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VOCABULARY: &[(&str, &[&str])] = &[
+        ("contract", &["agreement", "party", "parties", "clause", "term", "termination", "governing", "law", "obligations", "hereby"]),
+        ("invoice", &["invoice", "amount", "due", "payment", "total", "vat", "billing", "number", "paid", "balance"]),
+        ("resume", &["experience", "skills", "education", "university", "worked", "engineer", "references", "languages", "led", "degree"]),
+    ];
+    const FILLER: &[&str] = &["the", "of", "and", "a", "this", "for", "with", "on", "in", "by"];
+
+    /// Twelve documents per label mixing its words with shared filler.
+    fn examples() -> Vec<TrainingExample> {
+        let mut examples = Vec::new();
+        for (label, words) in VOCABULARY {
+            for i in 0..12 {
+                let text = (0..8)
+                    .map(|j| if j % 2 == 0 { words[(i * 3 + j * 7) % words.len()] } else { FILLER[(i + j) % FILLER.len()] })
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                examples.push(TrainingExample { text, label: label.to_string() });
+            }
+        }
+        examples
+    }
+
+    fn check_routing(classifier: &Classifier) {
+        let cases = [
+            ("Invoice number 4411: total amount due with VAT, payment by Friday.", "invoice"),
+            ("This agreement binds both parties; termination under clause 9 and governing law.", "contract"),
+            ("Software engineer with ten years of experience, a degree and strong skills.", "resume"),
+        ];
+        for (text, label) in cases {
+            let found = classifier.classify(text);
+            assert_eq!(found.label, label, "{text}");
+            assert_eq!(found.probabilities.len(), 3);
+            assert!((found.probabilities.iter().map(|p| p.probability).sum::<f64>() - 1.0).abs() < 1e-9);
+            assert!(found.probabilities.windows(2).all(|w| w[0].probability >= w[1].probability));
+        }
+    }
+
+    #[test]
+    fn test_naive_bayes_routes_documents() {
+        let (classifier, report) = Classifier::train(&examples(), &ClassifierOptions::default()).unwrap();
+        assert_eq!(report.labels.iter().map(|l| (l.label.as_str(), l.examples)).collect::<Vec<_>>(), vec![("contract", 12), ("invoice", 12), ("resume", 12)]);
+        assert_eq!(report.validation_accuracy, Some(1.0));
+        assert!(report.temperature > 0.0);
+        check_routing(&classifier);
+
+        let uncalibrated = ClassifierOptions { calibration_split: 0.0, char_ngrams: 4, ..Default::default() };
+        let (classifier, report) = Classifier::train(&examples(), &uncalibrated).unwrap();
+        assert_eq!((report.temperature, report.validation_accuracy), (1.0, None));
+        check_routing(&classifier);
+    }
+
+    #[test]
+    fn test_logistic_regression_round_trips() {
+        let options = ClassifierOptions { algorithm: Algorithm::LogisticRegression, ..Default::default() };
+        let (classifier, _) = Classifier::train(&examples(), &options).unwrap();
+        check_routing(&classifier);
+
+        let bytes = classifier.to_bytes();
+        let restored = Classifier::from_bytes(&bytes).unwrap();
+        let text = "Payment of the outstanding balance is due.";
+        assert_eq!(restored.classify(text), classifier.classify(text));
+        assert!(Classifier::from_bytes(&bytes[..bytes.len() / 2]).is_err());
+        assert!(Classifier::from_bytes(&[]).is_err());
+
+        let corrupt = |damage: fn(&mut Classifier)| {
+            let mut damaged = classifier.clone();
+            damage(&mut damaged);
+            Classifier::from_bytes(&damaged.to_bytes()).is_err()
+        };
+        assert!(!corrupt(|_| {}));
+        assert!(corrupt(|c| c.labels.clear()));
+        assert!(corrupt(|c| c.labels.truncate(2)));
+        assert!(corrupt(|c| {
+            if let Model::LogisticRegression { weights, .. } = &mut c.model {
+                weights.values_mut().next().unwrap().pop();
+            }
+        }));
+        assert!(corrupt(|c| c.temperature = 0.0));
+        assert!(corrupt(|c| c.options.features = 0));
+    }
+
+    #[test]
+    fn test_training_rejects_bad_input() {
+        let one_label = vec![TrainingExample { text: "total due".to_string(), label: "invoice".to_string() }];
+        assert!(Classifier::train(&one_label, &ClassifierOptions::default()).is_err());
+        let no_features = ClassifierOptions { word_ngrams: 0, ..Default::default() };
+        assert!(Classifier::train(&examples(), &no_features).is_err());
+        let bad_split = ClassifierOptions { calibration_split: 1.0, ..Default::default() };
+        assert!(Classifier::train(&examples(), &bad_split).is_err());
+        for smoothing in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let bad_smoothing = ClassifierOptions { smoothing, ..Default::default() };
+            assert!(Classifier::train(&examples(), &bad_smoothing).is_err(), "{}", smoothing);
+        }
+    }
+}
@@ -0,0 +1,465 @@
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::extraction::{Position, Utf16Offsets};
+use crate::semantic::fnv1a;
+use crate::sentences::Segmenter;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffGranularity {
+    /// Words and punctuation; whitespace changes are ignored.
+    #[default]
+    Word,
+    /// Sentences, compared with their whitespace collapsed.
+    Sentence,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffAlgorithm {
+    /// Shortest edit script.
+    #[default]
+    Myers,
+    /// Anchors on units occurring once in each text, which keeps
+    /// reorganized documents readable; Myers between the anchors.
+    Patience,
+}
+
+/// Options in the shape of the TS `DiffOptions`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DiffOptions {
+    pub granularity: DiffGranularity,
+    pub algorithm: DiffAlgorithm,
+    /// Reports a deleted block inserted unchanged elsewhere as one move.
+    pub detect_moves: bool,
+    /// Shortest block, in words, reported as a move.
+    pub min_move_words: usize,
+    /// ISO 639-1 code choosing the sentence boundary rules; detected when unset.
+    pub language: Option<String>,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        DiffOptions {
+            granularity: DiffGranularity::Word,
+            algorithm: DiffAlgorithm::Myers,
+            detect_moves: false,
+            min_move_words: 5,
+            language: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Insert,
+    Delete,
+    Replace,
+    Move,
+}
+
+/// A change with its UTF-16 span in each text. The span of an insertion in
+/// the old text, and of a deletion in the new one, is empty and marks where
+/// the change happened.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub old: Position,
+    pub new: Position,
+    pub old_text: String,
+    pub new_text: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Diff {
+    /// Share of units left in place or moved, from 0.0 to 1.0.
+    pub similarity: f64,
+    /// Changes in the order of the old text.
+    pub changes: Vec<Change>,
+}
+
+/// A word or sentence: its byte span and the hash it is compared by.
+struct Unit {
+    start: usize,
+    end: usize,
+    key: u64,
+}
+
+pub fn diff(old: &str, new: &str, options: &DiffOptions) -> Diff {
+    let (a, b) = match options.granularity {
+        DiffGranularity::Word => (words(old), words(new)),
+        DiffGranularity::Sentence => {
+            let segmenter = Segmenter::for_text(old, options.language.as_deref());
+            (sentences(old, &segmenter), sentences(new, &segmenter))
+        }
+    };
+    let (a_keys, b_keys): (Vec<u64>, Vec<u64>) = (a.iter().map(|u| u.key).collect(), b.iter().map(|u| u.key).collect());
+    let mut matches = Vec::new();
+    match options.algorithm {
+        DiffAlgorithm::Myers => myers(&a_keys, &b_keys, 0, 0, &mut matches),
+        DiffAlgorithm::Patience => patience(&a_keys, &b_keys, 0, 0, &mut matches),
+    }
+    slide(&a_keys, &b_keys, &mut matches);
+
+    // Changes fill the gaps between matched units
+    let (mut old_utf16, mut new_utf16) = (Utf16Offsets::new(old), Utf16Offsets::new(new));
+    let mut changes = Vec::new();
+    let mut gaps = Vec::new(); // units on each side of each change
+    let (mut i, mut j) = (0, 0);
+    for (ai, bj) in matches.into_iter().chain(std::iter::once((a.len(), b.len()))) {
+        if ai > i || bj > j {
+            let (old_start, old_end) = span(old, &a, i, ai);
+            let (new_start, new_end) = span(new, &b, j, bj);
+            changes.push(Change {
+                kind: match (ai > i, bj > j) {
+                    (true, true) => ChangeKind::Replace,
+                    (true, false) => ChangeKind::Delete,
+                    _ => ChangeKind::Insert,
+                },
+                old: Position { start: old_utf16.at(old_start), end: old_utf16.at(old_end) },
+                new: Position { start: new_utf16.at(new_start), end: new_utf16.at(new_end) },
+                old_text: old[old_start..old_end].to_string(),
+                new_text: new[new_start..new_end].to_string(),
+            });
+            gaps.push((ai - i, bj - j));
+        }
+        (i, j) = (ai + 1, bj + 1);
+    }
+
+    let changed: usize = gaps.iter().map(|&(old, new)| old + new).sum();
+    let mut moved = 0;
+    if options.detect_moves {
+        moved = pair_moves(&mut changes, &gaps, options.min_move_words.max(1));
+    }
+    let total = a.len() + b.len();
+    Diff {
+        similarity: if total == 0 { 1.0 } else { (total - changed + moved) as f64 / total as f64 },
+        changes,
+    }
+}
+
+fn words(text: &str) -> Vec<Unit> {
+    text.split_word_bound_indices()
+        .filter(|(_, token)| !token.trim().is_empty())
+        .map(|(start, token)| Unit { start, end: start + token.len(), key: fnv1a(token.as_bytes()) })
+        .collect()
+}
+
+fn sentences(text: &str, segmenter: &Segmenter) -> Vec<Unit> {
+    segmenter
+        .spans(text)
+        .into_iter()
+        .filter_map(|(start, end)| {
+            let sentence = &text[start..end];
+            let trimmed = sentence.trim_start();
+            let start = start + sentence.len() - trimmed.len();
+            let trimmed = trimmed.trim_end();
+            let collapsed = trimmed.split_whitespace().collect::<Vec<_>>().join(" ");
+            (!trimmed.is_empty()).then(|| Unit { start, end: start + trimmed.len(), key: fnv1a(collapsed.as_bytes()) })
+        })
+        .collect()
+}
+
+/// Byte span of units `[from, to)`, or the empty span where they would be.
+fn span(text: &str, units: &[Unit], from: usize, to: usize) -> (usize, usize) {
+    if to > from {
+        (units[from].start, units[to - 1].end)
+    } else {
+        let at = units.get(from).map_or(text.len(), |u| u.start);
+        (at, at)
+    }
+}
+
+/// Turns each deletion whose text is inserted elsewhere into a move;
+/// returns the number of units that moved, counting both sides.
+fn pair_moves(changes: &mut Vec<Change>, gaps: &[(usize, usize)], min_words: usize) -> usize {
+    let normalized = |text: &str| text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut inserts: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, change) in changes.iter().enumerate().rev() {
+        if change.kind == ChangeKind::Insert && change.new_text.unicode_words().count() >= min_words {
+            inserts.entry(normalized(&change.new_text)).or_default().push(index);
+        }
+    }
+
+    let mut moved = 0;
+    let mut paired = Vec::new();
+    for index in 0..changes.len() {
+        if changes[index].kind != ChangeKind::Delete {
+            continue;
+        }
+        let Some(insert) = inserts.get_mut(&normalized(&changes[index].old_text)).and_then(Vec::pop) else {
+            continue;
+        };
+        let (new, new_text) = (changes[insert].new, changes[insert].new_text.clone());
+        let change = &mut changes[index];
+        change.kind = ChangeKind::Move;
+        change.new = new;
+        change.new_text = new_text;
+        moved += gaps[index].0 + gaps[insert].1;
+        paired.push(insert);
+    }
+    paired.sort_unstable();
+    for insert in paired.into_iter().rev() {
+        changes.remove(insert);
+    }
+    moved
+}
+
+/// Appends the matched index pairs of the shortest edit script between `a`
+/// and `b`, offset by `a_offset` and `b_offset`, in order. Splits at the
+/// middle snake and recurses on both sides, so memory stays linear in the
+/// input rather than growing with the square of the edit distance.
+fn myers(a: &[u64], b: &[u64], a_offset: usize, b_offset: usize, matches: &mut Vec<(usize, usize)>) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    matches.extend((0..prefix).map(|i| (a_offset + i, b_offset + i)));
+    let (a_rest, b_rest) = (&a[prefix..], &b[prefix..]);
+    let suffix = a_rest.iter().rev().zip(b_rest.iter().rev()).take_while(|(x, y)| x == y).count();
+    let (a_mid, b_mid) = (&a_rest[..a_rest.len() - suffix], &b_rest[..b_rest.len() - suffix]);
+
+    let (a_start, b_start) = (a_offset + prefix, b_offset + prefix);
+    if !a_mid.is_empty() && !b_mid.is_empty() {
+        // Both sides are non-empty and differ at each end, so the edit
+        // distance is at least 2 and each half is strictly shorter
+        let (x, y, u, v) = middle_snake(a_mid, b_mid);
+        myers(&a_mid[..x], &b_mid[..y], a_start, b_start, matches);
+        matches.extend((0..u - x).map(|i| (a_start + x + i, b_start + y + i)));
+        myers(&a_mid[u..], &b_mid[v..], a_start + u, b_start + v, matches);
+    }
+    let (a_end, b_end) = (a_start + a_mid.len(), b_start + b_mid.len());
+    matches.extend((0..suffix).map(|i| (a_end + i, b_end + i)));
+}
+
+/// Finds the snake `(x, y)..(u, v)` in the middle of a shortest edit script
+/// by searching forward from the start and backward from the end until the
+/// two searches meet (Myers 1986, section 4b).
+fn middle_snake(a: &[u64], b: &[u64]) -> (usize, usize, usize, usize) {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let max = (n + m + 1) / 2;
+    // Furthest x reached on each diagonal k, counted from the start going
+    // forward and from the end going backward
+    let mut forward = vec![0isize; 2 * max as usize + 3];
+    let mut backward = vec![0isize; 2 * max as usize + 3];
+    let index = |k: isize| (k + max + 1) as usize;
+    for d in 0..=max {
+        let mut k = -d;
+        while k <= d {
+            let x0 = if k == -d || (k != d && forward[index(k - 1)] < forward[index(k + 1)]) {
+                forward[index(k + 1)]
+            } else {
+                forward[index(k - 1)] + 1
+            };
+            let y0 = x0 - k;
+            let (mut x, mut y) = (x0, y0);
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[index(k)] = x;
+            let reverse_k = delta - k;
+            if odd && reverse_k.abs() < d && x + backward[index(reverse_k)] >= n {
+                return (x0 as usize, y0 as usize, x as usize, y as usize);
+            }
+            k += 2;
+        }
+
+        let mut k = -d;
+        while k <= d {
+            let x0 = if k == -d || (k != d && backward[index(k - 1)] < backward[index(k + 1)]) {
+                backward[index(k + 1)]
+            } else {
+                backward[index(k - 1)] + 1
+            };
+            let y0 = x0 - k;
+            let (mut x, mut y) = (x0, y0);
+            while x < n && y < m && a[(n - x - 1) as usize] == b[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[index(k)] = x;
+            let forward_k = delta - k;
+            if !odd && forward_k.abs() <= d && x + forward[index(forward_k)] >= n {
+                return ((n - x) as usize, (m - y) as usize, (n - x0) as usize, (m - y0) as usize);
+            }
+            k += 2;
+        }
+    }
+    unreachable!("the searches meet within (n + m + 1) / 2 steps")
+}
+
+/// Slides each pure insertion or deletion forward while the unit after it
+/// equals its first one, so that an inserted ". Next sentence" becomes
+/// "Next sentence." and blocks line up for move detection.
+fn slide(a: &[u64], b: &[u64], matches: &mut [(usize, usize)]) {
+    let (mut i, mut j) = (0, 0);
+    for p in 0..matches.len() {
+        let (ai, bj) = matches[p];
+        let mut t = 0;
+        if ai == i && bj > j {
+            while p + t < matches.len() && matches[p + t] == (ai + t, bj + t) && b[j + t] == b[bj + t] {
+                matches[p + t] = (ai + t, j + t);
+                t += 1;
+            }
+        } else if bj == j && ai > i {
+            while p + t < matches.len() && matches[p + t] == (ai + t, bj + t) && a[i + t] == a[ai + t] {
+                matches[p + t] = (i + t, bj + t);
+                t += 1;
+            }
+        }
+        (i, j) = (matches[p].0 + 1, matches[p].1 + 1);
+    }
+}
+
+/// Patience diff: matches the units unique to both sides in their longest
+/// common order, then recurses between them.
+fn patience(a: &[u64], b: &[u64], a_offset: usize, b_offset: usize, matches: &mut Vec<(usize, usize)>) {
+    let mut counts: HashMap<u64, (usize, usize, usize)> = HashMap::new(); // key -> (in a, in b, index in b)
+    for &key in a {
+        counts.entry(key).or_default().0 += 1;
+    }
+    for (j, &key) in b.iter().enumerate() {
+        let entry = counts.entry(key).or_default();
+        entry.1 += 1;
+        entry.2 = j;
+    }
+    let unique: Vec<(usize, usize)> = a
+        .iter()
+        .enumerate()
+        .filter_map(|(i, key)| match counts[key] {
+            (1, 1, j) => Some((i, j)),
+            _ => None,
+        })
+        .collect();
+    let anchors = longest_increasing(&unique);
+    if anchors.is_empty() {
+        myers(a, b, a_offset, b_offset, matches);
+        return;
+    }
+
+    let (mut i, mut j) = (0, 0);
+    for (ai, bj) in anchors {
+        patience(&a[i..ai], &b[j..bj], a_offset + i, b_offset + j, matches);
+        matches.push((a_offset + ai, b_offset + bj));
+        (i, j) = (ai + 1, bj + 1);
+    }
+    patience(&a[i..], &b[j..], a_offset + i, b_offset + j, matches);
+}
+
+/// Longest subsequence of `pairs`, ordered by their first index, whose
+/// second indices increase (patience sorting).
+fn longest_increasing(pairs: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut tops: Vec<usize> = Vec::new(); // pair index on top of each pile
+    let mut parents: Vec<Option<usize>> = vec![None; pairs.len()];
+    for (index, &(_, j)) in pairs.iter().enumerate() {
+        let pile = tops.partition_point(|&top| pairs[top].1 < j);
+        parents[index] = pile.checked_sub(1).map(|p| tops[p]);
+        if pile == tops.len() {
+            tops.push(index);
+        } else {
+            tops[pile] = index;
+        }
+    }
+    let mut sequence = Vec::new();
+    let mut next = tops.last().copied();
+    while let Some(index) = next {
+        sequence.push(pairs[index]);
+        next = parents[index];
+    }
+    sequence.reverse();
+    sequence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(diff: &Diff) -> Vec<ChangeKind> {
+        diff.changes.iter().map(|c| c.kind).collect()
+    }
+
+    #[test]
+    fn test_word_changes_with_offsets_in_both_texts() {
+        let old = "The tenant pays rent monthly to the landlord.";
+        let new = "The tenant pays the full rent quarterly to the landlord!";
+        for algorithm in [DiffAlgorithm::Myers, DiffAlgorithm::Patience] {
+            let diff = diff(old, new, &DiffOptions { algorithm, ..Default::default() });
+            assert_eq!(kinds(&diff), vec![ChangeKind::Insert, ChangeKind::Replace, ChangeKind::Replace]);
+            let texts: Vec<(&str, &str)> = diff.changes.iter().map(|c| (c.old_text.as_str(), c.new_text.as_str())).collect();
+            assert_eq!(texts, vec![("", "the full"), ("monthly", "quarterly"), (".", "!")]);
+
+            let insert = &diff.changes[0];
+            assert_eq!((insert.old.start, insert.old.end), (16, 16));
+            assert_eq!((insert.new.start, insert.new.end), (16, 24));
+            assert!((diff.similarity - 14.0 / 20.0).abs() < 1e-9);
+        }
+        assert_eq!(diff("Ünïcode 🎉 text", "Ünïcode 🎉 text", &DiffOptions::default()).similarity, 1.0);
+        let deleted = diff("Ünïcode 🎉 text", "text", &DiffOptions::default());
+        assert_eq!(deleted.changes[0].old, Position { start: 0, end: 10 });
+        assert_eq!(deleted.changes[0].new, Position { start: 0, end: 0 });
+    }
+
+    #[test]
+    fn test_sentence_granularity_ignores_rewrapping() {
+        let old = "Payment is due in thirty days.\nLate fees apply. Disputes go to the billing team.";
+        let new = "Payment is due in thirty days. Late fees apply.\n\nDisputes go to the finance team.";
+        let options = DiffOptions { granularity: DiffGranularity::Sentence, language: Some("en".to_string()), ..Default::default() };
+        let diff = diff(old, new, &options);
+        assert_eq!(kinds(&diff), vec![ChangeKind::Replace]);
+        assert_eq!(diff.changes[0].new_text, "Disputes go to the finance team.");
+        assert!((diff.similarity - 4.0 / 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_moved_paragraphs() {
+        let old = "Scope of the services and deliverables.\n\nFees are invoiced monthly in arrears.\n\nEither party may terminate with notice.";
+        let new = "Fees are invoiced monthly in arrears.\n\nScope of the services and deliverables.\n\nEither party may terminate with notice.";
+        let plain = diff(old, new, &DiffOptions::default());
+        assert_eq!(kinds(&plain), vec![ChangeKind::Delete, ChangeKind::Insert]);
+
+        let options = DiffOptions { detect_moves: true, ..Default::default() };
+        let moved = diff(old, new, &options);
+        assert_eq!(kinds(&moved), vec![ChangeKind::Move]);
+        let change = &moved.changes[0];
+        assert_eq!(change.old_text, change.new_text);
+        assert_ne!(change.old.start, change.new.start);
+        assert_eq!(moved.similarity, 1.0);
+
+        // Blocks below the minimum stay deletions and insertions
+        let options = DiffOptions { detect_moves: true, min_move_words: 10, ..Default::default() };
+        assert_eq!(kinds(&diff(old, new, &options)), vec![ChangeKind::Delete, ChangeKind::Insert]);
+    }
+
+    #[test]
+    fn test_myers_finds_a_longest_common_subsequence() {
+        let mut seed = 7u64;
+        let mut sequence = |len: usize| -> Vec<u64> {
+            (0..len)
+                .map(|_| {
+                    seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                    (seed >> 60) % 4
+                })
+                .collect()
+        };
+        for (n, m) in [(0, 5), (1, 1), (9, 4), (30, 31), (64, 40), (120, 150)] {
+            let (a, b) = (sequence(n), sequence(m));
+            let mut lcs = vec![vec![0; m + 1]; n + 1];
+            for i in (0..n).rev() {
+                for j in (0..m).rev() {
+                    lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+                }
+            }
+
+            let mut matches = Vec::new();
+            myers(&a, &b, 0, 0, &mut matches);
+            assert_eq!(matches.len(), lcs[0][0], "{} against {}", n, m);
+            assert!(matches.iter().all(|&(i, j)| a[i] == b[j]));
+            assert!(matches.windows(2).all(|w| w[0].0 < w[1].0 && w[0].1 < w[1].1));
+        }
+    }
+}
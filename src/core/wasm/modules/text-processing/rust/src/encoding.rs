@@ -0,0 +1,160 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, GB18030, GBK, UTF_16BE, UTF_16LE, UTF_8};
+use serde::{Serialize, Deserialize};
+
+use crate::TextProcessingError;
+
+/// BOM-less UTF-16 is assumed when one byte of each pair is zero this often,
+/// as it is for text mostly in Latin script.
+const UTF16_ZERO_SHARE: f64 = 0.3;
+
+/// Bytes decoded to text, in the shape of the TS `DecodedText`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Decoded {
+    pub text: String,
+    /// Lowercased WHATWG name of the encoding the bytes were read as.
+    pub encoding: String,
+    /// Whether some bytes were malformed and replaced by U+FFFD.
+    pub had_errors: bool,
+}
+
+/// Decodes `bytes` as the encoding labelled `label`, or as the detected one
+/// when it is unset or "auto". A byte order mark overrides both.
+pub fn decode(bytes: &[u8], label: Option<&str>) -> Result<Decoded, TextProcessingError> {
+    let encoding = match label.map(str::trim).filter(|l| !l.eq_ignore_ascii_case("auto")) {
+        Some(label) => Encoding::for_label(label.as_bytes())
+            .ok_or_else(|| TextProcessingError::InvalidOptions(format!("unknown encoding '{}'", label)))?,
+        None => detect(bytes),
+    };
+    let (text, used, had_errors) = encoding.decode(bytes);
+    Ok(Decoded {
+        text: text.into_owned(),
+        encoding: used.name().to_lowercase(),
+        had_errors,
+    })
+}
+
+/// Byte order mark, then BOM-less UTF-16, then valid UTF-8, then the
+/// statistical guess of chardetng among the legacy encodings.
+fn detect(bytes: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+    if let Some(encoding) = utf16_without_bom(bytes) {
+        return encoding;
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return UTF_8;
+    }
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    match detector.guess(None, true) {
+        // GB18030 decodes everything GBK does, and more
+        guess if guess == GBK => GB18030,
+        guess => guess,
+    }
+}
+
+fn utf16_without_bom(bytes: &[u8]) -> Option<&'static Encoding> {
+    let pairs = bytes.len() / 2;
+    if pairs < 2 {
+        return None;
+    }
+    let (even, odd) = bytes
+        .chunks_exact(2)
+        .fold((0, 0), |(even, odd), pair| (even + usize::from(pair[0] == 0), odd + usize::from(pair[1] == 0)));
+    let share = |zeros: usize| zeros as f64 / pairs as f64;
+    if share(odd) >= UTF16_ZERO_SHARE && even * 4 < odd {
+        Some(UTF_16LE)
+    } else if share(even) >= UTF16_ZERO_SHARE && odd * 4 < even {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::{SHIFT_JIS, WINDOWS_1252};
+
+    fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|unit| if big_endian { unit.to_be_bytes() } else { unit.to_le_bytes() })
+            .collect()
+    }
+
+    fn detected(bytes: &[u8]) -> (String, String) {
+        let decoded = decode(bytes, None).unwrap();
+        (decoded.encoding, decoded.text)
+    }
+
+    #[test]
+    fn test_unicode_with_and_without_bom() {
+        let text = "Invoice\0 total: 42 €";
+        assert_eq!(detected(text.as_bytes()), ("utf-8".to_string(), text.to_string()));
+        assert_eq!(detected(&[b"\xEF\xBB\xBF".as_slice(), text.as_bytes()].concat()).1, text);
+
+        let le = [vec![0xFF, 0xFE], utf16(text, false)].concat();
+        assert_eq!(detected(&le), ("utf-16le".to_string(), text.to_string()));
+        // No zero byte cuts the text short
+        assert_eq!(detected(&utf16(text, false)), ("utf-16le".to_string(), text.to_string()));
+        assert_eq!(detected(&utf16(text, true)), ("utf-16be".to_string(), text.to_string()));
+    }
+
+    #[test]
+    fn test_legacy_encodings() {
+        let csv = "Name;Straße;Betrag\nMüller;Hauptstraße 5;12,50 €\nLefèvre;Rue de l'Église;7,00 €\n";
+        let (bytes, _, _) = WINDOWS_1252.encode(csv);
+        assert_eq!(detected(&bytes), ("windows-1252".to_string(), csv.to_string()));
+
+        let japanese = "請求書の支払い期限は来月末です。ご確認ください。";
+        let (bytes, _, _) = SHIFT_JIS.encode(japanese);
+        assert_eq!(detected(&bytes), ("shift_jis".to_string(), japanese.to_string()));
+
+        let chinese = "发票的付款期限是下个月底。请确认收到。";
+        let (bytes, _, _) = GB18030.encode(chinese);
+        assert_eq!(detected(&bytes), ("gb18030".to_string(), chinese.to_string()));
+    }
+
+    #[test]
+    fn test_declared_encoding() {
+        let (bytes, _, _) = WINDOWS_1252.encode("Café");
+        let decoded = decode(&bytes, Some("latin1")).unwrap();
+        assert_eq!((decoded.encoding.as_str(), decoded.text.as_str(), decoded.had_errors), ("windows-1252", "Café", false));
+
+        let decoded = decode(&bytes, Some("utf-8")).unwrap();
+        assert!(decoded.had_errors);
+        assert_eq!(decode(&bytes, Some("AUTO")).unwrap().text, "Café");
+        assert!(decode(&bytes, Some("klingon")).is_err());
+    }
+
+    #[test]
+    fn test_legacy_pointer_input() {
+        let mut processor = crate::TextProcessor::new();
+        let bytes = utf16("Grüße aus Köln", false);
+        let ptr = processor.allocate(bytes.len());
+        processor.memory[ptr..ptr + bytes.len()].copy_from_slice(&bytes);
+        assert_eq!(processor.read_string(ptr, bytes.len(), None).unwrap(), "Grüße aus Köln");
+
+        let (bytes, _, _) = WINDOWS_1252.encode("Café");
+        let label = b"latin1";
+        let text_ptr = processor.allocate(bytes.len() + label.len());
+        processor.memory[text_ptr..text_ptr + bytes.len()].copy_from_slice(&bytes);
+        processor.memory[text_ptr + bytes.len()..text_ptr + bytes.len() + label.len()].copy_from_slice(label);
+        let record = [64, 0, 0, 0, 1, 0, 0, (text_ptr + bytes.len()) as i32, label.len() as i32];
+        let config_ptr = processor.allocate(record.len() * 4);
+        for (i, field) in record.iter().enumerate() {
+            processor.memory[config_ptr + 4 * i..config_ptr + 4 * i + 4].copy_from_slice(&field.to_le_bytes());
+        }
+        let config = processor.read_config(config_ptr).unwrap();
+        assert_eq!(config.encoding.as_deref(), Some("latin1"));
+        assert_eq!(processor.read_string(text_ptr, bytes.len(), config.encoding.as_deref()).unwrap(), "Café");
+
+        // Ranges past the end of memory are errors rather than panics
+        assert!(matches!(processor.read_string(usize::MAX, 2, None), Err(TextProcessingError::OutOfBounds { .. })));
+        assert!(processor.read_string(config_ptr, 64, None).is_err());
+        assert!(processor.read_config(processor.memory.len() - 8).is_err());
+    }
+}
@@ -0,0 +1,800 @@
+use std::collections::HashMap;
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde::{Serialize, Deserialize};
+
+use crate::gazetteer::Gazetteer;
+
+/// Month names by lowercase spelling, for English, German, French and Spanish.
+pub(crate) const MONTHS: &[(&str, u32)] = &[
+    ("january", 1), ("jan", 1), ("januar", 1), ("janvier", 1), ("enero", 1),
+    ("february", 2), ("feb", 2), ("februar", 2), ("février", 2), ("fevrier", 2), ("febrero", 2),
+    ("march", 3), ("mar", 3), ("märz", 3), ("maerz", 3), ("mars", 3), ("marzo", 3),
+    ("april", 4), ("apr", 4), ("avril", 4), ("abril", 4),
+    ("may", 5), ("mai", 5), ("mayo", 5),
+    ("june", 6), ("jun", 6), ("juni", 6), ("juin", 6), ("junio", 6),
+    ("july", 7), ("jul", 7), ("juli", 7), ("juillet", 7), ("julio", 7),
+    ("august", 8), ("aug", 8), ("août", 8), ("aout", 8), ("agosto", 8),
+    ("september", 9), ("sept", 9), ("sep", 9), ("septembre", 9), ("septiembre", 9),
+    ("october", 10), ("oct", 10), ("oktober", 10), ("octobre", 10), ("octubre", 10),
+    ("november", 11), ("nov", 11), ("novembre", 11), ("noviembre", 11),
+    ("december", 12), ("dec", 12), ("dezember", 12), ("dez", 12), ("décembre", 12), ("decembre", 12), ("diciembre", 12),
+];
+
+/// ISO 4217 codes accepted next to an amount.
+const CURRENCY_CODES: &[&str] = &[
+    "USD", "EUR", "GBP", "JPY", "CHF", "CAD", "AUD", "NZD", "CNY", "HKD", "SGD", "INR", "KRW", "RUB",
+    "TRY", "BRL", "MXN", "ZAR", "SEK", "NOK", "DKK", "PLN", "CZK", "HUF", "ILS", "AED", "SAR", "THB",
+    "IDR", "MYR", "PHP", "VND", "NGN", "EGP", "KES", "ARS", "CLP", "COP", "PEN", "TWD", "RON", "BGN",
+    "UAH", "PKR", "BDT", "QAR", "KWD",
+];
+
+/// Currency symbols and names with the ISO code they stand for and how sure
+/// that reading is (`$` alone is usually, not always, US dollars).
+const CURRENCY_SYMBOLS: &[(&str, &str, f64)] = &[
+    ("US$", "USD", 0.97), ("C$", "CAD", 0.95), ("A$", "AUD", 0.95), ("NZ$", "NZD", 0.95),
+    ("HK$", "HKD", 0.95), ("R$", "BRL", 0.95), ("$", "USD", 0.85), ("€", "EUR", 0.97),
+    ("£", "GBP", 0.97), ("¥", "JPY", 0.85), ("₹", "INR", 0.97), ("₩", "KRW", 0.97),
+    ("₽", "RUB", 0.97), ("₺", "TRY", 0.97), ("₪", "ILS", 0.97), ("₫", "VND", 0.97),
+    ("₦", "NGN", 0.97), ("฿", "THB", 0.97), ("zł", "PLN", 0.95), ("Fr.", "CHF", 0.9),
+    ("dollars", "USD", 0.8), ("dollar", "USD", 0.8), ("euros", "EUR", 0.9), ("euro", "EUR", 0.9),
+    ("pounds", "GBP", 0.75), ("yen", "JPY", 0.9), ("rupees", "INR", 0.85), ("rupee", "INR", 0.85),
+];
+
+/// IBAN length per country (ISO 13616 registry).
+const IBAN_LENGTHS: &[(&str, usize)] = &[
+    ("AD", 24), ("AE", 23), ("AL", 28), ("AT", 20), ("AZ", 28), ("BA", 20), ("BE", 16), ("BG", 22),
+    ("BH", 22), ("BR", 29), ("CH", 21), ("CR", 22), ("CY", 28), ("CZ", 24), ("DE", 22), ("DK", 18),
+    ("DO", 28), ("EE", 20), ("EG", 29), ("ES", 24), ("FI", 18), ("FO", 18), ("FR", 27), ("GB", 22),
+    ("GE", 22), ("GI", 23), ("GL", 18), ("GR", 27), ("GT", 28), ("HR", 21), ("HU", 28), ("IE", 22),
+    ("IL", 23), ("IS", 26), ("IT", 27), ("JO", 30), ("KW", 30), ("KZ", 20), ("LB", 28), ("LI", 21),
+    ("LT", 20), ("LU", 20), ("LV", 21), ("MC", 27), ("MD", 24), ("ME", 22), ("MK", 19), ("MR", 27),
+    ("MT", 31), ("MU", 30), ("NL", 18), ("NO", 15), ("PK", 24), ("PL", 28), ("PS", 29), ("PT", 25),
+    ("QA", 29), ("RO", 24), ("RS", 22), ("SA", 24), ("SE", 24), ("SI", 19), ("SK", 24), ("SM", 27),
+    ("TN", 24), ("TR", 26), ("UA", 29), ("VG", 24), ("XK", 20),
+];
+
+pub(crate) fn alternation<'a>(words: impl Iterator<Item = &'a str>) -> String {
+    let mut words: Vec<&str> = words.collect();
+    // Longest first: the regex crate prefers the earliest alternative
+    words.sort_by_key(|w| std::cmp::Reverse(w.chars().count()));
+    words.iter().map(|w| regex::escape(w)).collect::<Vec<_>>().join("|")
+}
+
+const AMOUNT: &str = r"\d{1,3}(?:[,.'\x{A0}\x{202F}]\d{3})+(?:[.,]\d{1,2})?|\d+(?:[.,]\d{1,2})?";
+const SCALE: &str = r"(?:\s?(?P<scale>thousand|million|billion|bn|mn|k|m)\b)?";
+
+lazy_static! {
+    static ref EMAIL: Regex = Regex::new(r"(?i)\b[a-z0-9][a-z0-9._%+-]*@[a-z0-9](?:[a-z0-9-]*[a-z0-9])?(?:\.[a-z0-9](?:[a-z0-9-]*[a-z0-9])?)*\.[a-z]{2,24}\b").unwrap();
+    static ref URL: Regex = Regex::new(r#"(?i)\b(?:https?://|ftp://|www\.)[^\s<>"'`]+"#).unwrap();
+    static ref PHONE: Regex = Regex::new(r"(?:\+|\b00\s?)?(?:\(\d{1,4}\)[ .-]?)?\d(?:[ .\-/]?(?:\(0\)|\(\d{1,4}\))?[ .-]?\d){6,18}").unwrap();
+    static ref PERCENTAGE: Regex = Regex::new(r"(?i)[-+]?\b\d+(?:[.,]\d+)?(?:\s?(?P<sign>%|‰)|\s(?P<word>percent|per cent|pct)\b)").unwrap();
+    static ref IBAN: Regex = Regex::new(r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]){11,30}").unwrap();
+    static ref CARD: Regex = Regex::new(r"\b\d(?:[ -]?\d){12,18}\b").unwrap();
+    static ref CURRENCY_BEFORE: Regex = Regex::new(&format!(
+        r"(?:(?P<symbol>{})|\b(?P<code>[A-Z]{{3}}))\s?(?P<amount>{}){}",
+        alternation(CURRENCY_SYMBOLS.iter().filter(|s| !s.0.chars().all(char::is_alphabetic)).map(|s| s.0)),
+        AMOUNT,
+        SCALE,
+    )).unwrap();
+    static ref CURRENCY_AFTER: Regex = Regex::new(&format!(
+        r"\b(?P<amount>{}){}\s?(?:(?P<symbol>{}|(?:{})\b)|(?P<code>[A-Z]{{3}})\b)",
+        AMOUNT,
+        SCALE,
+        alternation(CURRENCY_SYMBOLS.iter().filter(|s| !s.0.chars().all(char::is_alphabetic)).map(|s| s.0)),
+        // Words end there, so that "10 europeans" is no amount of euros
+        alternation(CURRENCY_SYMBOLS.iter().filter(|s| s.0.chars().all(char::is_alphabetic)).map(|s| s.0)),
+    )).unwrap();
+    static ref ISO_DATE: Regex = Regex::new(r"\b(?P<y>\d{4})-(?P<m>\d{1,2})-(?P<d>\d{1,2})(?:[T ](?P<h>\d{2}):(?P<min>\d{2})(?::(?P<s>\d{2})(?:\.\d+)?)?(?P<tz>Z|[+-]\d{2}:?\d{2})?)?\b").unwrap();
+    static ref YMD_DATE: Regex = Regex::new(r"\b(?P<y>\d{4})(?P<sep>[/.])(?P<m>\d{1,2})[/.](?P<d>\d{1,2})\b").unwrap();
+    static ref NUMERIC_DATE: Regex = Regex::new(r"\b(?P<a>\d{1,2})(?P<sep>[/.-])(?P<b>\d{1,2})(?P<sep2>[/.-])(?P<y>\d{4}|\d{2})\b").unwrap();
+    static ref MONTH_DAY_DATE: Regex = Regex::new(&format!(
+        r"(?i)\b(?P<month>{})\.?\s+(?P<d>\d{{1,2}})(?:st|nd|rd|th)?,?\s+(?P<y>\d{{4}})\b",
+        alternation(MONTHS.iter().map(|m| m.0)),
+    )).unwrap();
+    static ref DAY_MONTH_DATE: Regex = Regex::new(&format!(
+        r"(?i)\b(?P<d>\d{{1,2}})(?:st|nd|rd|th|\.)?\s+(?:of\s+|de\s+)?(?P<month>{})\.?,?\s+(?:de\s+)?(?P<y>\d{{4}})\b",
+        alternation(MONTHS.iter().map(|m| m.0)),
+    )).unwrap();
+    static ref MONTH_YEAR_DATE: Regex = Regex::new(&format!(
+        r"(?i)\b(?P<month>{})\.?\s+(?:de\s+)?(?P<y>\d{{4}})\b",
+        alternation(MONTHS.iter().map(|m| m.0)),
+    )).unwrap();
+    pub(crate) static ref MONTH_NUMBERS: HashMap<String, u32> = MONTHS.iter().map(|&(name, month)| (name.to_string(), month)).collect();
+}
+
+/// Kinds of entity the recognizers produce, named as the TS `EntityType`.
+/// Declaration order breaks ties between equally long, equally confident matches.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum EntityKind {
+    Email,
+    Url,
+    Iban,
+    CreditCard,
+    Date,
+    Money,
+    Percentage,
+    Phone,
+    Person,
+    Organization,
+    Location,
+    Custom,
+}
+
+impl EntityKind {
+    fn name(self) -> &'static str {
+        match self {
+            EntityKind::Email => "email",
+            EntityKind::Url => "url",
+            EntityKind::Iban => "iban",
+            EntityKind::CreditCard => "creditCard",
+            EntityKind::Date => "date",
+            EntityKind::Money => "money",
+            EntityKind::Percentage => "percentage",
+            EntityKind::Phone => "phone",
+            EntityKind::Person => "person",
+            EntityKind::Organization => "organization",
+            EntityKind::Location => "location",
+            EntityKind::Custom => "custom",
+        }
+    }
+}
+
+/// Entity options in the shape of the TS `EntityExtractionOptions`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExtractionOptions {
+    /// Kinds to look for; empty means all.
+    pub entity_types: Vec<EntityKind>,
+    pub min_confidence: f64,
+    /// Calling code (without `+`) assumed for phone numbers written without one.
+    pub default_calling_code: Option<String>,
+    /// Read ambiguous numeric dates such as `03/04/2024` as day first.
+    pub day_first: bool,
+}
+
+impl Default for ExtractionOptions {
+    fn default() -> Self {
+        ExtractionOptions {
+            entity_types: Vec::new(),
+            min_confidence: 0.0,
+            default_calling_code: None,
+            day_first: false,
+        }
+    }
+}
+
+/// A recognized entity in byte offsets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntitySpan {
+    pub kind: EntityKind,
+    pub start: usize,
+    pub end: usize,
+    pub confidence: f64,
+    pub metadata: EntityMetadata,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EntityMetadata {
+    /// Canonical form: E.164 phone, ISO 8601 date, compact IBAN, lowercase email...
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalized: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub brand: Option<String>, // card network
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dictionary: Option<String>, // gazetteer dictionary the match came from
+}
+
+/// The TS `Entity`, with offsets in UTF-16 code units like `String.prototype.slice`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Entity {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: EntityKind,
+    pub value: String,
+    pub confidence: f64,
+    pub position: Position,
+    pub metadata: EntityMetadata,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Runs every requested recognizer and the gazetteer, if any, over `text`
+/// and keeps the best non-overlapping matches, in text order.
+pub fn extract(text: &str, options: &ExtractionOptions, gazetteer: Option<&Gazetteer>) -> Vec<Entity> {
+    let spans = find(text, options, gazetteer);
+    let mut utf16 = Utf16Offsets::new(text);
+    spans
+        .into_iter()
+        .map(|span| {
+            let position = Position { start: utf16.at(span.start), end: utf16.at(span.end) };
+            Entity {
+                id: format!("{}-{}-{}", span.kind.name(), position.start, position.end),
+                kind: span.kind,
+                value: text[span.start..span.end].to_string(),
+                confidence: span.confidence,
+                position,
+                metadata: span.metadata,
+            }
+        })
+        .collect()
+}
+
+/// Byte-offset form of `extract`.
+pub fn find(text: &str, options: &ExtractionOptions, gazetteer: Option<&Gazetteer>) -> Vec<EntitySpan> {
+    let wanted = |kind| options.entity_types.is_empty() || options.entity_types.contains(&kind);
+    let mut candidates = Vec::new();
+    if wanted(EntityKind::Email) {
+        emails(text, &mut candidates);
+    }
+    if wanted(EntityKind::Url) {
+        urls(text, &mut candidates);
+    }
+    if wanted(EntityKind::Iban) {
+        ibans(text, &mut candidates);
+    }
+    if wanted(EntityKind::CreditCard) {
+        cards(text, &mut candidates);
+    }
+    if wanted(EntityKind::Date) {
+        dates(text, options.day_first, &mut candidates);
+    }
+    if wanted(EntityKind::Money) {
+        money(text, &mut candidates);
+    }
+    if wanted(EntityKind::Percentage) {
+        percentages(text, &mut candidates);
+    }
+    if wanted(EntityKind::Phone) {
+        phones(text, options.default_calling_code.as_deref(), &mut candidates);
+    }
+    // Overlapping dictionary matches were asked for and bypass resolution
+    let mut overlapping = Vec::new();
+    if let Some(gazetteer) = gazetteer {
+        let matches = gazetteer.find(text, &options.entity_types);
+        if gazetteer.longest_match() {
+            candidates.extend(matches);
+        } else {
+            overlapping = matches;
+        }
+    }
+    candidates.retain(|c| c.confidence >= options.min_confidence);
+    overlapping.retain(|c| c.confidence >= options.min_confidence);
+
+    let mut spans = resolve_overlaps(candidates);
+    if !overlapping.is_empty() {
+        spans.extend(overlapping);
+        spans.sort_by_key(|s| s.start);
+    }
+    spans
+}
+
+/// Keeps the longest of overlapping candidates, then the most confident.
+pub(crate) fn resolve_overlaps(candidates: Vec<EntitySpan>) -> Vec<EntitySpan> {
+    sweep_overlaps(candidates, |c| (c.start, c.end), |a, b| {
+        (a.end - a.start).cmp(&(b.end - b.start))
+            .then_with(|| a.confidence.total_cmp(&b.confidence))
+            .then_with(|| b.kind.cmp(&a.kind))
+    })
+}
+
+/// Drops overlapping items in one sweep in text order: an item overlapping
+/// the last one kept replaces it when `rank` orders it higher. Returns the
+/// kept items in text order.
+pub(crate) fn sweep_overlaps<T>(
+    mut items: Vec<T>,
+    bounds: impl Fn(&T) -> (usize, usize),
+    rank: impl Fn(&T, &T) -> std::cmp::Ordering,
+) -> Vec<T> {
+    items.sort_by(|a, b| bounds(a).0.cmp(&bounds(b).0).then_with(|| rank(b, a)));
+    let mut kept: Vec<T> = Vec::with_capacity(items.len());
+    for item in items {
+        match kept.last_mut() {
+            Some(last) if bounds(&item).0 < bounds(last).1 => {
+                if rank(&item, last).is_gt() {
+                    *last = item;
+                }
+            }
+            _ => kept.push(item),
+        }
+    }
+    kept
+}
+
+fn span(kind: EntityKind, start: usize, end: usize, confidence: f64, metadata: EntityMetadata) -> EntitySpan {
+    EntitySpan { kind, start, end, confidence, metadata }
+}
+
+fn normalized(value: String) -> EntityMetadata {
+    EntityMetadata { normalized: Some(value), ..Default::default() }
+}
+
+fn emails(text: &str, out: &mut Vec<EntitySpan>) {
+    for m in EMAIL.find_iter(text) {
+        let local = &m.as_str()[..m.as_str().find('@').unwrap_or(0)];
+        if local.ends_with('.') || local.contains("..") {
+            continue;
+        }
+        out.push(span(EntityKind::Email, m.start(), m.end(), 0.99, normalized(m.as_str().to_lowercase())));
+    }
+}
+
+fn urls(text: &str, out: &mut Vec<EntitySpan>) {
+    for m in URL.find_iter(text) {
+        // Sentence punctuation and unbalanced closing brackets are not part of the URL
+        let mut url = m.as_str();
+        loop {
+            let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?', '\'', '"']);
+            let trimmed = match trimmed.chars().last() {
+                Some(close @ (')' | ']' | '}')) => {
+                    let open = match close { ')' => '(', ']' => '[', _ => '{' };
+                    if trimmed.matches(open).count() < trimmed.matches(close).count() {
+                        &trimmed[..trimmed.len() - 1]
+                    } else {
+                        trimmed
+                    }
+                }
+                _ => trimmed,
+            };
+            if trimmed.len() == url.len() {
+                break;
+            }
+            url = trimmed;
+        }
+        let host_start = url.find("://").map_or(0, |i| i + 3);
+        if !url[host_start..].contains('.') {
+            continue;
+        }
+        let (confidence, canonical) = if host_start == 0 {
+            (0.9, format!("http://{}", url))
+        } else {
+            (0.98, url.to_string())
+        };
+        out.push(span(EntityKind::Url, m.start(), m.start() + url.len(), confidence, normalized(canonical)));
+    }
+}
+
+fn ibans(text: &str, out: &mut Vec<EntitySpan>) {
+    for m in IBAN.find_iter(text) {
+        let country = &m.as_str()[..2];
+        let Some(&(_, length)) = IBAN_LENGTHS.iter().find(|&&(c, _)| c == country) else {
+            continue;
+        };
+        // The pattern may run into a following word; take exactly `length` characters
+        let mut compact = String::with_capacity(length);
+        let mut end = m.start();
+        for (i, c) in m.as_str().char_indices() {
+            if c == ' ' {
+                continue;
+            }
+            compact.push(c);
+            end = m.start() + i + c.len_utf8();
+            if compact.len() == length {
+                break;
+            }
+        }
+        if compact.len() != length || !is_word_boundary(text, end) || !iban_checksum_valid(&compact) {
+            continue;
+        }
+        out.push(span(EntityKind::Iban, m.start(), end, 0.99, EntityMetadata {
+            normalized: Some(compact),
+            country: Some(country.to_string()),
+            ..Default::default()
+        }));
+    }
+}
+
+/// ISO 7064 mod 97-10 over the IBAN with its first four characters moved to the end.
+fn iban_checksum_valid(iban: &str) -> bool {
+    let rearranged = iban[4..].chars().chain(iban[..4].chars());
+    let mut remainder = 0u32;
+    for c in rearranged {
+        let Some(value) = c.to_digit(36) else {
+            return false;
+        };
+        remainder = if value < 10 {
+            (remainder * 10 + value) % 97
+        } else {
+            (remainder * 100 + value) % 97
+        };
+    }
+    remainder == 1
+}
+
+fn cards(text: &str, out: &mut Vec<EntitySpan>) {
+    for m in CARD.find_iter(text) {
+        let digits: String = m.as_str().chars().filter(char::is_ascii_digit).collect();
+        let Some(brand) = card_brand(&digits) else {
+            continue;
+        };
+        if !luhn_valid(&digits) {
+            continue;
+        }
+        out.push(span(EntityKind::CreditCard, m.start(), m.end(), 0.97, EntityMetadata {
+            normalized: Some(digits),
+            brand: Some(brand.to_string()),
+            ..Default::default()
+        }));
+    }
+}
+
+/// Card network from the issuer prefix, if `digits` has a length that network issues.
+fn card_brand(digits: &str) -> Option<&'static str> {
+    let prefix = |n: usize| digits[..n].parse::<u32>().unwrap_or(0);
+    let len = digits.len();
+    match () {
+        _ if digits.starts_with('4') && matches!(len, 13 | 16 | 19) => Some("visa"),
+        _ if ((51..=55).contains(&prefix(2)) || (2221..=2720).contains(&prefix(4))) && len == 16 => Some("mastercard"),
+        _ if matches!(prefix(2), 34 | 37) && len == 15 => Some("amex"),
+        _ if (prefix(4) == 6011 || prefix(2) == 65 || (644..=649).contains(&prefix(3))) && (16..=19).contains(&len) => Some("discover"),
+        _ if (3528..=3589).contains(&prefix(4)) && (16..=19).contains(&len) => Some("jcb"),
+        _ if (matches!(prefix(2), 36 | 38) || (300..=305).contains(&prefix(3))) && len == 14 => Some("diners"),
+        _ if prefix(2) == 62 && (16..=19).contains(&len) => Some("unionpay"),
+        _ => None,
+    }
+}
+
+fn luhn_valid(digits: &str) -> bool {
+    let sum: u32 = digits
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(i, b)| {
+            let d = (b - b'0') as u32;
+            if i % 2 == 1 {
+                if d * 2 > 9 { d * 2 - 9 } else { d * 2 }
+            } else {
+                d
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+fn dates(text: &str, day_first: bool, out: &mut Vec<EntitySpan>) {
+    let number = |caps: &Captures, name: &str| caps.name(name).and_then(|m| m.as_str().parse::<u32>().ok());
+    let month = |caps: &Captures| MONTH_NUMBERS.get(&caps["month"].to_lowercase()).copied();
+
+    for caps in ISO_DATE.captures_iter(text) {
+        let (Some(y), Some(m), Some(d)) = (number(&caps, "y"), number(&caps, "m"), number(&caps, "d")) else {
+            continue;
+        };
+        let Some(mut iso) = iso_date(y, m, d) else {
+            continue;
+        };
+        if let (Some(h), Some(min)) = (number(&caps, "h"), number(&caps, "min")) {
+            if h > 23 || min > 59 || number(&caps, "s").is_some_and(|s| s > 60) {
+                continue;
+            }
+            iso.push_str(&format!("T{:02}:{:02}:{:02}", h, min, number(&caps, "s").unwrap_or(0)));
+            if let Some(tz) = caps.name("tz") {
+                iso.push_str(tz.as_str());
+            }
+        }
+        let whole = caps.get(0).unwrap();
+        out.push(span(EntityKind::Date, whole.start(), whole.end(), 0.98, normalized(iso)));
+    }
+
+    for caps in YMD_DATE.captures_iter(text) {
+        let (Some(y), Some(m), Some(d)) = (number(&caps, "y"), number(&caps, "m"), number(&caps, "d")) else {
+            continue;
+        };
+        let whole = caps.get(0).unwrap();
+        if whole.as_str().matches(&caps["sep"]).count() != 2 {
+            continue;
+        }
+        if let Some(iso) = iso_date(y, m, d) {
+            out.push(span(EntityKind::Date, whole.start(), whole.end(), 0.95, normalized(iso)));
+        }
+    }
+
+    for caps in NUMERIC_DATE.captures_iter(text) {
+        if caps["sep"] != caps["sep2"] {
+            continue;
+        }
+        let (Some(a), Some(b)) = (number(&caps, "a"), number(&caps, "b")) else {
+            continue;
+        };
+        let year_text = &caps["y"];
+        let mut y: u32 = year_text.parse().unwrap_or(0);
+        let mut confidence: f64 = 0.9;
+        if year_text.len() == 2 {
+            y += if y < 50 { 2000 } else { 1900 };
+            confidence -= 0.1;
+        }
+        // Dotted dates are day first across Europe
+        let (d, m) = if a > 12 {
+            (a, b)
+        } else if b > 12 {
+            (b, a)
+        } else {
+            confidence -= if a == b { 0.0 } else { 0.2 };
+            if day_first || &caps["sep"] == "." { (a, b) } else { (b, a) }
+        };
+        if let Some(iso) = iso_date(y, m, d) {
+            let whole = caps.get(0).unwrap();
+            out.push(span(EntityKind::Date, whole.start(), whole.end(), confidence, normalized(iso)));
+        }
+    }
+
+    for regex in [&*MONTH_DAY_DATE, &*DAY_MONTH_DATE] {
+        for caps in regex.captures_iter(text) {
+            let (Some(y), Some(m), Some(d)) = (number(&caps, "y"), month(&caps), number(&caps, "d")) else {
+                continue;
+            };
+            if let Some(iso) = iso_date(y, m, d) {
+                let whole = caps.get(0).unwrap();
+                out.push(span(EntityKind::Date, whole.start(), whole.end(), 0.95, normalized(iso)));
+            }
+        }
+    }
+
+    for caps in MONTH_YEAR_DATE.captures_iter(text) {
+        let (Some(y), Some(m)) = (number(&caps, "y"), month(&caps)) else {
+            continue;
+        };
+        // "May" and "mar" are also ordinary words
+        let confidence = if caps["month"].len() <= 3 { 0.7 } else { 0.85 };
+        let whole = caps.get(0).unwrap();
+        out.push(span(EntityKind::Date, whole.start(), whole.end(), confidence, normalized(format!("{:04}-{:02}", y, m))));
+    }
+}
+
+/// `YYYY-MM-DD` if the date exists.
+fn iso_date(year: u32, month: u32, day: u32) -> Option<String> {
+    let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return None,
+    };
+    ((1..=days).contains(&day) && (1000..=9999).contains(&year))
+        .then(|| format!("{:04}-{:02}-{:02}", year, month, day))
+}
+
+fn money(text: &str, out: &mut Vec<EntitySpan>) {
+    for regex in [&*CURRENCY_BEFORE, &*CURRENCY_AFTER] {
+        for caps in regex.captures_iter(text) {
+            let (currency, confidence) = if let Some(symbol) = caps.name("symbol") {
+                match CURRENCY_SYMBOLS.iter().find(|s| s.0.eq_ignore_ascii_case(symbol.as_str())) {
+                    Some(&(_, code, confidence)) => (code, confidence),
+                    None => continue,
+                }
+            } else {
+                match CURRENCY_CODES.iter().find(|&&c| c == &caps["code"]) {
+                    Some(code) => (*code, 0.97),
+                    None => continue,
+                }
+            };
+            let Some(mut amount) = parse_amount(&caps["amount"]) else {
+                continue;
+            };
+            amount *= match caps.name("scale").map(|s| s.as_str().to_lowercase()).as_deref() {
+                Some("k" | "thousand") => 1e3,
+                Some("m" | "mn" | "million") => 1e6,
+                Some("bn" | "billion") => 1e9,
+                _ => 1.0,
+            };
+            let whole = caps.get(0).unwrap();
+            out.push(span(EntityKind::Money, whole.start(), whole.end(), confidence, EntityMetadata {
+                normalized: Some(format!("{} {}", amount, currency)),
+                currency: Some(currency.to_string()),
+                amount: Some(amount),
+                ..Default::default()
+            }));
+        }
+    }
+}
+
+/// Parses an amount with either `,` or `.` as the decimal separator: a final
+/// separator followed by one or two digits is decimal, any other is grouping.
+fn parse_amount(amount: &str) -> Option<f64> {
+    let decimal = amount
+        .rfind([',', '.'])
+        .filter(|&i| (2..=3).contains(&(amount.len() - i)));
+    let mut plain = String::with_capacity(amount.len());
+    for (i, c) in amount.char_indices() {
+        if c.is_ascii_digit() {
+            plain.push(c);
+        } else if Some(i) == decimal {
+            plain.push('.');
+        }
+    }
+    plain.parse().ok()
+}
+
+fn percentages(text: &str, out: &mut Vec<EntitySpan>) {
+    for caps in PERCENTAGE.captures_iter(text) {
+        let whole = caps.get(0).unwrap();
+        let number: String = whole.as_str()
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | ','))
+            .map(|c| if c == ',' { '.' } else { c })
+            .collect();
+        let Ok(mut value) = number.parse::<f64>() else {
+            continue;
+        };
+        if caps.name("sign").is_some_and(|s| s.as_str() == "‰") {
+            value /= 10.0;
+        }
+        let confidence = if caps.name("sign").is_some() { 0.97 } else { 0.9 };
+        out.push(span(EntityKind::Percentage, whole.start(), whole.end(), confidence, EntityMetadata {
+            normalized: Some(format!("{}%", value)),
+            amount: Some(value),
+            ..Default::default()
+        }));
+    }
+}
+
+fn phones(text: &str, default_calling_code: Option<&str>, out: &mut Vec<EntitySpan>) {
+    for m in PHONE.find_iter(text) {
+        let candidate = m.as_str();
+        // Part of a longer token such as an order number or a word
+        let before = text[..m.start()].chars().next_back();
+        if before.is_some_and(|c| c.is_alphanumeric() || matches!(c, '+' | '-' | '/' | '.')) || !is_word_boundary(text, m.end()) {
+            continue;
+        }
+        // Numeric dates, valid or not, fit the phone pattern too
+        if [&*ISO_DATE, &*YMD_DATE, &*NUMERIC_DATE].iter().any(|r| r.find(candidate).is_some_and(|d| d.len() == candidate.len())) {
+            continue;
+        }
+        let international = candidate.starts_with('+') || candidate.starts_with("00");
+        // "(0)" marks a trunk prefix dropped when dialling from abroad
+        let digits: String = candidate.replace("(0)", "").chars().filter(char::is_ascii_digit).collect();
+        let formatted = candidate.chars().any(|c| !c.is_ascii_digit() && c != '+');
+
+        let (normalized, confidence) = if international {
+            let number = if candidate.starts_with("00") { &digits[2..] } else { &digits[..] };
+            if !(8..=15).contains(&number.len()) || number.starts_with('0') {
+                continue;
+            }
+            (Some(format!("+{}", number)), 0.95)
+        } else {
+            if !(7..=12).contains(&digits.len()) {
+                continue;
+            }
+            let confidence = if formatted { 0.8 } else { 0.5 };
+            match default_calling_code {
+                Some(code) => {
+                    let national = if code == "1" && digits.len() == 11 {
+                        digits.strip_prefix('1').unwrap_or(&digits)
+                    } else {
+                        digits.strip_prefix('0').unwrap_or(&digits)
+                    };
+                    let e164 = format!("+{}{}", code, national);
+                    if e164.len() - 1 > 15 {
+                        continue;
+                    }
+                    (Some(e164), confidence)
+                }
+                None => (None, confidence - 0.1),
+            }
+        };
+        out.push(span(EntityKind::Phone, m.start(), m.end(), confidence, EntityMetadata {
+            normalized,
+            ..Default::default()
+        }));
+    }
+}
+
+fn is_word_boundary(text: &str, at: usize) -> bool {
+    !text[at..].chars().next().is_some_and(char::is_alphanumeric)
+}
+
+/// Converts ascending byte offsets into UTF-16 code unit offsets in one pass.
+pub(crate) struct Utf16Offsets<'a> {
+    text: &'a str,
+    byte: usize,
+    utf16: usize,
+}
+
+impl<'a> Utf16Offsets<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Utf16Offsets { text, byte: 0, utf16: 0 }
+    }
+
+    pub fn at(&mut self, byte: usize) -> usize {
+        if byte < self.byte {
+            self.byte = 0;
+            self.utf16 = 0;
+        }
+        self.utf16 += self.text[self.byte..byte].encode_utf16().count();
+        self.byte = byte;
+        self.utf16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(text: &str, options: &ExtractionOptions) -> Vec<(EntityKind, String, Option<String>)> {
+        find(text, options, None)
+            .into_iter()
+            .map(|s| (s.kind, text[s.start..s.end].to_string(), s.metadata.normalized))
+            .collect()
+    }
+
+    #[test]
+    fn test_contact_details() {
+        let options = ExtractionOptions { default_calling_code: Some("44".to_string()), ..Default::default() };
+        let text = "Write to Jane.Doe@Example.co.uk or see https://example.com/about (our site). Call +1 (415) 555-0132 or 020 7946 0958.";
+        assert_eq!(found(text, &options), vec![
+            (EntityKind::Email, "Jane.Doe@Example.co.uk".to_string(), Some("jane.doe@example.co.uk".to_string())),
+            (EntityKind::Url, "https://example.com/about".to_string(), Some("https://example.com/about".to_string())),
+            (EntityKind::Phone, "+1 (415) 555-0132".to_string(), Some("+14155550132".to_string())),
+            (EntityKind::Phone, "020 7946 0958".to_string(), Some("+442079460958".to_string())),
+        ]);
+    }
+
+    #[test]
+    fn test_financial_identifiers() {
+        let text = "Pay €1.234,50 (USD 2.5m, 15 % off) to DE89 3704 0044 0532 0130 00 BIC COBADEFF or card 4111 1111 1111 1111; not 4111 1111 1111 1112.";
+        let spans = find(text, &ExtractionOptions::default(), None);
+        let kinds: Vec<EntityKind> = spans.iter().map(|s| s.kind).collect();
+        assert_eq!(kinds, vec![EntityKind::Money, EntityKind::Money, EntityKind::Percentage, EntityKind::Iban, EntityKind::CreditCard]);
+        assert_eq!(spans[0].metadata.amount, Some(1234.5));
+        assert_eq!(spans[0].metadata.currency.as_deref(), Some("EUR"));
+        assert_eq!(spans[1].metadata.amount, Some(2_500_000.0));
+        assert_eq!(spans[2].metadata.amount, Some(15.0));
+        assert_eq!(spans[3].metadata.normalized.as_deref(), Some("DE89370400440532013000"));
+        assert_eq!(&text[spans[3].start..spans[3].end], "DE89 3704 0044 0532 0130 00");
+        assert_eq!(spans[4].metadata.brand.as_deref(), Some("visa"));
+
+        let options = ExtractionOptions { entity_types: vec![EntityKind::Money], ..Default::default() };
+        assert!(found("We hired 10 europeans and 5 yenta, 3 dollarbirds and 2 rupeeless poundsmiths.", &options).is_empty());
+        let amounts: Vec<String> = found("Paid 10 euros, 5 yen and 20 zł.", &options).into_iter().map(|(_, text, _)| text).collect();
+        assert_eq!(amounts, vec!["10 euros", "5 yen", "20 zł"]);
+    }
+
+    #[test]
+    fn test_dates() {
+        let text = "Signed 2024-02-29, due March 5th, 2024 (5. März 2024), renewed 31/12/23 and 03/04/2024; not 2023-02-29.";
+        let dates: Vec<String> = found(text, &ExtractionOptions::default())
+            .into_iter()
+            .map(|(_, _, normalized)| normalized.unwrap())
+            .collect();
+        assert_eq!(dates, vec!["2024-02-29", "2024-03-05", "2024-03-05", "2023-12-31", "2024-03-04"]);
+
+        let options = ExtractionOptions { day_first: true, entity_types: vec![EntityKind::Date], ..Default::default() };
+        assert_eq!(found("03/04/2024", &options)[0].2.as_deref(), Some("2024-04-03"));
+    }
+
+    #[test]
+    fn test_utf16_positions_and_filters() {
+        let text = "📧 ops@example.org — 12%";
+        let entities = extract(text, &ExtractionOptions::default(), None);
+        assert_eq!(entities.len(), 2);
+        assert_eq!(entities[0].position, Position { start: 3, end: 18 });
+        assert_eq!(entities[0].id, "email-3-18");
+        assert_eq!(entities[1].position, Position { start: 21, end: 24 });
+
+        let options = ExtractionOptions { min_confidence: 0.98, ..Default::default() };
+        assert_eq!(extract(text, &options, None).len(), 1);
+        let json = serde_json::to_value(&entities[1]).unwrap();
+        assert_eq!(json["type"], "percentage");
+        assert_eq!(json["metadata"]["normalized"], "12%");
+    }
+
+    #[test]
+    fn test_sweep_keeps_the_best_of_overlapping_spans() {
+        let spans = vec![(12, 14, 1), (0, 4, 1), (2, 10, 1), (3, 6, 9), (10, 12, 1), (10, 12, 5)];
+        let by_length = |a: &(usize, usize, u32), b: &(usize, usize, u32)| (a.1 - a.0).cmp(&(b.1 - b.0)).then(a.2.cmp(&b.2));
+        let kept = sweep_overlaps(spans, |s| (s.0, s.1), by_length);
+        assert_eq!(kept, vec![(2, 10, 1), (10, 12, 5), (12, 14, 1)]);
+    }
+}
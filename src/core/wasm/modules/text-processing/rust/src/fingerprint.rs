@@ -0,0 +1,299 @@
+use std::collections::{HashMap, HashSet};
+use serde::{Serialize, Deserialize};
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::semantic::fnv1a;
+use crate::TextProcessingError;
+
+/// Seed of the MinHash permutations. Changing it invalidates every stored
+/// fingerprint.
+const MINHASH_SEED: u64 = 0x5EED_F1D6_E7A1_2024;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FingerprintOptions {
+    /// Words per shingle.
+    pub shingle_size: usize,
+    /// MinHash signature length.
+    pub permutations: usize,
+}
+
+impl Default for FingerprintOptions {
+    fn default() -> Self {
+        FingerprintOptions { shingle_size: 3, permutations: 128 }
+    }
+}
+
+/// Fingerprints in the shape of the TS `Fingerprint`. They only depend on
+/// the text and the options, so they can be stored and compared later.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Fingerprint {
+    /// 64-bit SimHash as 16 hex digits, since JS numbers cannot hold it.
+    pub simhash: String,
+    pub minhash: Vec<u32>,
+}
+
+impl Fingerprint {
+    /// Share of equal MinHash values, an estimate of the Jaccard similarity
+    /// of the shingle sets; 0 when either text has no words.
+    pub fn similarity(&self, other: &Fingerprint) -> f64 {
+        if self.minhash.is_empty() || self.minhash.len() != other.minhash.len() {
+            return 0.0;
+        }
+        let equal = self.minhash.iter().zip(&other.minhash).filter(|(a, b)| a == b).count();
+        equal as f64 / self.minhash.len() as f64
+    }
+}
+
+pub fn fingerprint(text: &str, options: &FingerprintOptions) -> Fingerprint {
+    let shingles = shingles(text, options.shingle_size.max(1));
+    Fingerprint {
+        simhash: format!("{:016x}", simhash(&shingles)),
+        minhash: if shingles.is_empty() { Vec::new() } else { minhash(shingles.keys().copied(), options.permutations) },
+    }
+}
+
+/// Hashes of the word shingles of `text` with their counts. Words are
+/// NFKC-normalized and lowercased; texts shorter than a shingle are one.
+fn shingles(text: &str, size: usize) -> HashMap<u64, usize> {
+    let words: Vec<String> = text.unicode_words().map(|w| w.nfkc().flat_map(char::to_lowercase).collect()).collect();
+    let mut shingles = HashMap::new();
+    if words.is_empty() {
+        return shingles;
+    }
+    for window in words.windows(size.min(words.len())) {
+        *shingles.entry(fnv1a(window.join(" ").as_bytes())).or_insert(0) += 1;
+    }
+    shingles
+}
+
+/// Each bit is the sign of the count-weighted vote of the shingles on it.
+fn simhash(shingles: &HashMap<u64, usize>) -> u64 {
+    let mut votes = [0i64; 64];
+    for (&shingle, &count) in shingles {
+        let hash = mix(shingle);
+        for (bit, vote) in votes.iter_mut().enumerate() {
+            *vote += if hash >> bit & 1 == 1 { count as i64 } else { -(count as i64) };
+        }
+    }
+    votes.iter().enumerate().filter(|(_, &v)| v > 0).fold(0, |hash, (bit, _)| hash | 1 << bit)
+}
+
+/// Minimum of each seeded hash over the shingles, which must not be empty.
+fn minhash(shingles: impl Iterator<Item = u64> + Clone, permutations: usize) -> Vec<u32> {
+    let mut seed = MINHASH_SEED;
+    (0..permutations)
+        .map(|_| {
+            seed = mix(seed);
+            shingles.clone().map(|s| (mix(s ^ seed) >> 32) as u32).min().unwrap_or_default()
+        })
+        .collect()
+}
+
+/// SplitMix64 finalizer.
+fn mix(x: u64) -> u64 {
+    let x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    let x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/// Bits that differ between two SimHashes, or `None` if either is malformed.
+pub fn hamming_distance(a: &str, b: &str) -> Option<u32> {
+    let a = u64::from_str_radix(a, 16).ok()?;
+    let b = u64::from_str_radix(b, 16).ok()?;
+    Some((a ^ b).count_ones())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct DuplicateIndexOptions {
+    /// Lowest estimated Jaccard similarity reported as a duplicate.
+    pub threshold: f64,
+}
+
+impl Default for DuplicateIndexOptions {
+    fn default() -> Self {
+        DuplicateIndexOptions { threshold: 0.8 }
+    }
+}
+
+/// A fingerprint similar to the one looked up.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Duplicate {
+    pub id: String,
+    pub similarity: f64,
+}
+
+/// Locality-sensitive hashing over MinHash signatures: signatures are cut
+/// into bands, and documents sharing any band are candidates, checked
+/// against the threshold. Bands are sized when the first signature arrives.
+pub struct DuplicateIndex {
+    threshold: f64,
+    rows: usize, // signature values per band
+    fingerprints: HashMap<String, Fingerprint>,
+    buckets: HashMap<(usize, u64), HashSet<String>>,
+}
+
+impl DuplicateIndex {
+    pub fn new(options: &DuplicateIndexOptions) -> Self {
+        DuplicateIndex {
+            threshold: options.threshold.clamp(0.0, 1.0),
+            rows: 0,
+            fingerprints: HashMap::new(),
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Adds the fingerprint of document `id`, replacing any it had. Documents
+    /// without words have an empty signature and are left out.
+    pub fn add(&mut self, id: &str, fingerprint: Fingerprint) -> Result<(), TextProcessingError> {
+        if fingerprint.minhash.is_empty() {
+            self.remove(id);
+            return Ok(());
+        }
+        if self.fingerprints.is_empty() {
+            self.rows = rows_per_band(fingerprint.minhash.len(), self.threshold);
+        }
+        self.check(&fingerprint)?;
+        self.remove(id);
+        for key in self.bands(&fingerprint) {
+            self.buckets.entry(key).or_default().insert(id.to_string());
+        }
+        self.fingerprints.insert(id.to_string(), fingerprint);
+        Ok(())
+    }
+
+    /// Removes document `id`; returns whether it was there.
+    pub fn remove(&mut self, id: &str) -> bool {
+        let Some(fingerprint) = self.fingerprints.remove(id) else {
+            return false;
+        };
+        for key in self.bands(&fingerprint) {
+            if let Some(bucket) = self.buckets.get_mut(&key) {
+                bucket.remove(id);
+                if bucket.is_empty() {
+                    self.buckets.remove(&key);
+                }
+            }
+        }
+        true
+    }
+
+    /// Documents at least `threshold` similar to `fingerprint`, most similar first.
+    pub fn find(&self, fingerprint: &Fingerprint) -> Result<Vec<Duplicate>, TextProcessingError> {
+        if self.fingerprints.is_empty() || fingerprint.minhash.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.check(fingerprint)?;
+        let candidates: HashSet<&String> = self
+            .bands(fingerprint)
+            .filter_map(|key| self.buckets.get(&key))
+            .flatten()
+            .collect();
+        let mut duplicates: Vec<Duplicate> = candidates
+            .into_iter()
+            .map(|id| Duplicate { id: id.clone(), similarity: self.fingerprints[id].similarity(fingerprint) })
+            .filter(|d| d.similarity >= self.threshold)
+            .collect();
+        duplicates.sort_by(|a, b| b.similarity.total_cmp(&a.similarity).then_with(|| a.id.cmp(&b.id)));
+        Ok(duplicates)
+    }
+
+    fn check(&self, fingerprint: &Fingerprint) -> Result<(), TextProcessingError> {
+        match self.fingerprints.values().next() {
+            Some(known) if known.minhash.len() != fingerprint.minhash.len() => Err(TextProcessingError::InvalidOptions(format!(
+                "fingerprint has {} MinHash values but the index has {}",
+                fingerprint.minhash.len(),
+                known.minhash.len()
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn bands<'a>(&self, fingerprint: &'a Fingerprint) -> impl Iterator<Item = (usize, u64)> + 'a {
+        fingerprint.minhash.chunks_exact(self.rows).enumerate().map(|(band, values)| {
+            let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+            (band, fnv1a(&bytes))
+        })
+    }
+}
+
+/// Rows per band whose S-curve `(1/b)^(1/r)` is closest to `threshold`.
+fn rows_per_band(permutations: usize, threshold: f64) -> usize {
+    (1..=permutations.max(1))
+        .min_by(|&a, &b| {
+            let error = |rows: usize| {
+                let bands = (permutations / rows).max(1) as f64;
+                ((1.0 / bands).powf(1.0 / rows as f64) - threshold).abs()
+            };
+            error(a).total_cmp(&error(b))
+        })
+        .unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1: &str = "This Services Agreement is entered into by Acme Corporation and Globex Limited. \
+        Acme shall deliver the software described in Schedule A within ninety days. \
+        Globex shall pay the fees listed in Schedule B within thirty days of each invoice. \
+        Either party may terminate this agreement with sixty days written notice.";
+    const V2: &str = "This Services Agreement is entered into by Acme Corporation and Globex Limited. \
+        Acme shall deliver the software described in Schedule A within ninety days. \
+        Globex shall pay the fees listed in Schedule B within forty five days of each invoice. \
+        Either party may terminate this agreement with sixty days written notice.";
+    const OTHER: &str = "Minutes of the quarterly board meeting. The board approved the budget \
+        for the new warehouse and asked management to report on hiring plans next quarter.";
+
+    #[test]
+    fn test_fingerprints_are_stable() {
+        let options = FingerprintOptions::default();
+        let first = fingerprint(V1, &options);
+        assert_eq!(first, fingerprint(V1, &options));
+        assert_eq!(first.minhash.len(), 128);
+        assert_eq!(first.simhash.len(), 16);
+        // Pinned so a change to hashing shows up as a failure, not as stale stored fingerprints
+        assert_eq!(fingerprint("Invoice 42 is overdue.", &options).simhash, "1821209206802050");
+        assert!(fingerprint("", &options).minhash.is_empty());
+    }
+
+    #[test]
+    fn test_near_duplicates_are_close() {
+        let options = FingerprintOptions::default();
+        let (v1, v2, other) = (fingerprint(V1, &options), fingerprint(V2, &options), fingerprint(OTHER, &options));
+        assert!(v1.similarity(&v2) > 0.7, "{}", v1.similarity(&v2));
+        assert!(v1.similarity(&other) < 0.1);
+        // Texts without words are not alike, not even to each other
+        let (blank, dashes) = (fingerprint("  ", &options), fingerprint("— … —", &options));
+        assert_eq!((blank.similarity(&dashes), blank.similarity(&v1)), (0.0, 0.0));
+        assert!(hamming_distance(&v1.simhash, &v2.simhash).unwrap() < hamming_distance(&v1.simhash, &other.simhash).unwrap());
+        assert_eq!(hamming_distance(&v1.simhash, "not hex"), None);
+
+        // Case and compatibility forms do not matter
+        assert_eq!(fingerprint("ＡＣＭＥ shall DELIVER", &options), fingerprint("acme shall deliver", &options));
+    }
+
+    #[test]
+    fn test_index_finds_candidates_above_threshold() {
+        let options = FingerprintOptions::default();
+        let mut index = DuplicateIndex::new(&DuplicateIndexOptions { threshold: 0.7 });
+        index.add("v1", fingerprint(V1, &options)).unwrap();
+        index.add("other", fingerprint(OTHER, &options)).unwrap();
+
+        let found = index.find(&fingerprint(V2, &options)).unwrap();
+        assert_eq!(found.iter().map(|d| d.id.as_str()).collect::<Vec<_>>(), vec!["v1"]);
+        assert!(index.find(&fingerprint("Unrelated text about lunch.", &options)).unwrap().is_empty());
+
+        assert!(index.remove("v1"));
+        assert!(index.find(&fingerprint(V2, &options)).unwrap().is_empty());
+        let short = FingerprintOptions { permutations: 64, ..Default::default() };
+        assert!(index.add("v2", fingerprint(V2, &short)).is_err());
+
+        index.add("blank", fingerprint("", &options)).unwrap();
+        index.add("dashes", fingerprint("— … —", &options)).unwrap();
+        assert!(index.find(&fingerprint("***", &options)).unwrap().is_empty());
+    }
+}
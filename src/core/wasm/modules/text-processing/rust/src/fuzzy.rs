@@ -0,0 +1,459 @@
+use serde::{Serialize, Deserialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::api::IdentifiedChunk;
+use crate::extraction::{Position, Utf16Offsets};
+use crate::search::SearchIndex;
+use crate::TextProcessingError;
+
+/// Options in the shape of the TS `FuzzyOptions`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FuzzyOptions {
+    /// Edits allowed; scales with the length of the term when unset.
+    pub max_distance: Option<usize>,
+    /// Counts swapping two adjacent characters as one edit rather than two.
+    pub transpositions: bool,
+    pub limit: usize,
+}
+
+impl Default for FuzzyOptions {
+    fn default() -> Self {
+        FuzzyOptions { max_distance: None, transpositions: true, limit: 10 }
+    }
+}
+
+impl FuzzyOptions {
+    /// No edits up to 2 characters, one up to 5, two beyond.
+    fn max_distance(&self, length: usize) -> usize {
+        self.max_distance.unwrap_or(match length {
+            0..=2 => 0,
+            3..=5 => 1,
+            _ => 2,
+        })
+    }
+}
+
+/// A dictionary term within reach of a fuzzy lookup.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FuzzyTerm {
+    pub term: String,
+    pub distance: usize,
+    pub frequency: u64,
+}
+
+/// An approximate occurrence of a pattern, in UTF-16 code units of the chunk text.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FuzzyMatch {
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+    pub distance: usize,
+}
+
+/// An indexed chunk with its approximate occurrences of a pattern.
+#[derive(Serialize)]
+pub struct FuzzyHit<'a> {
+    pub chunk: &'a IdentifiedChunk,
+    pub distance: usize, // of the closest match
+    pub matches: Vec<FuzzyMatch>,
+}
+
+/// A misspelled query word and its replacement.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Correction {
+    pub word: String,
+    pub suggestion: String,
+    pub distance: usize,
+    pub position: Position,
+}
+
+/// The query with every correctable word replaced.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub text: String,
+    pub corrections: Vec<Correction>,
+}
+
+/// Edit distance between `a` and `b` in characters: Levenshtein, or the
+/// optimal string alignment variant of Damerau-Levenshtein with `transpositions`.
+pub fn distance(a: &str, b: &str, transpositions: bool) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut before: Vec<usize> = Vec::new();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (row[j - 1] + 1).min(previous[j] + 1).min(previous[j - 1] + cost);
+            if transpositions && i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(before[j - 2] + 1);
+            }
+        }
+        before = std::mem::replace(&mut previous, row);
+    }
+    previous[b.len()]
+}
+
+/// Lowercased terms and their frequencies in a trie, so that a fuzzy lookup
+/// walks the trie as a Levenshtein automaton and abandons every branch
+/// already further than the allowed distance.
+pub struct TermDictionary {
+    nodes: Vec<Node>, // the root first
+}
+
+#[derive(Default)]
+struct Node {
+    children: Vec<(char, u32)>, // sorted by char
+    frequency: u64,             // 0 unless a term ends here
+}
+
+impl Default for TermDictionary {
+    fn default() -> Self {
+        TermDictionary { nodes: vec![Node::default()] }
+    }
+}
+
+impl TermDictionary {
+    pub fn add(&mut self, term: &str, count: u64) {
+        let mut node = 0;
+        for c in term.chars() {
+            node = match self.nodes[node].children.binary_search_by_key(&c, |&(c, _)| c) {
+                Ok(i) => self.nodes[node].children[i].1 as usize,
+                Err(i) => {
+                    let child = self.nodes.len();
+                    self.nodes.push(Node::default());
+                    self.nodes[node].children.insert(i, (c, child as u32));
+                    child
+                }
+            };
+        }
+        self.nodes[node].frequency += count;
+    }
+
+    /// Takes `count` occurrences of `term` back out; branches stay in the trie.
+    pub fn remove(&mut self, term: &str, count: u64) {
+        if let Some(node) = self.find(term) {
+            let frequency = &mut self.nodes[node].frequency;
+            *frequency = frequency.saturating_sub(count);
+        }
+    }
+
+    pub fn frequency(&self, term: &str) -> u64 {
+        self.find(term).map_or(0, |node| self.nodes[node].frequency)
+    }
+
+    fn find(&self, term: &str) -> Option<usize> {
+        term.chars().try_fold(0, |node, c| {
+            let children = &self.nodes[node].children;
+            let i = children.binary_search_by_key(&c, |&(c, _)| c).ok()?;
+            Some(children[i].1 as usize)
+        })
+    }
+
+    /// The terms within `options` edits of `term`, closest and then most
+    /// frequent first.
+    pub fn search(&self, term: &str, options: &FuzzyOptions) -> Vec<FuzzyTerm> {
+        let query: Vec<char> = term.to_lowercase().chars().collect();
+        let mut walk = Walk {
+            dictionary: self,
+            query: &query,
+            max_distance: options.max_distance(query.len()),
+            transpositions: options.transpositions,
+            prefix: String::new(),
+            found: Vec::new(),
+        };
+        let first: Vec<usize> = (0..=query.len()).collect();
+        walk.descend(0, None, &[], &first);
+
+        let mut found = walk.found;
+        found.sort_by(|a, b| {
+            a.distance.cmp(&b.distance).then(b.frequency.cmp(&a.frequency)).then_with(|| a.term.cmp(&b.term))
+        });
+        found.truncate(options.limit);
+        found
+    }
+}
+
+/// One depth-first lookup; each trie edge adds a row of the edit distance
+/// table between the query and the prefix spelled so far.
+struct Walk<'a> {
+    dictionary: &'a TermDictionary,
+    query: &'a [char],
+    max_distance: usize,
+    transpositions: bool,
+    prefix: String,
+    found: Vec<FuzzyTerm>,
+}
+
+impl Walk<'_> {
+    fn descend(&mut self, node: usize, last: Option<char>, before: &[usize], previous: &[usize]) {
+        let (dictionary, query) = (self.dictionary, self.query);
+        for &(c, child) in &dictionary.nodes[node].children {
+            let mut row = vec![previous[0] + 1; query.len() + 1];
+            for j in 1..=query.len() {
+                let cost = usize::from(query[j - 1] != c);
+                row[j] = (row[j - 1] + 1).min(previous[j] + 1).min(previous[j - 1] + cost);
+                if self.transpositions && j > 1 && last == Some(query[j - 1]) && c == query[j - 2] {
+                    row[j] = row[j].min(before[j - 2] + 1);
+                }
+            }
+
+            // A transposition reaches back two rows, but to a cell at most one
+            // less than a cell of `row`, so the row minimum still bounds every
+            // term below
+            if row.iter().min().is_some_and(|&d| d > self.max_distance) {
+                continue;
+            }
+            self.prefix.push(c);
+            let frequency = dictionary.nodes[child as usize].frequency;
+            if frequency > 0 && row[query.len()] <= self.max_distance {
+                self.found.push(FuzzyTerm { term: self.prefix.clone(), distance: row[query.len()], frequency });
+            }
+            self.descend(child as usize, Some(c), previous, &row);
+            self.prefix.pop();
+        }
+    }
+}
+
+/// Approximate occurrences of `pattern` in the text of every indexed chunk,
+/// ignoring case; chunks with the closest matches come first.
+pub fn search<'a>(
+    index: &'a SearchIndex,
+    pattern: &str,
+    options: &FuzzyOptions,
+) -> Result<Vec<FuzzyHit<'a>>, TextProcessingError> {
+    let pattern: Vec<char> = pattern.chars().map(lowercase).collect();
+    let max_distance = options.max_distance(pattern.len());
+    if max_distance >= pattern.len() {
+        return Err(TextProcessingError::InvalidOptions(
+            "maxDistance must be less than the length of the pattern".to_string(),
+        ));
+    }
+
+    let mut hits: Vec<FuzzyHit> = index
+        .chunks()
+        .filter_map(|chunk| {
+            let matches = find(&chunk.chunk.text, &pattern, max_distance, options.transpositions);
+            let distance = matches.iter().map(|m| m.distance).min()?;
+            Some(FuzzyHit { chunk, distance, matches })
+        })
+        .collect();
+    hits.sort_by(|a, b| a.distance.cmp(&b.distance).then(b.matches.len().cmp(&a.matches.len())));
+    hits.truncate(options.limit);
+    Ok(hits)
+}
+
+/// Non-overlapping substrings of `text` within `max_distance` edits of
+/// `pattern`, found with Sellers' algorithm: the edit distance table starts
+/// afresh at every character, and each cell carries where its alignment began.
+fn find(text: &str, pattern: &[char], max_distance: usize, transpositions: bool) -> Vec<FuzzyMatch> {
+    let offsets: Vec<usize> = text.char_indices().map(|(offset, _)| offset).chain([text.len()]).collect();
+    let chars: Vec<char> = text.chars().map(lowercase).collect();
+    let m = pattern.len();
+
+    let mut candidates: Vec<(usize, usize, usize)> = Vec::new(); // (distance, start, end) in chars
+    let mut before: Vec<(usize, usize)> = Vec::new();
+    let mut previous: Vec<(usize, usize)> = (0..=m).map(|i| (i, 0)).collect(); // (distance, start)
+    for j in 1..=chars.len() {
+        let mut column = vec![(0, j); m + 1];
+        for i in 1..=m {
+            let cost = usize::from(pattern[i - 1] != chars[j - 1]);
+            let mut cell = best(previous[i - 1].0 + cost, previous[i - 1].1, (previous[i].0 + 1, previous[i].1));
+            cell = best(column[i - 1].0 + 1, column[i - 1].1, cell);
+            if transpositions && i > 1 && j > 1 && pattern[i - 1] == chars[j - 2] && pattern[i - 2] == chars[j - 1] {
+                cell = best(before[i - 2].0 + 1, before[i - 2].1, cell);
+            }
+            column[i] = cell;
+        }
+        if column[m].0 <= max_distance {
+            candidates.push((column[m].0, column[m].1, j));
+        }
+        before = std::mem::replace(&mut previous, column);
+    }
+
+    // Closest and then shortest first, each claiming its characters
+    candidates.sort_by_key(|&(distance, start, end)| (distance, end - start, start));
+    let mut taken: Vec<(usize, usize, usize)> = Vec::new();
+    for candidate in candidates {
+        if taken.iter().all(|&(_, start, end)| candidate.2 <= start || candidate.1 >= end) {
+            taken.push(candidate);
+        }
+    }
+    taken.sort_by_key(|&(_, start, _)| start);
+
+    let mut utf16 = Utf16Offsets::new(text);
+    taken
+        .into_iter()
+        .map(|(distance, start, end)| FuzzyMatch {
+            text: text[offsets[start]..offsets[end]].to_string(),
+            start: utf16.at(offsets[start]),
+            end: utf16.at(offsets[end]),
+            distance,
+        })
+        .collect()
+}
+
+/// The lower of two (distance, start) cells; on a tie, the later start.
+fn best(distance: usize, start: usize, other: (usize, usize)) -> (usize, usize) {
+    if distance < other.0 || (distance == other.0 && start > other.1) {
+        (distance, start)
+    } else {
+        other
+    }
+}
+
+/// Lowercases one character to one character, keeping offsets aligned.
+fn lowercase(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => l,
+        _ => c,
+    }
+}
+
+/// "Did you mean": replaces each word of `query` missing from `dictionary`
+/// with its closest, most frequent neighbour. `None` when nothing changes.
+pub fn suggest(query: &str, dictionary: &TermDictionary, options: &FuzzyOptions) -> Option<Suggestion> {
+    let mut utf16 = Utf16Offsets::new(query);
+    let mut text = String::with_capacity(query.len());
+    let mut corrections = Vec::new();
+    let mut copied = 0;
+    for (offset, word) in query.unicode_word_indices() {
+        let lower = word.to_lowercase();
+        if word.chars().any(|c| c.is_numeric()) || dictionary.frequency(&lower) > 0 {
+            continue;
+        }
+        let Some(best) = dictionary.search(&lower, &FuzzyOptions { limit: 1, ..options.clone() }).pop() else {
+            continue;
+        };
+        let suggestion = match_case(word, &best.term);
+        text.push_str(&query[copied..offset]);
+        text.push_str(&suggestion);
+        copied = offset + word.len();
+        corrections.push(Correction {
+            word: word.to_string(),
+            suggestion,
+            distance: best.distance,
+            position: Position { start: utf16.at(offset), end: utf16.at(copied) },
+        });
+    }
+    if corrections.is_empty() {
+        return None;
+    }
+    text.push_str(&query[copied..]);
+    Some(Suggestion { text, corrections })
+}
+
+/// `term` in the capitalization of `word`: all caps, initial capital or as is.
+fn match_case(word: &str, term: &str) -> String {
+    let mut chars = word.chars();
+    let first = chars.next().is_some_and(char::is_uppercase);
+    if first && word.chars().count() > 1 && chars.all(|c| !c.is_lowercase()) {
+        return term.to_uppercase();
+    }
+    let mut term_chars = term.chars();
+    match term_chars.next() {
+        Some(c) if first => c.to_uppercase().chain(term_chars).collect(),
+        _ => term.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dictionary() -> TermDictionary {
+        let mut dictionary = TermDictionary::default();
+        for (term, frequency) in [
+            ("invoice", 40), ("invoices", 12), ("involve", 3), ("payment", 25), ("terms", 18),
+            ("team", 30), ("tame", 1), ("the", 200), ("contract", 22), ("größe", 2),
+        ] {
+            dictionary.add(term, frequency);
+        }
+        dictionary
+    }
+
+    #[test]
+    fn test_distance_and_dictionary_lookup() {
+        assert_eq!(distance("kitten", "sitting", false), 3);
+        assert_eq!(distance("invoice", "inovice", false), 2);
+        assert_eq!(distance("invoice", "inovice", true), 1);
+        assert_eq!(distance("ca", "abc", true), 3); // optimal string alignment, not unrestricted
+        assert_eq!(distance("", "größe", true), 5);
+
+        let dictionary = dictionary();
+        let terms = |term: &str, options: &FuzzyOptions| -> Vec<(String, usize)> {
+            dictionary.search(term, options).into_iter().map(|t| (t.term, t.distance)).collect()
+        };
+        let two = FuzzyOptions { max_distance: Some(2), ..Default::default() };
+        assert_eq!(
+            terms("Inovice", &two),
+            vec![("invoice".to_string(), 1), ("invoices".to_string(), 2)],
+        );
+        let levenshtein = FuzzyOptions { max_distance: Some(1), transpositions: false, ..Default::default() };
+        assert!(terms("inovice", &levenshtein).is_empty());
+        // Two edits allowed for long terms, none for short ones
+        assert_eq!(terms("contrcat", &FuzzyOptions::default()), vec![("contract".to_string(), 1)]);
+        assert!(terms("te", &FuzzyOptions::default()).is_empty());
+        assert_eq!(terms("grösse", &FuzzyOptions::default()), vec![("größe".to_string(), 2)]);
+
+        // Every term the brute force finds, and in the same order
+        let all = ["invoice", "invoices", "involve", "payment", "terms", "team", "tame", "the", "contract", "größe"];
+        for query in ["taem", "invoic", "payemnt", "thme", "x"] {
+            let mut expected: Vec<(String, usize)> = all
+                .iter()
+                .map(|t| (t.to_string(), distance(query, t, true)))
+                .filter(|(_, d)| *d <= 2)
+                .collect();
+            expected.sort_by_key(|(t, d)| (*d, std::cmp::Reverse(dictionary.frequency(t))));
+            assert_eq!(terms(query, &FuzzyOptions { limit: 100, ..two.clone() }), expected, "{query}");
+        }
+    }
+
+    #[test]
+    fn test_substring_matches() {
+        let text = "Tne invioce for Größe 7 and the invoice total are attached; see invocie №2.";
+        let pattern: Vec<char> = "invoice".chars().collect();
+        let matches = find(text, &pattern, 1, true);
+        let utf16: Vec<u16> = text.encode_utf16().collect();
+        let found: Vec<(String, usize)> = matches
+            .iter()
+            .map(|m| (String::from_utf16(&utf16[m.start..m.end]).unwrap(), m.distance))
+            .collect();
+        assert_eq!(
+            found,
+            vec![("invioce".to_string(), 1), ("invoice".to_string(), 0), ("invocie".to_string(), 1)],
+        );
+        assert!(matches.iter().zip(&found).all(|(m, f)| m.text == f.0));
+        assert_eq!(find(text, &pattern, 1, false).len(), 1);
+
+        let pattern: Vec<char> = "grösse".chars().collect();
+        assert_eq!(find(text, &pattern, 2, true)[0].text, "Größe");
+    }
+
+    #[test]
+    fn test_did_you_mean() {
+        let mut dictionary = dictionary();
+        let query = "Unpaid Invocie for the TAEM, per contrcat 42";
+        let suggestion = suggest(query, &dictionary, &FuzzyOptions::default()).unwrap();
+        assert_eq!(suggestion.text, "Unpaid Invoice for the TEAM, per contract 42");
+        let words: Vec<(&str, &str, usize)> = suggestion
+            .corrections
+            .iter()
+            .map(|c| (c.word.as_str(), c.suggestion.as_str(), c.distance))
+            .collect();
+        assert_eq!(words, vec![("Invocie", "Invoice", 1), ("TAEM", "TEAM", 1), ("contrcat", "contract", 1)]);
+        assert_eq!(suggestion.corrections[0].position, Position { start: 7, end: 14 });
+
+        // Frequency breaks ties between equally close terms
+        dictionary.add("tame", 100);
+        assert_eq!(suggest("taem", &dictionary, &FuzzyOptions::default()).unwrap().text, "tame");
+        dictionary.remove("tame", 100);
+        assert_eq!(suggest("taem", &dictionary, &FuzzyOptions::default()).unwrap().text, "team");
+
+        assert_eq!(suggest("the invoice terms", &dictionary, &FuzzyOptions::default()), None);
+        assert_eq!(suggest("", &dictionary, &FuzzyOptions::default()), None);
+    }
+}
@@ -0,0 +1,285 @@
+use aho_corasick::{AhoCorasick, BuildError};
+use serde::{Serialize, Deserialize};
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::extraction::{EntityKind, EntityMetadata, EntitySpan};
+
+/// Confidence of a match that equals the dictionary term as written.
+const EXACT_CONFIDENCE: f64 = 1.0;
+/// Confidence of a match that only equals it after folding.
+const FOLDED_CONFIDENCE: f64 = 0.9;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GazetteerOptions {
+    /// Match regardless of letter case.
+    pub case_insensitive: bool,
+    /// Match after NFKC normalization, so ligatures and full-width forms count.
+    pub normalize: bool,
+    /// Only match terms that are not part of a longer word.
+    pub whole_words: bool,
+    /// Report only the longest of overlapping matches; otherwise report all.
+    pub longest_match: bool,
+}
+
+impl Default for GazetteerOptions {
+    fn default() -> Self {
+        GazetteerOptions {
+            case_insensitive: true,
+            normalize: true,
+            whole_words: true,
+            longest_match: true,
+        }
+    }
+}
+
+/// A user dictionary in the shape of the TS `GazetteerDictionary`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Dictionary {
+    pub name: String,
+    #[serde(default = "default_entity_type")]
+    pub entity_type: EntityKind,
+    pub terms: Vec<Term>,
+}
+
+fn default_entity_type() -> EntityKind {
+    EntityKind::Custom
+}
+
+/// A dictionary term, optionally an alias of a canonical name.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum Term {
+    Plain(String),
+    Alias { term: String, canonical: Option<String> },
+}
+
+impl Term {
+    fn text(&self) -> &str {
+        match self {
+            Term::Plain(term) | Term::Alias { term, .. } => term,
+        }
+    }
+
+    fn canonical(&self) -> &str {
+        match self {
+            Term::Alias { canonical: Some(canonical), .. } => canonical,
+            _ => self.text(),
+        }
+    }
+}
+
+/// A folded pattern and where it came from.
+struct Pattern {
+    dictionary: usize,
+    term: String, // as written
+    canonical: String,
+}
+
+/// All loaded dictionaries compiled into one automaton, so the text is
+/// scanned once however many dictionaries and terms there are.
+pub struct Gazetteer {
+    automaton: AhoCorasick,
+    patterns: Vec<Pattern>,
+    names: Vec<(String, EntityKind)>,
+    options: GazetteerOptions,
+}
+
+impl Gazetteer {
+    pub fn new(dictionaries: Vec<Dictionary>, options: GazetteerOptions) -> Result<Self, BuildError> {
+        let mut patterns = Vec::new();
+        let mut folded = Vec::new();
+        let mut names = Vec::with_capacity(dictionaries.len());
+        for (index, dictionary) in dictionaries.into_iter().enumerate() {
+            for term in &dictionary.terms {
+                let key = fold(term.text(), &options).text;
+                if key.trim().is_empty() {
+                    continue;
+                }
+                folded.push(key);
+                patterns.push(Pattern {
+                    dictionary: index,
+                    term: term.text().to_string(),
+                    canonical: term.canonical().to_string(),
+                });
+            }
+            names.push((dictionary.name, dictionary.entity_type));
+        }
+
+        // Overlapping matches need the standard match kind; the longest is
+        // chosen in `find` once the filters have run
+        let automaton = AhoCorasick::new(&folded)?;
+        Ok(Gazetteer { automaton, patterns, names, options })
+    }
+
+    pub fn longest_match(&self) -> bool {
+        self.options.longest_match
+    }
+
+    /// Dictionary matches in `text`, in byte offsets of `text`.
+    pub fn find(&self, text: &str, kinds: &[EntityKind]) -> Vec<EntitySpan> {
+        let folded = fold(text, &self.options);
+        let mut matches = Vec::new(); // (start, end, pattern)
+        for m in self.automaton.find_overlapping_iter(&folded.text) {
+            // Folding may merge characters; only whole graphemes map back
+            let (Some(start), Some(end)) = (folded.original(m.start()), folded.original(m.end())) else {
+                continue;
+            };
+            let pattern = m.pattern().as_usize();
+            let kind = &self.names[self.patterns[pattern].dictionary].1;
+            if !kinds.is_empty() && !kinds.contains(kind) {
+                continue;
+            }
+            if self.options.whole_words && !is_whole_word(text, start, end) {
+                continue;
+            }
+            matches.push((start, end, pattern));
+        }
+
+        if self.options.longest_match {
+            // Leftmost-longest among the matches that passed, so that a
+            // rejected "Acme Corp" in "Acme Corporation" leaves "Acme"
+            matches.sort_unstable_by_key(|&(start, end, pattern)| (start, std::cmp::Reverse(end), pattern));
+            let mut covered = 0;
+            matches.retain(|&(start, end, _)| {
+                let keep = start >= covered;
+                if keep {
+                    covered = end;
+                }
+                keep
+            });
+        }
+
+        matches
+            .into_iter()
+            .map(|(start, end, pattern)| {
+                let pattern = &self.patterns[pattern];
+                let (name, kind) = &self.names[pattern.dictionary];
+                EntitySpan {
+                    kind: *kind,
+                    start,
+                    end,
+                    confidence: if text[start..end] == pattern.term { EXACT_CONFIDENCE } else { FOLDED_CONFIDENCE },
+                    metadata: EntityMetadata {
+                        normalized: Some(pattern.canonical.clone()),
+                        dictionary: Some(name.clone()),
+                        ..Default::default()
+                    },
+                }
+            })
+            .collect()
+    }
+}
+
+/// Text folded grapheme by grapheme, with the folded offset of each
+/// grapheme mapped to its original offset.
+struct Folded {
+    text: String,
+    boundaries: Vec<(usize, usize)>, // (folded, original), ascending, ending at both lengths
+}
+
+impl Folded {
+    fn original(&self, folded: usize) -> Option<usize> {
+        self.boundaries
+            .binary_search_by_key(&folded, |&(f, _)| f)
+            .ok()
+            .map(|i| self.boundaries[i].1)
+    }
+}
+
+fn fold(text: &str, options: &GazetteerOptions) -> Folded {
+    let mut folded = Folded { text: String::with_capacity(text.len()), boundaries: Vec::new() };
+    for (offset, grapheme) in text.grapheme_indices(true) {
+        folded.boundaries.push((folded.text.len(), offset));
+        match (options.normalize, options.case_insensitive) {
+            (true, true) => folded.text.extend(grapheme.nfkc().flat_map(char::to_lowercase)),
+            (true, false) => folded.text.extend(grapheme.nfkc()),
+            (false, true) => folded.text.extend(grapheme.chars().flat_map(char::to_lowercase)),
+            (false, false) => folded.text.push_str(grapheme),
+        }
+    }
+    folded.boundaries.push((folded.text.len(), text.len()));
+    folded
+}
+
+/// Whether `text[start..end]` is not part of a longer word. Edges that are
+/// punctuation, as in "Inc.", need no boundary.
+fn is_whole_word(text: &str, start: usize, end: usize) -> bool {
+    let word = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+    let matched = &text[start..end];
+    let joined_before = word(matched.chars().next()) && word(text[..start].chars().next_back());
+    let joined_after = word(matched.chars().next_back()) && word(text[end..].chars().next());
+    !joined_before && !joined_after
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dictionary(name: &str, entity_type: EntityKind, terms: &[&str]) -> Dictionary {
+        Dictionary {
+            name: name.to_string(),
+            entity_type,
+            terms: terms.iter().map(|t| Term::Plain(t.to_string())).collect(),
+        }
+    }
+
+    fn matched(gazetteer: &Gazetteer, text: &str) -> Vec<String> {
+        gazetteer.find(text, &[]).iter().map(|s| text[s.start..s.end].to_string()).collect()
+    }
+
+    #[test]
+    fn test_folding_and_longest_match() {
+        let gazetteer = Gazetteer::new(vec![
+            dictionary("customers", EntityKind::Organization, &["Acme", "Acme Corp", "Ｆｏｏ Ｌｔｄ"]),
+            dictionary("cities", EntityKind::Location, &["Zürich"]),
+        ], GazetteerOptions::default()).unwrap();
+
+        let text = "ACME CORP and Foo Ltd opened in Zu\u{308}rich; acmeville is unrelated.";
+        assert_eq!(matched(&gazetteer, text), vec!["ACME CORP", "Foo Ltd", "Zu\u{308}rich"]);
+
+        let spans = gazetteer.find(text, &[EntityKind::Location]);
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].metadata.dictionary.as_deref(), Some("cities"));
+        assert_eq!(spans[0].confidence, FOLDED_CONFIDENCE);
+    }
+
+    #[test]
+    fn test_options() {
+        let terms = dictionary("products", EntityKind::Custom, &["Widget", "Widget Pro"]);
+        let strict = GazetteerOptions { case_insensitive: false, whole_words: false, longest_match: false, ..Default::default() };
+        let gazetteer = Gazetteer::new(vec![terms.clone()], strict).unwrap();
+        assert_eq!(matched(&gazetteer, "Widget Pro, widget, Widgets"), vec!["Widget", "Widget Pro", "Widget"]);
+
+        let gazetteer = Gazetteer::new(vec![terms], GazetteerOptions::default()).unwrap();
+        assert_eq!(matched(&gazetteer, "Widget Pro, widget, Widgets"), vec!["Widget Pro", "widget"]);
+    }
+
+    #[test]
+    fn test_rejected_longer_term_leaves_its_prefix() {
+        let gazetteer = Gazetteer::new(vec![
+            dictionary("customers", EntityKind::Organization, &["Acme", "Acme Corp"]),
+            dictionary("products", EntityKind::Custom, &["Acme Corporation Suite"]),
+        ], GazetteerOptions::default()).unwrap();
+        // "Acme Corp" is part of the longer word "Corporation"
+        assert_eq!(matched(&gazetteer, "Acme Corporation signed."), vec!["Acme"]);
+        // A longer match of another kind does not hide it either
+        let spans = gazetteer.find("Acme Corporation Suite", &[EntityKind::Organization]);
+        assert_eq!(spans.iter().map(|s| (s.start, s.end)).collect::<Vec<_>>(), vec![(0, 4)]);
+        assert_eq!(matched(&gazetteer, "Acme Corp, Acme Corporation Suite"), vec!["Acme Corp", "Acme Corporation Suite"]);
+    }
+
+    #[test]
+    fn test_aliases() {
+        let json = r#"[{"name": "customers", "entityType": "organization",
+            "terms": ["Initech", {"term": "IBM", "canonical": "International Business Machines"}]}]"#;
+        let dictionaries: Vec<Dictionary> = serde_json::from_str(json).unwrap();
+        let gazetteer = Gazetteer::new(dictionaries, GazetteerOptions::default()).unwrap();
+        let spans = gazetteer.find("Initech bought IBM.", &[]);
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].confidence, EXACT_CONFIDENCE);
+        assert_eq!(spans[1].metadata.normalized.as_deref(), Some("International Business Machines"));
+    }
+}
@@ -0,0 +1,637 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use serde::{Serialize, Deserialize};
+use serde_json::{Map, Value};
+
+use crate::TextProcessingError;
+
+/// First byte of a snapshot, bumped whenever its layout changes.
+const FORMAT_VERSION: u8 = 1;
+/// Highest layer a node can be assigned to.
+const MAX_LEVEL: usize = 16;
+/// Below this many nodes, deleted ones are never swept out.
+const MIN_REBUILD_SIZE: usize = 1024;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Metric {
+    /// `1 - cosine similarity`; vectors are normalized on insert.
+    #[default]
+    Cosine,
+    /// Negated dot product.
+    Dot,
+    /// Euclidean distance.
+    L2,
+}
+
+impl Metric {
+    /// Distance used inside the graph; L2 is left squared.
+    fn distance(self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            Metric::Cosine => 1.0 - dot(a, b),
+            Metric::Dot => -dot(a, b),
+            Metric::L2 => a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum(),
+        }
+    }
+
+    fn reported(self, distance: f32) -> f32 {
+        match self {
+            Metric::L2 => distance.sqrt(),
+            _ => distance,
+        }
+    }
+}
+
+/// Index options in the shape of the TS `VectorIndexOptions`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VectorIndexOptions {
+    pub metric: Metric,
+    /// Links per node on the upper layers; twice as many on the bottom one.
+    pub m: usize,
+    /// Candidates considered when linking a new node.
+    pub ef_construction: usize,
+    /// Candidates considered per query unless the query says otherwise.
+    pub ef_search: usize,
+}
+
+impl Default for VectorIndexOptions {
+    fn default() -> Self {
+        VectorIndexOptions {
+            metric: Metric::Cosine,
+            m: 16,
+            ef_construction: 200,
+            ef_search: 64,
+        }
+    }
+}
+
+/// A condition on one metadata field: a plain value to equal (or, for an
+/// array field, to contain), or comparison operators that must all hold.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum Condition {
+    Operators(Operators),
+    Equals(Value),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Operators {
+    pub eq: Option<Value>,
+    pub ne: Option<Value>,
+    #[serde(rename = "in")]
+    pub one_of: Option<Vec<Value>>,
+    pub gt: Option<f64>,
+    pub gte: Option<f64>,
+    pub lt: Option<f64>,
+    pub lte: Option<f64>,
+}
+
+impl Condition {
+    fn matches(&self, field: Option<&Value>) -> bool {
+        let equals = |expected: &Value| match field {
+            Some(Value::Array(items)) if !expected.is_array() => items.contains(expected),
+            Some(value) => value == expected,
+            None => expected.is_null(),
+        };
+        let number = field.and_then(Value::as_f64);
+        let compare = |bound: Option<f64>, holds: fn(f64, f64) -> bool| {
+            bound.is_none_or(|bound| number.is_some_and(|n| holds(n, bound)))
+        };
+        match self {
+            Condition::Equals(expected) => equals(expected),
+            Condition::Operators(op) => {
+                op.eq.as_ref().is_none_or(equals)
+                    && op.ne.as_ref().is_none_or(|v| !equals(v))
+                    && op.one_of.as_ref().is_none_or(|values| values.iter().any(equals))
+                    && compare(op.gt, |n, b| n > b)
+                    && compare(op.gte, |n, b| n >= b)
+                    && compare(op.lt, |n, b| n < b)
+                    && compare(op.lte, |n, b| n <= b)
+            }
+        }
+    }
+}
+
+/// Query options in the shape of the TS `VectorSearchOptions`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct VectorSearchOptions {
+    pub k: usize,
+    /// Candidates considered; defaults to the index's `efSearch`.
+    pub ef: Option<usize>,
+    /// Metadata conditions, all of which a result must meet.
+    pub filter: HashMap<String, Condition>,
+}
+
+impl Default for VectorSearchOptions {
+    fn default() -> Self {
+        VectorSearchOptions { k: 10, ef: None, filter: HashMap::new() }
+    }
+}
+
+#[derive(Serialize)]
+pub struct VectorHit<'a> {
+    pub id: &'a str,
+    pub distance: f32,
+    pub metadata: &'a Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Node {
+    id: String,
+    vector: Vec<f32>,
+    metadata: Map<String, Value>,
+    links: Vec<Vec<u32>>, // neighbours on each layer the node is on
+    deleted: bool,
+}
+
+/// A node and its distance to whatever is being searched for.
+#[derive(Clone, Copy, PartialEq)]
+struct Scored {
+    distance: f32,
+    node: u32,
+}
+
+impl Eq for Scored {}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance.total_cmp(&other.distance).then(self.node.cmp(&other.node))
+    }
+}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Hierarchical navigable small world graph over vectors keyed by chunk id.
+/// Deleted vectors stay in the graph for routing until they outnumber the
+/// live ones, when the graph is rebuilt without them.
+#[derive(Serialize, Deserialize)]
+pub struct VectorIndex {
+    options: VectorIndexOptions,
+    dimensions: usize,
+    nodes: Vec<Node>,
+    entry: Option<u32>,
+    deleted: usize,
+    seed: u64, // level generator state, so rebuilt snapshots stay reproducible
+    #[serde(skip)]
+    slots: HashMap<String, u32>, // id -> live node
+}
+
+impl VectorIndex {
+    pub fn new(options: VectorIndexOptions) -> Self {
+        VectorIndex {
+            options: VectorIndexOptions {
+                m: options.m.max(2),
+                ef_construction: options.ef_construction.max(1),
+                ..options
+            },
+            dimensions: 0,
+            nodes: Vec::new(),
+            entry: None,
+            deleted: 0,
+            seed: 0x9E37_79B9_7F4A_7C15,
+            slots: HashMap::new(),
+        }
+    }
+
+    /// Reads a snapshot written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TextProcessingError> {
+        let Some((&FORMAT_VERSION, body)) = bytes.split_first() else {
+            return Err(TextProcessingError::InvalidIndex("unknown format version".to_string()));
+        };
+        let mut index: VectorIndex = rmp_serde::from_slice(body)
+            .map_err(|e| TextProcessingError::InvalidIndex(e.to_string()))?;
+        index.validate()?;
+        index.slots = index
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| !node.deleted)
+            .map(|(slot, node)| (node.id.clone(), slot as u32))
+            .collect();
+        Ok(index)
+    }
+
+    /// Checks that a snapshot's graph only refers to nodes and layers it has,
+    /// so that searching and inserting cannot index out of bounds.
+    fn validate(&self) -> Result<(), TextProcessingError> {
+        let invalid = |reason: &str| Err(TextProcessingError::InvalidIndex(reason.to_string()));
+        let count = self.nodes.len();
+        if self.entry.is_some() != (count > 0) || self.entry.is_some_and(|entry| entry as usize >= count) {
+            return invalid("entry point is not a node");
+        }
+        if self.deleted != self.nodes.iter().filter(|node| node.deleted).count() {
+            return invalid("deleted count does not match the nodes");
+        }
+        for node in &self.nodes {
+            if node.vector.len() != self.dimensions {
+                return invalid("vector dimensions do not match the index");
+            }
+            if node.links.is_empty() {
+                return invalid("node is on no layer");
+            }
+            // A link on a layer must lead to a node that is on that layer too
+            let linked = node.links.iter().enumerate().all(|(layer, links)| {
+                links.iter().all(|&n| self.nodes.get(n as usize).is_some_and(|n| n.links.len() > layer))
+            });
+            if !linked {
+                return invalid("links do not match the nodes");
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![FORMAT_VERSION];
+        rmp_serde::encode::write_named(&mut bytes, self).expect("writing to a Vec cannot fail");
+        bytes
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Inserts `vector` under `id`, replacing any vector with that id. The
+    /// first vector fixes the number of dimensions.
+    pub fn insert(&mut self, id: &str, mut vector: Vec<f32>, metadata: Map<String, Value>) -> Result<(), TextProcessingError> {
+        if self.nodes.is_empty() {
+            self.dimensions = vector.len();
+        }
+        self.check(&vector)?;
+        if self.options.metric == Metric::Cosine {
+            normalize(&mut vector);
+        }
+        self.remove(id);
+        self.link(Node { id: id.to_string(), vector, metadata, links: Vec::new(), deleted: false });
+        Ok(())
+    }
+
+    /// Removes the vector with `id`; returns whether there was one.
+    pub fn remove(&mut self, id: &str) -> bool {
+        let Some(slot) = self.slots.remove(id) else {
+            return false;
+        };
+        self.nodes[slot as usize].deleted = true;
+        self.deleted += 1;
+        if self.nodes.len() >= MIN_REBUILD_SIZE && self.deleted > self.len() {
+            self.rebuild();
+        }
+        true
+    }
+
+    /// The `options.k` live vectors closest to `query` that meet the filter, closest first.
+    pub fn search(&self, query: &[f32], options: &VectorSearchOptions) -> Result<Vec<VectorHit<'_>>, TextProcessingError> {
+        let Some(entry) = self.entry else {
+            return Ok(Vec::new());
+        };
+        self.check(query)?;
+        let mut query = query.to_vec();
+        if self.options.metric == Metric::Cosine {
+            normalize(&mut query);
+        }
+
+        let accept = |node: &Node| {
+            !node.deleted && options.filter.iter().all(|(field, condition)| condition.matches(node.metadata.get(field)))
+        };
+        let entry = self.descend(&query, entry, 0);
+        let ef = options.ef.unwrap_or(self.options.ef_search).max(options.k);
+        let found = self.search_layer(&query, &[entry], ef, 0, &accept);
+        Ok(found
+            .into_iter()
+            .take(options.k)
+            .map(|s| {
+                let node = &self.nodes[s.node as usize];
+                VectorHit { id: &node.id, distance: self.options.metric.reported(s.distance), metadata: &node.metadata }
+            })
+            .collect())
+    }
+
+    fn check(&self, vector: &[f32]) -> Result<(), TextProcessingError> {
+        if vector.len() != self.dimensions {
+            return Err(TextProcessingError::DimensionMismatch { expected: self.dimensions, actual: vector.len() });
+        }
+        if !vector.iter().all(|x| x.is_finite()) {
+            return Err(TextProcessingError::InvalidOptions("vector values must be finite".to_string()));
+        }
+        Ok(())
+    }
+
+    fn link(&mut self, mut node: Node) {
+        let slot = self.nodes.len() as u32;
+        let level = self.random_level();
+        node.links = vec![Vec::new(); level + 1];
+        let query = node.vector.clone();
+        self.slots.insert(node.id.clone(), slot);
+        self.nodes.push(node);
+
+        let Some(entry) = self.entry else {
+            self.entry = Some(slot);
+            return;
+        };
+        let top = self.nodes[entry as usize].links.len() - 1;
+        let mut entries = vec![self.descend(&query, entry, level)];
+        for layer in (0..=level.min(top)).rev() {
+            let found = self.search_layer(&query, &entries, self.options.ef_construction, layer, &|n: &Node| !n.deleted);
+            let neighbours = self.select(&found, self.max_links(layer));
+            for &neighbour in &neighbours {
+                self.nodes[neighbour as usize].links[layer].push(slot);
+                self.prune(neighbour, layer);
+            }
+            self.nodes[slot as usize].links[layer] = neighbours;
+            if !found.is_empty() {
+                entries = found.iter().map(|s| s.node).collect();
+            }
+        }
+        if level > top {
+            self.entry = Some(slot);
+        }
+    }
+
+    /// Greedy walk from the top layer down to just above `layer`.
+    fn descend(&self, query: &[f32], entry: u32, layer: usize) -> u32 {
+        let top = self.nodes[entry as usize].links.len() - 1;
+        let mut current = entry;
+        for l in (layer + 1..=top).rev() {
+            if let Some(nearest) = self.search_layer(query, &[current], 1, l, &|_| true).first() {
+                current = nearest.node;
+            }
+        }
+        current
+    }
+
+    /// Best-first search of one layer, returning up to `ef` accepted nodes,
+    /// closest first. Rejected nodes are still walked through.
+    fn search_layer(&self, query: &[f32], entries: &[u32], ef: usize, layer: usize, accept: &dyn Fn(&Node) -> bool) -> Vec<Scored> {
+        let mut visited: HashSet<u32> = entries.iter().copied().collect();
+        let mut candidates = BinaryHeap::new();
+        let mut results: BinaryHeap<Scored> = BinaryHeap::new();
+        for &node in entries {
+            let scored = Scored { distance: self.distance_to(query, node), node };
+            candidates.push(Reverse(scored));
+            if accept(&self.nodes[node as usize]) {
+                results.push(scored);
+            }
+        }
+        while results.len() > ef {
+            results.pop();
+        }
+
+        while let Some(Reverse(current)) = candidates.pop() {
+            if results.len() >= ef && results.peek().is_some_and(|worst| current.distance > worst.distance) {
+                break;
+            }
+            for &neighbour in &self.nodes[current.node as usize].links[layer] {
+                if !visited.insert(neighbour) {
+                    continue;
+                }
+                let scored = Scored { distance: self.distance_to(query, neighbour), node: neighbour };
+                if results.len() < ef || results.peek().is_some_and(|worst| scored.distance < worst.distance) {
+                    candidates.push(Reverse(scored));
+                    if accept(&self.nodes[neighbour as usize]) {
+                        results.push(scored);
+                        if results.len() > ef {
+                            results.pop();
+                        }
+                    }
+                }
+            }
+        }
+        results.into_sorted_vec()
+    }
+
+    /// Neighbour selection heuristic: prefers candidates closer to the base
+    /// than to any already selected, so links spread in all directions, then
+    /// fills up with the closest of the rest.
+    fn select(&self, candidates: &[Scored], limit: usize) -> Vec<u32> {
+        let mut selected: Vec<Scored> = Vec::with_capacity(limit);
+        let mut skipped = Vec::new();
+        for &candidate in candidates {
+            if selected.len() == limit {
+                break;
+            }
+            let vector = &self.nodes[candidate.node as usize].vector;
+            if selected.iter().all(|s| self.distance_to(vector, s.node) > candidate.distance) {
+                selected.push(candidate);
+            } else {
+                skipped.push(candidate);
+            }
+        }
+        selected.extend(skipped.into_iter().take(limit - selected.len()));
+        selected.into_iter().map(|s| s.node).collect()
+    }
+
+    fn prune(&mut self, node: u32, layer: usize) {
+        let limit = self.max_links(layer);
+        let links = &self.nodes[node as usize].links[layer];
+        if links.len() <= limit {
+            return;
+        }
+        let vector = &self.nodes[node as usize].vector;
+        let mut scored: Vec<Scored> = links.iter().map(|&n| Scored { distance: self.distance_to(vector, n), node: n }).collect();
+        scored.sort();
+        self.nodes[node as usize].links[layer] = self.select(&scored, limit);
+    }
+
+    /// Relinks the live nodes from scratch, dropping deleted ones.
+    fn rebuild(&mut self) {
+        let nodes = std::mem::take(&mut self.nodes);
+        self.entry = None;
+        self.deleted = 0;
+        self.slots.clear();
+        for node in nodes.into_iter().filter(|n| !n.deleted) {
+            self.link(node);
+        }
+    }
+
+    fn max_links(&self, layer: usize) -> usize {
+        if layer == 0 { self.options.m * 2 } else { self.options.m }
+    }
+
+    fn distance_to(&self, query: &[f32], node: u32) -> f32 {
+        self.options.metric.distance(query, &self.nodes[node as usize].vector)
+    }
+
+    /// Exponentially distributed level, from an xorshift generator.
+    fn random_level(&mut self) -> usize {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        let uniform = ((self.seed >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+        let level = -uniform.ln() / (self.options.m as f64).ln();
+        (level as usize).min(MAX_LEVEL)
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = dot(vector, vector).sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Deterministic pseudo-random vectors.
+    fn vectors(count: usize, dimensions: usize) -> Vec<Vec<f32>> {
+        let mut state = 42u64;
+        (0..count)
+            .map(|_| {
+                (0..dimensions)
+                    .map(|_| {
+                        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                        (state >> 40) as f32 / (1u64 << 24) as f32 - 0.5
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn metadata(i: usize) -> Map<String, Value> {
+        let value = json!({ "document": format!("doc-{}", i % 5), "page": i % 40, "tags": if i.is_multiple_of(2) { vec!["even"] } else { vec!["odd"] } });
+        value.as_object().unwrap().clone()
+    }
+
+    fn build(metric: Metric, data: &[Vec<f32>]) -> VectorIndex {
+        let mut index = VectorIndex::new(VectorIndexOptions { metric, m: 8, ef_construction: 64, ..Default::default() });
+        for (i, vector) in data.iter().enumerate() {
+            index.insert(&format!("chunk-{}", i), vector.clone(), metadata(i)).unwrap();
+        }
+        index
+    }
+
+    /// Ids of the `k` closest vectors by exhaustive search.
+    fn exact(metric: Metric, data: &[Vec<f32>], query: &[f32], k: usize, keep: impl Fn(usize) -> bool) -> Vec<String> {
+        let prepare = |v: &[f32]| {
+            let mut v = v.to_vec();
+            if metric == Metric::Cosine {
+                normalize(&mut v);
+            }
+            v
+        };
+        let query = prepare(query);
+        let mut scored: Vec<(f32, usize)> = data
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| keep(i))
+            .map(|(i, v)| (metric.distance(&query, &prepare(v)), i))
+            .collect();
+        scored.sort_by(|a, b| a.0.total_cmp(&b.0));
+        scored.into_iter().take(k).map(|(_, i)| format!("chunk-{}", i)).collect()
+    }
+
+    fn recall(index: &VectorIndex, metric: Metric, data: &[Vec<f32>], queries: &[Vec<f32>], options: &VectorSearchOptions, keep: impl Fn(usize) -> bool + Copy) -> f64 {
+        let mut found = 0;
+        for query in queries {
+            let expected = exact(metric, data, query, options.k, keep);
+            let hits = index.search(query, options).unwrap();
+            found += hits.iter().filter(|h| expected.iter().any(|e| e == h.id)).count();
+        }
+        found as f64 / (queries.len() * options.k) as f64
+    }
+
+    #[test]
+    fn test_recall_against_exhaustive_search() {
+        let data = vectors(600, 16);
+        let queries = vectors(620, 16).split_off(600);
+        for metric in [Metric::Cosine, Metric::Dot, Metric::L2] {
+            let index = build(metric, &data);
+            let options = VectorSearchOptions::default();
+            let recall = recall(&index, metric, &data, &queries, &options, |_| true);
+            assert!(recall >= 0.9, "{:?}: {}", metric, recall);
+
+            let hits = index.search(&data[7], &options).unwrap();
+            if metric != Metric::Dot {
+                assert_eq!(hits[0].id, "chunk-7");
+                assert!(hits[0].distance.abs() < 1e-5);
+            }
+            assert!(hits.windows(2).all(|w| w[0].distance <= w[1].distance));
+        }
+    }
+
+    #[test]
+    fn test_filters_and_deletes() {
+        let data = vectors(600, 16);
+        let queries = vectors(620, 16).split_off(600);
+        let mut index = build(Metric::Cosine, &data);
+
+        let filter: HashMap<String, Condition> = serde_json::from_value(json!({
+            "document": { "in": ["doc-1", "doc-3"] },
+            "page": { "lt": 20 },
+            "tags": "odd",
+        })).unwrap();
+        let options = VectorSearchOptions { k: 5, filter, ..Default::default() };
+        let keep = |i: usize| (i % 5 == 1 || i % 5 == 3) && i % 40 < 20 && i % 2 == 1;
+        assert!(recall(&index, Metric::Cosine, &data, &queries, &options, keep) >= 0.9);
+        for hit in index.search(&queries[0], &options).unwrap() {
+            let i: usize = hit.id["chunk-".len()..].parse().unwrap();
+            assert!(keep(i), "{}", hit.id);
+        }
+
+        assert!(index.remove("chunk-7"));
+        assert!(!index.remove("chunk-7"));
+        let hits = index.search(&data[7], &VectorSearchOptions::default()).unwrap();
+        assert!(hits.iter().all(|h| h.id != "chunk-7"));
+
+        // Reinserting an id replaces its vector
+        index.insert("chunk-8", data[7].clone(), Map::new()).unwrap();
+        assert_eq!(index.len(), 599);
+        assert_eq!(index.search(&data[7], &VectorSearchOptions::default()).unwrap()[0].id, "chunk-8");
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let data = vectors(200, 8);
+        let mut index = build(Metric::L2, &data);
+        index.remove("chunk-3");
+        let bytes = index.to_bytes();
+        let restored = VectorIndex::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.len(), 199);
+
+        let options = VectorSearchOptions::default();
+        let ids = |index: &VectorIndex| index.search(&data[3], &options).unwrap().iter().map(|h| h.id.to_string()).collect::<Vec<_>>();
+        assert_eq!(ids(&restored), ids(&index));
+        assert_eq!(restored.search(&data[0], &options).unwrap()[0].metadata["document"], "doc-0");
+
+        assert!(matches!(
+            restored.search(&[0.0; 4], &options),
+            Err(TextProcessingError::DimensionMismatch { expected: 8, actual: 4 })
+        ));
+        assert!(VectorIndex::from_bytes(&bytes[..bytes.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn test_corrupt_snapshots_rejected() {
+        let data = vectors(50, 8);
+        let corrupt = |damage: fn(&mut VectorIndex)| {
+            let mut index = build(Metric::Cosine, &data);
+            damage(&mut index);
+            VectorIndex::from_bytes(&index.to_bytes()).err()
+        };
+        assert!(corrupt(|_| {}).is_none());
+        assert!(corrupt(|index| index.nodes[7].links[0].push(999)).is_some());
+        // A link up a layer its target is not on
+        assert!(corrupt(|index| {
+            let flat = index.nodes.iter().position(|n| n.links.len() == 1).unwrap() as u32;
+            index.nodes[7].links.push(vec![flat]);
+        })
+        .is_some());
+        assert!(corrupt(|index| index.nodes[7].links.clear()).is_some());
+        assert!(corrupt(|index| index.nodes[7].vector.truncate(7)).is_some());
+        assert!(corrupt(|index| index.entry = Some(50)).is_some());
+        assert!(corrupt(|index| index.entry = None).is_some());
+        assert!(corrupt(|index| index.dimensions = 4).is_some());
+    }
+}
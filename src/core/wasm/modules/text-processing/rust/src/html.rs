@@ -0,0 +1,786 @@
+use std::collections::HashSet;
+use html_escape::decode_html_entities;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Serialize, Deserialize};
+
+use crate::extraction::Utf16Offsets;
+
+/// Elements dropped with their content: they render nothing readable.
+const HIDDEN: &[&str] = &[
+    "head", "script", "style", "noscript", "template", "svg", "math", "iframe", "object", "canvas", "select", "button",
+    "textarea",
+];
+
+/// Page furniture dropped with boilerplate.
+const BOILERPLATE: &[&str] = &["nav", "footer", "aside", "form", "dialog", "menu"];
+
+const BOILERPLATE_ROLES: &[&str] = &[
+    "navigation", "banner", "complementary", "contentinfo", "dialog", "alertdialog", "menu", "menubar", "search",
+];
+
+/// Elements whose content is text, not markup.
+const RAW_TEXT: &[&str] = &["script", "style", "title", "textarea", "xmp", "iframe", "noembed", "noframes"];
+
+const VOID: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source", "track", "wbr",
+];
+
+/// Elements that begin a block of text of their own.
+const BLOCKS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "body", "caption", "dd", "details", "dialog", "div", "dl", "dt",
+    "fieldset", "figcaption", "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr", "html",
+    "li", "main", "nav", "ol", "p", "pre", "section", "summary", "table", "tbody", "td", "tfoot", "th", "thead", "tr",
+    "ul",
+];
+
+lazy_static! {
+    static ref ATTRIBUTE: Regex = Regex::new(r#"([^\s"'>/=]+)(?:\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'=<>`]+)))?"#).unwrap();
+    // Consent banners go even when their class also reads like content
+    static ref CONSENT: Regex = Regex::new(r"(?i)cookie|consent|gdpr").unwrap();
+    static ref UNLIKELY: Regex = Regex::new(
+        r"(?i)\bad(?:s|vert\w*)?\b|banner|breadcrumb|comment|disqus|foot|header|masthead|menu|\bnav|newsletter|pager|popup|promo|related|share|sidebar|skip|social|sponsor|subscribe"
+    ).unwrap();
+    static ref LIKELY: Regex = Regex::new(r"(?i)article|body|column|content|main|post|story|entry").unwrap();
+    static ref MARKDOWN_START: Regex = Regex::new(r"^(?:#|[-*+][ \t]|\d{1,9}[.)][ \t]|```|~~~|\|)").unwrap();
+}
+
+/// Options in the shape of the TS `HtmlOptions`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HtmlOptions {
+    /// Drop navigation, footers, sidebars and cookie banners, and keep only
+    /// the highest scoring content container.
+    pub remove_boilerplate: bool,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        HtmlOptions { remove_boilerplate: true }
+    }
+}
+
+/// Text of an HTML document as Markdown, in the shape of the TS `HtmlText`:
+/// headings, list items, table rows and preformatted code keep their
+/// Markdown form so the structural chunker sees them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HtmlText {
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub blocks: Vec<HtmlBlock>,
+}
+
+/// A block of the text and the element it came from, both in UTF-16 offsets.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HtmlBlock {
+    pub tag: String,
+    pub start: usize,
+    pub end: usize,
+    pub source_start: usize,
+    pub source_end: usize,
+}
+
+pub fn to_text(html: &str, options: &HtmlOptions) -> HtmlText {
+    let mut converter = Converter::new(options);
+    for (token, start, end) in Tokens::new(html) {
+        match token {
+            Token::Start { name, attributes } => converter.start(name, attributes, start, end),
+            Token::End { name } => converter.end(&name, end),
+            Token::Text(text) => converter.text(text, start, end),
+        }
+    }
+    converter.flush(None);
+    converter.flush_row(None);
+
+    let kept = options
+        .remove_boilerplate
+        .then(|| main_content(&converter.nodes, &converter.drafts))
+        .flatten();
+    let mut text = String::new();
+    let mut spans = Vec::new();
+    let mut tables = HashSet::new();
+    let mut previous_table = None;
+    for (i, draft) in converter.drafts.iter().enumerate() {
+        if kept.as_ref().is_some_and(|kept| !kept[i]) {
+            continue;
+        }
+        if !text.is_empty() {
+            text.push_str(if draft.table.is_some() && draft.table == previous_table { "\n" } else { "\n\n" });
+        }
+        let start = text.len();
+        text.push_str(&draft.render());
+        // The first row of a table is its header
+        if draft.table.is_some_and(|table| tables.insert(table)) {
+            text.push('\n');
+            text.push_str(&format!("|{}", " --- |".repeat(draft.cells.len())));
+        }
+        previous_table = draft.table;
+        spans.push((draft, start, text.len()));
+    }
+
+    let mut source = Utf16Offsets::new(html);
+    let mut target = Utf16Offsets::new(&text);
+    let blocks = spans
+        .into_iter()
+        .map(|(draft, start, end)| HtmlBlock {
+            tag: draft.tag.clone(),
+            start: target.at(start),
+            end: target.at(end),
+            source_start: source.at(draft.start),
+            source_end: source.at(draft.end),
+        })
+        .collect();
+    HtmlText { title: converter.title, blocks, text }
+}
+
+enum Token<'a> {
+    Start { name: String, attributes: &'a str },
+    End { name: String },
+    Text(&'a str),
+}
+
+/// Tokens of `html` with their byte spans. Comments, doctypes and processing
+/// instructions are skipped, and a `<` that opens no tag is text.
+struct Tokens<'a> {
+    html: &'a str,
+    lower: String,
+    at: usize,
+    raw: Option<String>, // raw text element just opened
+}
+
+impl<'a> Tokens<'a> {
+    fn new(html: &'a str) -> Self {
+        Tokens { html, lower: html.to_ascii_lowercase(), at: 0, raw: None }
+    }
+
+    /// Offset of `pattern` in `html`, ignoring ASCII case, or the end of `html`.
+    fn search(&self, pattern: &str, from: usize) -> usize {
+        self.lower[from..].find(pattern).map_or(self.html.len(), |i| from + i)
+    }
+
+    /// Offset just past the `>` closing the tag whose attributes start at `from`.
+    fn tag_end(&self, from: usize) -> usize {
+        let bytes = self.html.as_bytes();
+        let mut quote = None;
+        let mut previous = 0;
+        for (i, &b) in bytes.iter().enumerate().skip(from) {
+            match quote {
+                Some(q) if b == q => quote = None,
+                Some(_) => {}
+                None if b == b'>' => return i + 1,
+                None if (b == b'"' || b == b'\'') && previous == b'=' => quote = Some(b),
+                None => {}
+            }
+            if !b.is_ascii_whitespace() {
+                previous = b;
+            }
+        }
+        self.html.len()
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = (Token<'a>, usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = self.html.len();
+        loop {
+            let start = self.at;
+            if start >= len {
+                return None;
+            }
+            if let Some(name) = self.raw.take() {
+                let end = self.search(&format!("</{name}"), start);
+                self.at = end;
+                if end > start {
+                    return Some((Token::Text(&self.html[start..end]), start, end));
+                }
+                continue;
+            }
+
+            let rest = &self.html[start..];
+            if rest.starts_with("<!--") {
+                self.at = (self.search("-->", start + 4) + 3).min(len);
+                continue;
+            }
+            if rest.starts_with("<!") || rest.starts_with("<?") {
+                self.at = (self.search(">", start) + 1).min(len);
+                continue;
+            }
+            let closing = rest.starts_with("</");
+            let name_start = start + if closing { 2 } else { 1 };
+            if rest.starts_with('<') && self.html.as_bytes().get(name_start).is_some_and(u8::is_ascii_alphabetic) {
+                let name_end = self.html[name_start..]
+                    .find(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>')
+                    .map_or(len, |i| name_start + i);
+                let end = self.tag_end(name_end);
+                self.at = end;
+                let name = self.lower[name_start..name_end].to_string();
+                if closing {
+                    return Some((Token::End { name }, start, end));
+                }
+                if RAW_TEXT.contains(&name.as_str()) {
+                    self.raw = Some(name.clone());
+                }
+                let attributes = self.html[name_end..end].strip_suffix('>').unwrap_or(&self.html[name_end..end]);
+                return Some((Token::Start { name, attributes }, start, end));
+            }
+
+            // Step past the first character, a `<` that opens no tag included
+            let from = start + rest.chars().next().map_or(1, char::len_utf8);
+            let end = self.html[from..].find('<').map_or(len, |i| from + i);
+            self.at = end;
+            return Some((Token::Text(&self.html[start..end]), start, end));
+        }
+    }
+}
+
+fn attributes(source: &str) -> impl Iterator<Item = (String, &str)> {
+    ATTRIBUTE.captures_iter(source).map(|caps| {
+        let value = caps.get(2).or(caps.get(3)).or(caps.get(4)).map_or("", |m| m.as_str());
+        (caps[1].to_ascii_lowercase(), value)
+    })
+}
+
+/// An element of the document tree, as far as scoring needs it.
+struct Node {
+    parent: Option<usize>,
+}
+
+/// An element still open, innermost last.
+struct Open {
+    name: String,
+    node: usize,
+    start: usize,   // byte offset of the start tag
+    hidden: bool,   // dropped with its content
+    drafted: bool,  // has begun a block
+    number: usize,  // items so far in an `ol`, or the number of an `li` in one
+}
+
+/// A block of text being collected, in byte offsets of the HTML.
+struct Draft {
+    tag: String,
+    node: usize,
+    prefix: String,
+    text: String,
+    cells: Vec<String>, // of a table row
+    table: Option<usize>,
+    space: bool, // whitespace pending before the next character
+    chars: usize,
+    links: usize, // characters inside links
+    start: usize,
+    end: usize,
+}
+
+impl Draft {
+    fn new(tag: &str, node: usize, start: usize) -> Self {
+        Draft {
+            tag: tag.to_string(),
+            node,
+            prefix: String::new(),
+            text: String::new(),
+            cells: Vec::new(),
+            table: None,
+            space: false,
+            chars: 0,
+            links: 0,
+            start,
+            end: start,
+        }
+    }
+
+    fn is_code(&self) -> bool {
+        self.tag == "pre"
+    }
+
+    /// Appends `text`, collapsing whitespace unless preformatted.
+    fn push(&mut self, text: &str, link: bool, preformatted: bool, end: usize) {
+        let out = self.cells.last_mut().unwrap_or(&mut self.text);
+        for c in text.chars() {
+            if preformatted {
+                out.push(c);
+            } else if c.is_ascii_whitespace() {
+                self.space = true;
+                continue;
+            } else {
+                if self.space && !out.is_empty() && !out.ends_with('\n') {
+                    out.push(' ');
+                }
+                self.space = false;
+                out.push(c);
+            }
+            if !c.is_whitespace() {
+                self.chars += 1;
+                self.links += usize::from(link);
+            }
+        }
+        self.end = end;
+    }
+
+    fn is_empty(&self) -> bool {
+        self.text.trim().is_empty() && self.cells.iter().all(|cell| cell.trim().is_empty())
+    }
+
+    fn link_density(&self) -> f64 {
+        self.links as f64 / self.chars.max(1) as f64
+    }
+
+    /// The block as Markdown.
+    fn render(&self) -> String {
+        if !self.cells.is_empty() {
+            let cells: Vec<String> = self.cells.iter().map(|cell| cell.trim().replace('|', "\\|")).collect();
+            return format!("| {} |", cells.join(" | "));
+        }
+        if self.is_code() {
+            let code = self.text.trim_matches('\n');
+            let longest = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+            let fence = "`".repeat(longest.max(2) + 1);
+            return format!("{fence}\n{code}\n{fence}");
+        }
+        let text = self.text.trim();
+        if self.prefix.is_empty() && MARKDOWN_START.is_match(text) {
+            format!("\\{text}")
+        } else {
+            format!("{}{}", self.prefix, text)
+        }
+    }
+}
+
+struct Converter<'a> {
+    options: &'a HtmlOptions,
+    nodes: Vec<Node>, // the document root first
+    stack: Vec<Open>,
+    hidden: usize, // open elements that are hidden
+    drafts: Vec<Draft>,
+    current: Option<Draft>,
+    row: Option<Draft>,
+    title: Option<String>,
+}
+
+impl<'a> Converter<'a> {
+    fn new(options: &'a HtmlOptions) -> Self {
+        Converter {
+            options,
+            nodes: vec![Node { parent: None }],
+            stack: Vec::new(),
+            hidden: 0,
+            drafts: Vec::new(),
+            current: None,
+            row: None,
+            title: None,
+        }
+    }
+
+    fn is_open(&self, names: &[&str]) -> bool {
+        self.stack.iter().any(|open| names.contains(&open.name.as_str()))
+    }
+
+    /// Index of the open element whose block receives text: the innermost
+    /// table cell, whatever blocks it contains, or else the innermost block.
+    fn context(&self) -> Option<usize> {
+        let mut block = None;
+        for (i, open) in self.stack.iter().enumerate().rev() {
+            match open.name.as_str() {
+                "td" | "th" => return Some(i),
+                "table" => break,
+                name if block.is_none() && BLOCKS.contains(&name) => block = Some(i),
+                _ => {}
+            }
+        }
+        block
+    }
+
+    fn context_node(&self) -> usize {
+        self.context().map_or(0, |i| self.stack[i].node)
+    }
+
+    fn is_boilerplate(&self, name: &str, attributes: &str) -> bool {
+        if BOILERPLATE.contains(&name) || (name == "header" && !self.is_open(&["article", "main"])) {
+            return true;
+        }
+        let mut names = String::new();
+        for (attribute, value) in self::attributes(attributes) {
+            match attribute.as_str() {
+                "role" if BOILERPLATE_ROLES.contains(&value.to_ascii_lowercase().as_str()) => return true,
+                "class" | "id" => {
+                    names.push(' ');
+                    names.push_str(value);
+                }
+                _ => {}
+            }
+        }
+        CONSENT.is_match(&names)
+            || (!matches!(name, "html" | "body" | "article" | "main")
+                && UNLIKELY.is_match(&names)
+                && !LIKELY.is_match(&names))
+    }
+
+    fn is_hidden(&self, name: &str, attributes: &str) -> bool {
+        HIDDEN.contains(&name)
+            || self::attributes(attributes).any(|(attribute, value)| {
+                attribute == "hidden" || (attribute == "aria-hidden" && value.eq_ignore_ascii_case("true"))
+            })
+            || (self.options.remove_boilerplate && self.is_boilerplate(name, attributes))
+    }
+
+    /// Closes the innermost element named in `names`, with what it contains,
+    /// unless an element named in `scope` is open inside it.
+    fn close_implied(&mut self, names: &[&str], scope: &[&str]) {
+        for i in (0..self.stack.len()).rev() {
+            let name = self.stack[i].name.as_str();
+            if names.contains(&name) {
+                self.pop_to(i, None);
+                return;
+            }
+            if scope.contains(&name) {
+                return;
+            }
+        }
+    }
+
+    fn start(&mut self, name: String, attributes: &str, start: usize, end: usize) {
+        match name.as_str() {
+            "li" => self.close_implied(&["li"], &["ul", "ol", "menu"]),
+            "dt" | "dd" => self.close_implied(&["dt", "dd"], &["dl"]),
+            "td" | "th" => self.close_implied(&["td", "th"], &["tr", "table"]),
+            "tr" => self.close_implied(&["tr"], &["table"]),
+            "thead" | "tbody" | "tfoot" => self.close_implied(&["thead", "tbody", "tfoot"], &["table"]),
+            _ => {}
+        }
+        if BLOCKS.contains(&name.as_str()) {
+            self.close_implied(&["p"], BLOCKS);
+        }
+
+        let self_closing = attributes.trim_end().ends_with('/');
+        if self.hidden > 0 && name != "title" {
+            if !VOID.contains(&name.as_str()) && !self_closing {
+                self.push_open(name, start, true);
+            }
+            return;
+        }
+        if name == "br" {
+            // A line break within paragraphs, a space in headings and cells
+            let in_cell = self.context().is_some_and(|i| matches!(self.stack[i].name.as_str(), "td" | "th"));
+            match if in_cell { self.row.as_mut() } else { self.current.as_mut() } {
+                Some(draft) if !in_cell && !draft.prefix.starts_with('#') => {
+                    draft.text.push('\n');
+                    draft.space = false;
+                }
+                Some(draft) => draft.space = true,
+                None => {}
+            }
+            return;
+        }
+        if VOID.contains(&name.as_str()) || self_closing {
+            if BLOCKS.contains(&name.as_str()) {
+                self.flush(None);
+            }
+            return;
+        }
+
+        let hidden = name != "title" && self.is_hidden(&name, attributes);
+        if name == "tr" || (name == "table" && self.row.is_some()) {
+            self.flush_row(None);
+        }
+        let mut number = 0;
+        if name == "li" {
+            if let Some(list) = self.stack.iter_mut().rev().find(|o| matches!(o.name.as_str(), "ul" | "ol" | "menu")) {
+                list.number += 1;
+                if list.name == "ol" {
+                    number = list.number;
+                }
+            }
+        }
+        if !hidden && matches!(name.as_str(), "td" | "th") {
+            // Rows without a `tr` belong to the element holding their cells
+            let table = self.stack.iter().rev().find(|o| o.name == "table").map(|o| o.node);
+            let (node, row_start) = match self.stack.iter().rev().find(|o| o.name == "tr") {
+                Some(tr) => (tr.node, tr.start),
+                None => (self.stack.last().map_or(0, |o| o.node), start),
+            };
+            let row = self.row.get_or_insert_with(|| Draft { table, ..Draft::new("tr", node, row_start) });
+            row.cells.push(String::new());
+            row.space = false;
+            row.end = end;
+        }
+        self.push_open(name, start, hidden);
+        if let Some(open) = self.stack.last_mut() {
+            open.number = number;
+        }
+        self.flush_if_moved();
+    }
+
+    fn push_open(&mut self, name: String, start: usize, hidden: bool) {
+        let parent = self.stack.last().map_or(0, |open| open.node);
+        self.nodes.push(Node { parent: Some(parent) });
+        self.hidden += usize::from(hidden);
+        self.stack.push(Open { name, node: self.nodes.len() - 1, start, hidden, drafted: false, number: 0 });
+    }
+
+    fn end(&mut self, name: &str, end: usize) {
+        if name == "br" {
+            return self.start("br".to_string(), "", end, end);
+        }
+        if let Some(i) = self.stack.iter().rposition(|open| open.name == name) {
+            self.pop_to(i, Some(end));
+            self.flush_if_moved();
+        }
+    }
+
+    /// Closes the open elements from `index` on; `end` is the offset past the
+    /// end tag of the element at `index`, if it has one.
+    fn pop_to(&mut self, index: usize, end: Option<usize>) {
+        let closed = self.stack.split_off(index);
+        self.hidden -= closed.iter().filter(|open| open.hidden).count();
+        let is_closed = |node: usize| closed.iter().any(|open| open.node == node);
+        if self.current.as_ref().is_some_and(|d| is_closed(d.node)) {
+            let owner = self.current.as_ref().is_some_and(|d| d.node == closed[0].node);
+            self.flush(end.filter(|_| owner));
+        }
+        if self.row.as_ref().is_some_and(|r| is_closed(r.node) || closed.iter().any(|o| o.name == "table")) {
+            let owner = self.row.as_ref().is_some_and(|r| r.node == closed[0].node);
+            self.flush_row(end.filter(|_| owner));
+        }
+    }
+
+    /// Ends the current block once text would go to another element.
+    fn flush_if_moved(&mut self) {
+        if self.current.as_ref().is_some_and(|d| d.node != self.context_node()) {
+            self.flush(None);
+        }
+    }
+
+    fn flush(&mut self, end: Option<usize>) {
+        if let Some(mut draft) = self.current.take() {
+            draft.end = end.unwrap_or(draft.end);
+            if !draft.is_empty() {
+                self.drafts.push(draft);
+            }
+        }
+    }
+
+    fn flush_row(&mut self, end: Option<usize>) {
+        if let Some(mut row) = self.row.take() {
+            row.end = end.unwrap_or(row.end);
+            if !row.is_empty() {
+                self.drafts.push(row);
+            }
+        }
+    }
+
+    fn text(&mut self, raw: &str, start: usize, end: usize) {
+        if self.stack.last().is_some_and(|open| open.name == "title") {
+            if self.title.is_none() {
+                let title = decode_html_entities(raw).split_whitespace().collect::<Vec<_>>().join(" ");
+                self.title = (!title.is_empty()).then_some(title);
+            }
+            return;
+        }
+        if self.hidden > 0 {
+            return;
+        }
+        let text = decode_html_entities(raw);
+        let link = self.is_open(&["a"]);
+        let context = self.context();
+        let tag = context.map_or("p", |i| self.stack[i].name.as_str());
+        if matches!(tag, "td" | "th") {
+            if let Some(row) = self.row.as_mut() {
+                row.push(&text, link, false, end);
+            }
+            return;
+        }
+        if self.current.is_none() && text.trim().is_empty() {
+            return;
+        }
+
+        let preformatted = self.is_open(&["pre"]);
+        if self.current.is_none() {
+            let (node, element_start) = match context {
+                Some(i) if !self.stack[i].drafted => (self.stack[i].node, self.stack[i].start),
+                Some(i) => (self.stack[i].node, start),
+                None => (0, start),
+            };
+            let mut draft = Draft::new(tag, node, element_start);
+            draft.prefix = self.prefix(tag);
+            if let Some(i) = context {
+                self.stack[i].drafted = true;
+            }
+            self.current = Some(draft);
+        }
+        if let Some(draft) = self.current.as_mut() {
+            draft.push(&text, link, preformatted && draft.is_code(), end);
+        }
+    }
+
+    /// Markdown marker of a block of `tag`.
+    fn prefix(&self, tag: &str) -> String {
+        match tag {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => format!("{} ", "#".repeat(usize::from(tag.as_bytes()[1] - b'0'))),
+            "li" => {
+                let depth = self.stack.iter().filter(|o| matches!(o.name.as_str(), "ul" | "ol" | "menu")).count();
+                let indent = "  ".repeat(depth.saturating_sub(1));
+                match self.stack.iter().rev().find(|o| o.name == "li").map_or(0, |o| o.number) {
+                    0 => format!("{indent}- "),
+                    number => format!("{indent}{number}. "),
+                }
+            }
+            _ => String::new(),
+        }
+    }
+}
+
+/// Picks the blocks of the main content, readability style: each paragraph
+/// scores its parent, grandparent and great-grandparent by its length and
+/// commas, containers lose the share of their text that is links, and the
+/// best container is kept with the siblings scoring close to it and the
+/// long paragraphs next to it. Returns whether each draft is kept, or `None`
+/// to keep everything when no paragraph is long enough to score.
+fn main_content(nodes: &[Node], drafts: &[Draft]) -> Option<Vec<bool>> {
+    let ancestors = |node: usize| std::iter::successors(Some(node), |&n| nodes[n].parent);
+    let mut text = vec![0usize; nodes.len()];
+    let mut links = vec![0usize; nodes.len()];
+    let mut scores = vec![0.0f64; nodes.len()];
+    for draft in drafts {
+        for node in ancestors(draft.node) {
+            text[node] += draft.chars;
+            links[node] += draft.links;
+        }
+        if draft.chars < 25 || draft.prefix.starts_with('#') {
+            continue;
+        }
+        let commas = draft.text.matches([',', '，', '、']).count() + draft.cells.iter().map(|c| c.matches(',').count()).sum::<usize>();
+        let points = 1.0 + commas as f64 + (draft.chars / 100).min(3) as f64;
+        for (level, node) in ancestors(draft.node).skip(1).take(3).enumerate() {
+            scores[node] += points / [1.0, 2.0, 6.0][level];
+        }
+    }
+
+    let finals: Vec<f64> = (0..nodes.len())
+        .map(|n| scores[n] * (1.0 - links[n] as f64 / text[n].max(1) as f64))
+        .collect();
+    let top = (0..nodes.len()).filter(|&n| finals[n] > 0.0).max_by(|&a, &b| finals[a].total_cmp(&finals[b]))?;
+    let threshold = (finals[top] * 0.2).max(10.0);
+    let parent = nodes[top].parent;
+    let roots: HashSet<usize> = (0..nodes.len())
+        .filter(|&n| n == top || (parent.is_some() && nodes[n].parent == parent && finals[n] >= threshold))
+        .collect();
+
+    let kept = drafts
+        .iter()
+        .map(|draft| {
+            ancestors(draft.node).any(|n| roots.contains(&n))
+                || (parent.is_some()
+                    && nodes[draft.node].parent == parent
+                    && draft.chars > 80
+                    && draft.link_density() < 0.25)
+        })
+        .collect();
+    Some(kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structure::{parse_blocks, BlockKind};
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html><head><title>Release notes &ndash; Acme</title><style>p { color: red }</style></head>
+<body>
+<nav><ul><li><a href="/">Home</a><li><a href="/blog">Blog</a></ul></nav>
+<div id="cookie-banner" class="notice">We use cookies. <button>Accept</button></div>
+<div class="layout">
+  <article class="post">
+    <h1>Version 2.0</h1>
+    <p>This release rewrites the sync engine, adds offline support, and fixes a long list of bugs reported by users.</p>
+    <p>Upgrading takes a few minutes: back up your data, install the update, then restart the app &amp; sign in again.</p>
+    <script>track("view")</script>
+  </article>
+  <div class="sidebar"><h3>Related</h3><a href="/a">Version 1.9 is out, with many changes</a></div>
+</div>
+<footer>&copy; Acme Inc.</footer>
+</body></html>"#;
+
+    #[test]
+    fn test_boilerplate_removed() {
+        let converted = to_text(PAGE, &HtmlOptions::default());
+        assert_eq!(converted.title.as_deref(), Some("Release notes – Acme"));
+        assert_eq!(
+            converted.text,
+            "# Version 2.0\n\n\
+             This release rewrites the sync engine, adds offline support, and fixes a long list of bugs reported by users.\n\n\
+             Upgrading takes a few minutes: back up your data, install the update, then restart the app & sign in again."
+        );
+
+        let everything = to_text(PAGE, &HtmlOptions { remove_boilerplate: false });
+        assert!(everything.text.starts_with("- Home\n\n- Blog\n\nWe use cookies.\n\n# Version 2.0"));
+        assert!(everything.text.ends_with("### Related\n\nVersion 1.9 is out, with many changes\n\n© Acme Inc."));
+    }
+
+    #[test]
+    fn test_structure_reaches_the_chunker() {
+        let html = "<h2>Steps</h2><ol><li>Open <b>Settings</b><li>Choose <i>Sync</i><ul><li>nested</ul></ol>\
+                    <table><tr><th>Plan<th>Price</tr><tr><td>Basic</td><td>$5 | month</td></tr></table>\
+                    <pre><code>fn main() {\n    run();\n}</code></pre><p>Line one<br>line two</p><p>#1 fan</p>";
+        let converted = to_text(html, &HtmlOptions { remove_boilerplate: false });
+        assert_eq!(
+            converted.text,
+            "## Steps\n\n1. Open Settings\n\n2. Choose Sync\n\n  - nested\n\n\
+             | Plan | Price |\n| --- | --- |\n| Basic | $5 \\| month |\n\n\
+             ```\nfn main() {\n    run();\n}\n```\n\nLine one\nline two\n\n\\#1 fan"
+        );
+        let kinds: Vec<BlockKind> = parse_blocks(&converted.text).into_iter().map(|b| b.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                BlockKind::Heading { level: 2, title: "Steps".to_string() },
+                BlockKind::ListItem,
+                BlockKind::ListItem,
+                BlockKind::ListItem,
+                BlockKind::TableRow,
+                BlockKind::TableRow,
+                BlockKind::Code,
+                BlockKind::Paragraph,
+                BlockKind::Paragraph,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_blocks_map_to_source_elements() {
+        let html = "<p>Café <a href='x'>menu</a></p>\n<div>Crème brûlée<p>Tarte</p>après</div>";
+        let converted = to_text(html, &HtmlOptions::default());
+        assert_eq!(converted.text, "Café menu\n\nCrème brûlée\n\nTarte\n\naprès");
+
+        let source: Vec<u16> = html.encode_utf16().collect();
+        let target: Vec<u16> = converted.text.encode_utf16().collect();
+        let pieces: Vec<(String, String, String)> = converted
+            .blocks
+            .iter()
+            .map(|b| {
+                (
+                    b.tag.clone(),
+                    String::from_utf16(&target[b.start..b.end]).unwrap(),
+                    String::from_utf16(&source[b.source_start..b.source_end]).unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            pieces,
+            vec![
+                ("p".to_string(), "Café menu".to_string(), "<p>Café <a href='x'>menu</a></p>".to_string()),
+                ("div".to_string(), "Crème brûlée".to_string(), "<div>Crème brûlée".to_string()),
+                ("p".to_string(), "Tarte".to_string(), "<p>Tarte</p>".to_string()),
+                ("div".to_string(), "après".to_string(), "après</div>".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_text_nodes_starting_with_non_ascii() {
+        let html = "<p>été</p><li>Дом</li><div>東京 < 大阪</div><p>😀 ok</p>";
+        let converted = to_text(html, &HtmlOptions::default());
+        assert_eq!(converted.text, "été\n\n- Дом\n\n東京 < 大阪\n\n😀 ok");
+    }
+}
@@ -0,0 +1,358 @@
+use std::collections::{HashMap, HashSet};
+use serde::{Serialize, Deserialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::analysis::{has_stopwords, is_stopword, resolve_language};
+use crate::extraction::{Position, Utf16Offsets};
+
+/// PageRank damping factor.
+const DAMPING: f64 = 0.85;
+const MAX_ITERATIONS: usize = 100;
+const CONVERGENCE: f64 = 1e-6;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum KeywordMethod {
+    /// Rapid Automatic Keyword Extraction: word degree over frequency.
+    #[default]
+    Rake,
+    /// PageRank over the word co-occurrence graph.
+    TextRank,
+    /// Term frequency against the loaded corpus statistics.
+    TfIdf,
+}
+
+/// Keyword options in the shape of the TS `KeywordOptions`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct KeywordOptions {
+    pub method: KeywordMethod,
+    /// Number of phrases returned.
+    pub limit: usize,
+    /// Longest phrase, in words.
+    pub max_words: usize,
+    /// TextRank co-occurrence window, in words.
+    pub window: usize,
+    /// ISO 639-1 code choosing the stopwords, such as a chunk's
+    /// `metadata.language`; detected when unset.
+    pub language: Option<String>,
+}
+
+impl Default for KeywordOptions {
+    fn default() -> Self {
+        KeywordOptions {
+            method: KeywordMethod::Rake,
+            limit: 10,
+            max_words: 3,
+            window: 2,
+            language: None,
+        }
+    }
+}
+
+/// A ranked phrase, lowercased, with every place it occurs.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Keyword {
+    pub phrase: String,
+    pub score: f64,
+    pub occurrences: Vec<Position>,
+}
+
+/// Document frequencies of the terms of a corpus, for TF-IDF. Callers
+/// build it document by document and keep it as JSON between sessions.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CorpusStats {
+    pub documents: usize,
+    pub max_words: usize,
+    pub document_frequency: HashMap<String, usize>,
+}
+
+impl CorpusStats {
+    pub fn new(max_words: usize) -> Self {
+        CorpusStats { max_words: max_words.max(1), ..Default::default() }
+    }
+
+    pub fn add_document(&mut self, text: &str) {
+        let terms: HashSet<String> = terms(&candidates(text, None), self.max_words.max(1))
+            .into_keys()
+            .collect();
+        for term in terms {
+            *self.document_frequency.entry(term).or_insert(0) += 1;
+        }
+        self.documents += 1;
+    }
+
+    fn idf(&self, term: &str) -> f64 {
+        let df = self.document_frequency.get(term).copied().unwrap_or(0);
+        ((1.0 + self.documents as f64) / (1.0 + df as f64)).ln() + 1.0
+    }
+}
+
+/// A run of content words with nothing but whitespace between them.
+struct Candidate {
+    words: Vec<Word>,
+    sentence: usize,
+}
+
+struct Word {
+    text: String, // lowercased
+    position: Position,
+}
+
+/// Extracts keywords with the method in `options`; TF-IDF needs `corpus`.
+pub fn extract(text: &str, options: &KeywordOptions, corpus: Option<&CorpusStats>) -> Vec<Keyword> {
+    let candidates = candidates(text, options.language.as_deref());
+    let max_words = options.max_words.max(1);
+    let mut keywords = match (options.method, corpus) {
+        (KeywordMethod::Rake, _) => rake(&candidates, max_words),
+        (KeywordMethod::TextRank, _) => text_rank(&candidates, max_words, options.window.max(1)),
+        (KeywordMethod::TfIdf, Some(corpus)) => tf_idf(&candidates, max_words, corpus),
+        (KeywordMethod::TfIdf, None) => Vec::new(),
+    };
+    keywords.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.phrase.cmp(&b.phrase)));
+    keywords.truncate(options.limit);
+    keywords
+}
+
+fn candidates(text: &str, language: Option<&str>) -> Vec<Candidate> {
+    // Languages without a stopword list still get phrases split at English ones
+    let language = Some(resolve_language(text, language)).filter(|l| has_stopwords(l)).unwrap_or_else(|| "en".to_string());
+    let mut utf16 = Utf16Offsets::new(text);
+    let mut candidates = Vec::new();
+    let mut current: Vec<Word> = Vec::new();
+    let mut sentence = 0;
+
+    for (offset, token) in text.split_word_bound_indices() {
+        if token.chars().all(char::is_whitespace) {
+            if token.contains("\n\n") || token.contains("\r\n\r\n") {
+                sentence += 1;
+            }
+            continue;
+        }
+        let lower = token.to_lowercase();
+        let content = token.chars().any(char::is_alphabetic) && !is_stopword(&language, &lower); // stopwords delimit candidate phrases
+        if content {
+            let position = Position { start: utf16.at(offset), end: utf16.at(offset + token.len()) };
+            current.push(Word { text: lower, position });
+            continue;
+        }
+        if !current.is_empty() {
+            candidates.push(Candidate { words: std::mem::take(&mut current), sentence });
+        }
+        if matches!(token, "." | "!" | "?" | ";" | "。" | "！" | "？") {
+            sentence += 1;
+        }
+    }
+    if !current.is_empty() {
+        candidates.push(Candidate { words: current, sentence });
+    }
+    candidates
+}
+
+fn phrase(words: &[Word]) -> String {
+    words.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" ")
+}
+
+fn span(words: &[Word]) -> Position {
+    Position { start: words[0].position.start, end: words[words.len() - 1].position.end }
+}
+
+/// Collects scored phrases, merging repeated ones.
+fn collect(phrases: impl Iterator<Item = (String, f64, Position)>) -> Vec<Keyword> {
+    let mut keywords: Vec<Keyword> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for (phrase, score, position) in phrases {
+        match index.get(&phrase) {
+            Some(&i) => keywords[i].occurrences.push(position),
+            None => {
+                index.insert(phrase.clone(), keywords.len());
+                keywords.push(Keyword { phrase, score, occurrences: vec![position] });
+            }
+        }
+    }
+    keywords
+}
+
+fn rake(candidates: &[Candidate], max_words: usize) -> Vec<Keyword> {
+    let phrases: Vec<&Candidate> = candidates.iter().filter(|c| c.words.len() <= max_words).collect();
+
+    let mut frequency: HashMap<&str, f64> = HashMap::new();
+    let mut degree: HashMap<&str, f64> = HashMap::new();
+    for candidate in &phrases {
+        for word in &candidate.words {
+            *frequency.entry(&word.text).or_insert(0.0) += 1.0;
+            *degree.entry(&word.text).or_insert(0.0) += candidate.words.len() as f64;
+        }
+    }
+
+    collect(phrases.iter().map(|candidate| {
+        let score = candidate.words.iter().map(|w| degree[w.text.as_str()] / frequency[w.text.as_str()]).sum();
+        (phrase(&candidate.words), score, span(&candidate.words))
+    }))
+}
+
+fn text_rank(candidates: &[Candidate], max_words: usize, window: usize) -> Vec<Keyword> {
+    // Co-occurrence graph over content words within a sentence
+    let mut ids: HashMap<&str, usize> = HashMap::new();
+    let mut edges: Vec<HashMap<usize, f64>> = Vec::new();
+    let mut sequence: Vec<usize> = Vec::new();
+    let mut sentence = usize::MAX;
+    for candidate in candidates {
+        if candidate.sentence != sentence {
+            sentence = candidate.sentence;
+            sequence.clear();
+        }
+        for word in &candidate.words {
+            let id = *ids.entry(&word.text).or_insert_with(|| {
+                edges.push(HashMap::new());
+                edges.len() - 1
+            });
+            for &other in sequence.iter().rev().take(window) {
+                if other != id {
+                    *edges[id].entry(other).or_insert(0.0) += 1.0;
+                    *edges[other].entry(id).or_insert(0.0) += 1.0;
+                }
+            }
+            sequence.push(id);
+        }
+    }
+
+    let ranks = page_rank(&edges);
+    // Keep the top third of words and join adjacent ones into phrases
+    let mut sorted = ranks.clone();
+    sorted.sort_by(|a, b| b.total_cmp(a));
+    let cutoff = sorted.get(sorted.len().div_ceil(3).saturating_sub(1)).copied().unwrap_or(0.0);
+    let is_key = |word: &Word| ranks[ids[word.text.as_str()]] >= cutoff;
+
+    let mut phrases = Vec::new();
+    for candidate in candidates {
+        let mut run_start = None;
+        for i in 0..=candidate.words.len() {
+            match (i < candidate.words.len() && is_key(&candidate.words[i]), run_start) {
+                (true, None) => run_start = Some(i),
+                (false, Some(start)) => {
+                    let words = &candidate.words[start..i];
+                    if words.len() <= max_words {
+                        let score = words.iter().map(|w| ranks[ids[w.text.as_str()]]).sum();
+                        phrases.push((phrase(words), score, span(words)));
+                    }
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+    collect(phrases.into_iter())
+}
+
+/// Weighted PageRank, iterated until the ranks settle.
+pub(crate) fn page_rank(edges: &[HashMap<usize, f64>]) -> Vec<f64> {
+    let n = edges.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    let out_weight: Vec<f64> = edges.iter().map(|e| e.values().sum()).collect();
+    let mut ranks = vec![1.0 / n as f64; n];
+    for _ in 0..MAX_ITERATIONS {
+        let mut next = vec![(1.0 - DAMPING) / n as f64; n];
+        for (node, neighbours) in edges.iter().enumerate() {
+            for (&other, &weight) in neighbours {
+                next[node] += DAMPING * ranks[other] * weight / out_weight[other];
+            }
+        }
+        let delta: f64 = next.iter().zip(&ranks).map(|(a, b)| (a - b).abs()).sum();
+        ranks = next;
+        if delta < CONVERGENCE {
+            break;
+        }
+    }
+    ranks
+}
+
+/// Every n-gram of up to `max_words` words inside a candidate, with its spans.
+fn terms(candidates: &[Candidate], max_words: usize) -> HashMap<String, Vec<Position>> {
+    let mut terms: HashMap<String, Vec<Position>> = HashMap::new();
+    for candidate in candidates {
+        for n in 1..=max_words.min(candidate.words.len()) {
+            for words in candidate.words.windows(n) {
+                terms.entry(phrase(words)).or_default().push(span(words));
+            }
+        }
+    }
+    terms
+}
+
+fn tf_idf(candidates: &[Candidate], max_words: usize, corpus: &CorpusStats) -> Vec<Keyword> {
+    terms(candidates, max_words.min(corpus.max_words.max(1)))
+        .into_iter()
+        .map(|(phrase, mut occurrences)| {
+            occurrences.sort_by_key(|p| p.start);
+            let score = occurrences.len() as f64 * corpus.idf(&phrase);
+            Keyword { phrase, score, occurrences }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "Compatibility of systems of linear constraints over the set of natural numbers. \
+        Criteria of compatibility of a system of linear Diophantine equations, strict inequations, \
+        and nonstrict inequations are considered. Upper bounds for components of a minimal set of \
+        solutions and algorithms of construction of minimal generating sets of solutions for all \
+        types of systems are given.";
+
+    fn phrases(keywords: &[Keyword]) -> Vec<&str> {
+        keywords.iter().map(|k| k.phrase.as_str()).collect()
+    }
+
+    #[test]
+    fn test_rake() {
+        let keywords = extract(TEXT, &KeywordOptions { limit: 3, ..Default::default() }, None);
+        // Ties are broken alphabetically
+        assert_eq!(phrases(&keywords), vec!["linear diophantine equations", "minimal generating sets", "linear constraints"]);
+        assert_eq!(keywords[0].score, 8.5);
+        assert_eq!(keywords[1].score, 8.5);
+        let position = keywords[0].occurrences[0];
+        let utf16: Vec<u16> = TEXT.encode_utf16().collect();
+        assert_eq!(String::from_utf16(&utf16[position.start..position.end]).unwrap(), "linear Diophantine equations");
+    }
+
+    #[test]
+    fn test_stopwords_of_the_text_language() {
+        let text = "Die Kündigung des Vertrags muss schriftlich erfolgen, und die Kündigungsfrist beträgt drei Monate zum Ende des Quartals.";
+        for language in [None, Some("de".to_string())] {
+            let keywords = extract(text, &KeywordOptions { limit: 20, language, ..Default::default() }, None);
+            assert!(phrases(&keywords).contains(&"kündigung"), "{:?}", phrases(&keywords));
+            assert!(phrases(&keywords).iter().flat_map(|p| p.split(' ')).all(|w| !is_stopword("de", w)));
+        }
+    }
+
+    #[test]
+    fn test_text_rank() {
+        let options = KeywordOptions { method: KeywordMethod::TextRank, limit: 5, ..Default::default() };
+        let keywords = extract(TEXT, &options, None);
+        assert_eq!(keywords.len(), 5);
+        assert!(phrases(&keywords).contains(&"minimal set"), "{:?}", phrases(&keywords));
+        assert!(keywords.windows(2).all(|w| w[0].score >= w[1].score));
+    }
+
+    #[test]
+    fn test_tf_idf_against_corpus() {
+        let mut corpus = CorpusStats::new(2);
+        for document in ["The invoice is due in thirty days.", "Each invoice lists the contract number.", "The contract renews every year."] {
+            corpus.add_document(document);
+        }
+        let json = serde_json::to_string(&corpus).unwrap();
+        let corpus: CorpusStats = serde_json::from_str(&json).unwrap();
+        assert_eq!(corpus.documents, 3);
+
+        let options = KeywordOptions { method: KeywordMethod::TfIdf, limit: 2, ..Default::default() };
+        let keywords = extract("The invoice covers freight charges. Freight charges are billed monthly.", &options, Some(&corpus));
+        assert_eq!(phrases(&keywords), vec!["charges", "freight"]);
+        assert_eq!(keywords[0].occurrences.len(), 2);
+        assert!(extract(TEXT, &options, None).is_empty());
+    }
+}
@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+use lazy_static::lazy_static;
+use serde::{Serialize, Deserialize};
+
+/// Maximum number of ranked n-grams kept per profile (Cavnar & Trenkle).
+const PROFILE_SIZE: usize = 300;
+/// Softmax temperature applied to profile similarities when computing confidence.
+const CONFIDENCE_TEMPERATURE: f64 = 0.025;
+/// Below this many letters the n-gram statistics are too sparse to trust fully.
+const MIN_RELIABLE_LETTERS: usize = 40;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Script {
+    Latin,
+    Cyrillic,
+    Arabic,
+    Greek,
+    Hebrew,
+    Han,
+    Kana,
+    Hangul,
+    Thai,
+    Devanagari,
+    Bengali,
+    Gurmukhi,
+    Gujarati,
+    Tamil,
+    Telugu,
+    Kannada,
+    Malayalam,
+    Georgian,
+    Armenian,
+    Ethiopic,
+    Khmer,
+    Lao,
+    Myanmar,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Detection {
+    /// ISO 639-1 code of the detected language.
+    pub language: String,
+    pub script: Script,
+    pub confidence: f64,
+}
+
+struct Profile {
+    language: &'static str,
+    ranks: HashMap<String, usize>,
+}
+
+// Short samples of high-frequency words per language. Profiles are built from
+// these at first use, so adding a language only requires adding a sample here.
+const LATIN_SAMPLES: &[(&str, &str)] = &[
+    ("en", "the of and to a in that it be on as at by or an i have for not with you this but his from they say her she will one all would there their what out about who get which when make can like time just him know take people into year your good some could them see other than then now look only come its over think also back after use two how our work first well way even new want because any these give day most us is are was were been has had said should through where much before right here very those still between never during against under another number system part place world case point group problem fact called based given made information question government something thing being often"),
+    ("de", "der die und in den von zu das mit sich des auf für ist im dem nicht ein eine als auch es an werden aus er hat dass sie nach wird bei einer um am sind noch wie einem über einen so zum war haben nur oder aber vor zur bis mehr durch man sein wurde sei hatte kann gegen vom können schon wenn habe seine ihre dann unter wir soll ich eines jahr zwei jahren diese dieser wieder keine seiner worden möchten schön größer früh müssen zwischen gewesen"),
+    ("fr", "le de un à être et en avoir que pour dans ce il qui ne sur se pas plus pouvoir par je avec tout faire son mettre autre on mais nous comme ou si leur y dire elle devoir avant deux même prendre aussi celui donner bien où fois vous encore nouveau aller cela entre premier vouloir déjà grand mon me moins aucun lui temps très savoir falloir voir quelque sans raison notre dont non an monde jour monsieur demander alors après trouver personne rendre part dernier venir pendant passer peu lequel suite bon comprendre depuis point ainsi heure rester les des du la été était ça"),
+    ("es", "el la de que y a en un ser se no haber por con su para como estar tener le lo todo pero más hacer o poder decir este ir otro ese si me ya ver porque dar cuando él muy sin vez mucho saber qué sobre mi alguno mismo yo también hasta año dos querer entre así primero desde grande eso ni nos llegar pasar tiempo ella sí día uno bien poco deber entonces poner cosa tanto hombre parecer nuestro tan donde ahora parte después vida quedar siempre creer hablar llevar dejar nada cada seguir menos nuevo encontrar los las del está están años español niño señor"),
+    ("pt", "o de a e que do da em um para é com não uma os no se na por mais as dos como mas foi ao ele das tem à seu sua ou ser quando muito há nos já está eu também só pelo pela até isso ela entre era depois sem mesmo aos ter seus quem nas me esse eles estão você tinha foram essa num nem suas meu às minha têm numa pelos elas havia seja qual será nós tenho lhe deles essas esses pelas este fosse dele tu te vocês vos lhes meus minhas ação são então coração"),
+    ("it", "di e il la che in a per un è non una sono del con si da le al i dei della anche come più ma lo ha alla gli nel o se questo ci ho mi ne delle cosa essere tutto lei sua io suo molto hai fare perché sei bene nella quando solo stato era questa tu sul cui loro dopo ancora sempre c'è noi fatto poi qui può così tra prima già fa sia ogni tempo dove tutti nostro quello città però"),
+    ("nl", "de van een het en in is dat op te zijn met voor niet aan er die ook als bij om maar door dan nog wel naar uit worden kan zich ze heeft was dit al meer tot geen wordt hij over werd jaar moet hebben zo veel twee ons mijn onze deze wat wij u haar hun nu waren zou kunnen tussen omdat goed zeer daar waar alleen na toen moeten iets komen gaan zien weten groot nieuwe gemeente hier"),
+    ("sv", "och i att det som en på är av för med till den har de inte om ett han men var jag sig från vi så kan man när år säga hon under också efter eller nu sin där vid mot ska skulle kommer ut få finns vara hade alla andra mycket än här då sedan över bli två blir många bara dem vad göra ha nya måste genom något själv även hos inom redan stor svenska största högre världen människor"),
+    ("da", "og i at det er en til på de som med af for ikke der var den han har sig et om jeg men vi hun fra kan også så da efter skal være ved blev have over eller nu dem havde kunne sin år mange alle andre meget op hvor ud hvad skulle når end bliver min deres mod mellem både første selv dag nogle får går hvis aldrig blive københavn måske næste tilbage sådan større"),
+    ("nb", "og i det på som er en til å han av for med at var de ikke den har jeg om et men så seg hun fra vi ble skal kan da etter også nå eller dem sin være hadde mot sa mange kunne over alle inn når ut noen bare hvor andre vil sine mer år meg deg hvis kommer går må både får opp blitt første norske gjennom først skulle mellom henne dette hvordan bør"),
+    ("fi", "ja on ei se että hän oli olla kun mutta niin mitä tai jos nyt kuin vain myös tämä sen ovat jo sitten minä tässä mukaan kaikki hänen sekä koska voi sitä joka tulee tämän ole voidaan olisi vuonna vielä mikä pitää aina kanssa ennen tehdä näin siitä sillä heidän meidän kuitenkin paljon juuri jälkeen suomen yhteiskunta päivä työ hyvä kaksi yksi mieltä ihmiset välillä täällä häntä"),
+    ("pl", "i w nie na się z do to że jest o jak po co ale tak za od jego go już tylko przez jej czy być są może tym dla który ich mnie był było ten bardzo ja też jeszcze jednak jako kiedy gdzie sobie również bez tego lub nawet tej gdy wszystko można przy więc teraz będzie została został polski także która które których wiele czas dzień życie człowiek rok dwa ludzie między źródło żeby przed miał każdy"),
+    ("cs", "a se na v je že to s z o do i jsem ale jako by jak za pro k tak si od po jsou jeho bylo když už jen není být ve co které který která aby nebo ještě jsme mě ze také může byl mezi roce před podle pouze jejich všechny tím tento této řekl protože právě proto více velmi lidé den čas život člověk země dva práce první další však"),
+    ("sk", "a v sa na je že to s z o do i som ale ako by za pre k tak si od po sú jeho bolo keď už len nie byť vo čo ktoré ktorý ktorá aby alebo ešte sme ma zo tiež môže bol medzi roku pred podľa iba ich všetky tým tento tejto povedal pretože práve preto viac veľmi ľudia deň čas život človek krajina dva práca prvý ktorí ďalší však"),
+    ("hu", "a az és hogy nem is egy van meg volt de csak már el ki még ha mint azt be sem kell lesz vagy most minden mert fel úgy amely pedig le után lehet között így majd nagy által szerint ezt egyik itt amit akkor olyan alatt hogyan évben két első magyar ember idő élet nap munka ország város kerül során mindig több nélkül történt mégis össze"),
+    ("ro", "și de în a la cu că nu pe o un este din pentru care mai se sunt ca au fost sau dar el ea lui ei ce acest această fi prin după până când fără între despre foarte atunci numai trebuie poate acum anul doi oameni țara timp viață ziua lucru toate toți multe mare bine unde cum încă fiecare românia același împreună însă îşi şi ţară"),
+    ("tr", "ve bir bu da de için ile olan çok daha gibi en o ne var ama kadar sonra olarak olduğu her değil ben sen biz onlar şey büyük yeni iki ilk yıl zaman gün insan iş ülke türkiye şimdi nasıl neden çünkü ancak bile göre arasında üzerinde kendi tüm bütün hiç artık oldu olmak etmek yapmak gelmek vermek almak istemek söylemek görmek bilmek düşünmek"),
+    ("id", "yang dan di itu dengan untuk tidak ini dari dalam akan pada juga saya ke karena tersebut bisa ada mereka lebih kata sudah atau saat oleh menjadi orang kami telah hanya harus kita dia bahwa seperti tahun baru setelah masih banyak bagi jika secara dapat sebagai hari antara sangat indonesia pemerintah negara sendiri belum kepada semua tetapi hingga bagaimana mengatakan memiliki"),
+    ("vi", "và của là có được trong cho không những người một các với này đã để khi đến từ ra về như năm thì cũng đó sẽ nhiều tại vào lại theo sau nhưng làm trên nước việt nam rằng còn đang hai phải rất chúng tôi họ bạn mình nói chỉ nên thế nào hơn ngày thời gian cuộc sống công việc"),
+    ("tl", "ang ng sa na at mga ay hindi ako siya ka ko mo niya kami tayo sila nila namin natin ito iyan iyon dito doon para kung pero lang din rin po opo ba naman kasi talaga mas may wala isang dalawa araw taon tao bahay gusto kailangan alam sabi ginawa pilipinas maganda"),
+    ("hr", "i je u se na da za su s od a o ne kao to koji iz što ali bi sam će smo bio bila bilo li pa jer ili kako kad samo još već godine prema nakon između kroz prije tijekom također svoje njegov njihov može mogu treba biti hrvatska vrlo dobro ljudi dana vrijeme život čovjek zemlja dva rad prvi koje koja đak žena"),
+    ("sw", "na ya wa kwa katika ni la za kuwa hiyo huo hii hili yake wake wao sisi mimi wewe yeye lakini pia kama kwamba baada kabla sana zaidi hata tu bado ndiyo hapana watu mtu siku mwaka nchi kazi serikali tanzania kenya habari sasa leo jana kesho kubwa ndogo nzuri moja mbili tatu wakati maisha"),
+];
+
+const CYRILLIC_SAMPLES: &[(&str, &str)] = &[
+    ("ru", "и в не на я быть он с что а по это она этот к но они мы как из у который то за свой весь год от так о для ты же все тот мочь вы человек такой его сказать только или ещё бы себя один уже до время если сам когда другой вот говорить наш мой знать стать при чтобы дело жизнь кто первый очень два день её новый рука даже во со раз где там под можно ну какой после их работа без самый потом надо хотеть ли слово идти большой должен место иметь ничто"),
+    ("uk", "і в не на я бути він з що а по це вона цей до але вони ми як із у який те за свій все рік від так про для ти же той могти ви людина такий його сказати тільки або ще би себе один вже коли інший ось говорити наш мій знати стати при щоб справа життя хто перший дуже два день її новий рука навіть раз де там під можна після їх робота без самий потім треба хотіти слово йти великий повинен місце є україна ґанок"),
+    ("bg", "и в не на да се е за от с че по това тя той те ние като към но които беше са ще има още или му ги й си най този тази тези години време човек живот ден два първи много след преди между само така където когато защото може трябва България работа голям нов всички някои също вече"),
+    ("sr", "и је у се на да за су с од а о не као то који из што али би сам ће смо био била било ли па јер или како кад само још већ године према након између кроз пре током такође своје његов њихов може могу треба бити србија врло добро људи дана време живот човек земља два рад први које која ђак жена љубав њега"),
+];
+
+const ARABIC_SAMPLES: &[(&str, &str)] = &[
+    ("ar", "في من على إلى أن عن مع هذا هذه التي الذي كان قد لا ما هو هي كل بين بعد قبل عند حتى أو ثم لم لن إذا كما أيضا الله يوم سنة العالم الناس الدولة الحكومة العربية مصر السعودية الذين كانت يكون تكون وقال قال عليه عليها منذ خلال ذلك تلك هناك هنا حيث لكن"),
+    ("fa", "و در به از که این را با است آن برای یک خود تا کرد بر هم نیز شده شود کند می‌شود بود کنند ما من او آنها ایران دیگر چه اما یا بسیار پس هر سال روز کار مردم گفت باید چون همه نمی اگر دارد داشت گفته چند وی پیش بین چیزی خیلی زندگی کشور"),
+    ("ur", "اور کے کی میں ہے سے کو کا نے یہ وہ پر بھی ہیں تھا تھی کہ جو ایک کر گیا ہو رہا رہی کیا اس ان کچھ تو لیے ساتھ بعد پاکستان لوگ دن سال کام ہم آپ میرے ہمارے نہیں کہا گئے ہوئے والے جب تک اب یہاں وہاں کیونکہ"),
+];
+
+lazy_static! {
+    static ref LATIN_PROFILES: Vec<Profile> = build_profiles(LATIN_SAMPLES);
+    static ref CYRILLIC_PROFILES: Vec<Profile> = build_profiles(CYRILLIC_SAMPLES);
+    static ref ARABIC_PROFILES: Vec<Profile> = build_profiles(ARABIC_SAMPLES);
+}
+
+fn build_profiles(samples: &[(&'static str, &str)]) -> Vec<Profile> {
+    samples
+        .iter()
+        .map(|&(language, sample)| Profile {
+            language,
+            ranks: ranked_ngrams(sample)
+                .into_iter()
+                .enumerate()
+                .map(|(rank, gram)| (gram, rank))
+                .collect(),
+        })
+        .collect()
+}
+
+/// Returns the 1- to 3-grams of `text` ordered by descending frequency.
+fn ranked_ngrams(text: &str) -> Vec<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for word in text.split(|c: char| !c.is_alphabetic() && c != '\'') {
+        if word.is_empty() {
+            continue;
+        }
+        let padded: Vec<char> = std::iter::once(' ')
+            .chain(word.chars().flat_map(char::to_lowercase))
+            .chain(std::iter::once(' '))
+            .collect();
+        for n in 1..=3 {
+            for window in padded.windows(n) {
+                if n == 1 && window[0] == ' ' {
+                    continue;
+                }
+                *counts.entry(window.iter().collect()).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut ranked: Vec<(String, usize)> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked.truncate(PROFILE_SIZE);
+    ranked.into_iter().map(|(gram, _)| gram).collect()
+}
+
+/// Out-of-place distance between a document profile and a language profile,
+/// normalized to `[0, 1]`.
+fn profile_distance(document: &[String], profile: &Profile) -> f64 {
+    if document.is_empty() {
+        return 1.0;
+    }
+    let total: usize = document
+        .iter()
+        .enumerate()
+        .map(|(rank, gram)| match profile.ranks.get(gram) {
+            Some(&other) => rank.abs_diff(other),
+            None => PROFILE_SIZE,
+        })
+        .sum();
+    total as f64 / (document.len() * PROFILE_SIZE) as f64
+}
+
+fn script_of(c: char) -> Option<Script> {
+    let script = match c as u32 {
+        0x0041..=0x005A | 0x0061..=0x007A | 0x00C0..=0x024F | 0x1E00..=0x1EFF => Script::Latin,
+        0x0370..=0x03FF | 0x1F00..=0x1FFF => Script::Greek,
+        0x0400..=0x052F => Script::Cyrillic,
+        0x0530..=0x058F => Script::Armenian,
+        0x0590..=0x05FF => Script::Hebrew,
+        0x0600..=0x06FF | 0x0750..=0x077F | 0xFB50..=0xFDFF | 0xFE70..=0xFEFF => Script::Arabic,
+        0x0900..=0x097F => Script::Devanagari,
+        0x0980..=0x09FF => Script::Bengali,
+        0x0A00..=0x0A7F => Script::Gurmukhi,
+        0x0A80..=0x0AFF => Script::Gujarati,
+        0x0B80..=0x0BFF => Script::Tamil,
+        0x0C00..=0x0C7F => Script::Telugu,
+        0x0C80..=0x0CFF => Script::Kannada,
+        0x0D00..=0x0D7F => Script::Malayalam,
+        0x0E00..=0x0E7F => Script::Thai,
+        0x0E80..=0x0EFF => Script::Lao,
+        0x1000..=0x109F => Script::Myanmar,
+        0x10A0..=0x10FF => Script::Georgian,
+        0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => Script::Hangul,
+        0x1200..=0x139F => Script::Ethiopic,
+        0x1780..=0x17FF => Script::Khmer,
+        0x3040..=0x30FF | 0x31F0..=0x31FF | 0xFF66..=0xFF9F => Script::Kana,
+        0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0x20000..=0x2FA1F => Script::Han,
+        _ => return None,
+    };
+    Some(script)
+}
+
+/// Language implied by a script used (almost) exclusively for one language.
+fn script_language(script: Script) -> Option<&'static str> {
+    match script {
+        Script::Greek => Some("el"),
+        Script::Armenian => Some("hy"),
+        Script::Hebrew => Some("he"),
+        Script::Devanagari => Some("hi"),
+        Script::Bengali => Some("bn"),
+        Script::Gurmukhi => Some("pa"),
+        Script::Gujarati => Some("gu"),
+        Script::Tamil => Some("ta"),
+        Script::Telugu => Some("te"),
+        Script::Kannada => Some("kn"),
+        Script::Malayalam => Some("ml"),
+        Script::Thai => Some("th"),
+        Script::Lao => Some("lo"),
+        Script::Myanmar => Some("my"),
+        Script::Georgian => Some("ka"),
+        Script::Hangul => Some("ko"),
+        Script::Ethiopic => Some("am"),
+        Script::Khmer => Some("km"),
+        Script::Han => Some("zh"),
+        Script::Kana => Some("ja"),
+        Script::Latin | Script::Cyrillic | Script::Arabic => None,
+    }
+}
+
+/// Counts letters per script and returns them by descending frequency.
+fn script_counts(text: &str) -> Vec<(Script, usize)> {
+    let mut counts: HashMap<Script, usize> = HashMap::new();
+    for c in text.chars() {
+        if let Some(script) = script_of(c) {
+            *counts.entry(script).or_insert(0) += 1;
+        }
+    }
+    let mut counts: Vec<(Script, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| (a.0 as u8).cmp(&(b.0 as u8))));
+    counts
+}
+
+/// Identifies the language of `text`, or `None` if it contains no letters.
+pub fn detect(text: &str) -> Option<Detection> {
+    let counts = script_counts(text);
+    let total: usize = counts.iter().map(|&(_, n)| n).sum();
+    let &(mut script, mut count) = counts.first()?;
+
+    // Japanese mixes kanji with kana; any meaningful share of kana marks it.
+    let kana = counts.iter().find(|&&(s, _)| s == Script::Kana).map_or(0, |&(_, n)| n);
+    if script == Script::Han && kana * 10 >= count {
+        script = Script::Kana;
+        count += counts.iter().find(|&&(s, _)| s == Script::Han).map_or(0, |&(_, n)| n);
+    } else if script == Script::Kana {
+        count += counts.iter().find(|&&(s, _)| s == Script::Han).map_or(0, |&(_, n)| n);
+    }
+    let script_share = count as f64 / total as f64;
+
+    if let Some(language) = script_language(script) {
+        return Some(Detection {
+            language: language.to_string(),
+            script,
+            confidence: script_share,
+        });
+    }
+
+    let profiles: &[Profile] = match script {
+        Script::Latin => &LATIN_PROFILES,
+        Script::Cyrillic => &CYRILLIC_PROFILES,
+        _ => &ARABIC_PROFILES,
+    };
+    let document = ranked_ngrams(text);
+    let mut scored: Vec<(&'static str, f64)> = profiles
+        .iter()
+        .map(|profile| (profile.language, 1.0 - profile_distance(&document, profile)))
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let best = scored[0].1;
+    let partition: f64 = scored
+        .iter()
+        .map(|&(_, similarity)| ((similarity - best) / CONFIDENCE_TEMPERATURE).exp())
+        .sum();
+    let reliability = (count as f64 / MIN_RELIABLE_LETTERS as f64).min(1.0);
+
+    Some(Detection {
+        language: scored[0].0.to_string(),
+        script,
+        confidence: script_share * reliability / partition,
+    })
+}
+
+/// Combines per-chunk `(language, confidence, length)` results, weighted by
+/// chunk length, into a verdict for the whole input so callers need not scan
+/// the text twice.
+pub fn aggregate<'a, I>(detections: I) -> Option<(String, f64)>
+where
+    I: IntoIterator<Item = (&'a str, f64, usize)>,
+{
+    let mut weights: HashMap<&str, f64> = HashMap::new();
+    let mut total = 0.0;
+    for (language, confidence, len) in detections {
+        *weights.entry(language).or_insert(0.0) += confidence * len as f64;
+        total += len as f64;
+    }
+
+    let (language, weight) = weights
+        .into_iter()
+        .max_by(|a, b| a.1.total_cmp(&b.1).then_with(|| b.0.cmp(a.0)))?;
+    Some((language.to_string(), if total > 0.0 { weight / total } else { 0.0 }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn language(text: &str) -> String {
+        detect(text).expect("language detected").language
+    }
+
+    #[test]
+    fn test_detects_latin_languages() {
+        assert_eq!(language("The quarterly report shows that the company has grown faster than expected."), "en");
+        assert_eq!(language("Der Bericht zeigt, dass das Unternehmen schneller gewachsen ist als erwartet."), "de");
+        assert_eq!(language("Le rapport montre que la société a connu une croissance plus rapide que prévu."), "fr");
+        assert_eq!(language("El informe muestra que la empresa ha crecido más rápido de lo esperado."), "es");
+        assert_eq!(language("O relatório mostra que a empresa cresceu mais rápido do que o esperado."), "pt");
+        assert_eq!(language("Il rapporto mostra che la società è cresciuta più velocemente del previsto."), "it");
+        assert_eq!(language("Het rapport laat zien dat het bedrijf sneller is gegroeid dan verwacht."), "nl");
+        assert_eq!(language("Raport pokazuje, że firma rozwijała się szybciej niż oczekiwano."), "pl");
+    }
+
+    #[test]
+    fn test_samples_are_plain_word_lists() {
+        for &(language, sample) in LATIN_SAMPLES.iter().chain(CYRILLIC_SAMPLES).chain(ARABIC_SAMPLES) {
+            let mut seen = std::collections::HashSet::new();
+            let repeated: Vec<&str> = sample.split_whitespace().filter(|&word| !seen.insert(word)).collect();
+            assert!(repeated.is_empty(), "{} repeats {:?}", language, repeated);
+        }
+    }
+
+    #[test]
+    fn test_detects_by_script_and_profile() {
+        assert_eq!(language("Отчёт показывает, что компания росла быстрее, чем ожидалось."), "ru");
+        assert_eq!(language("Звіт показує, що компанія зростала швидше, ніж очікувалося."), "uk");
+        assert_eq!(language("يظهر التقرير أن الشركة نمت بشكل أسرع من المتوقع"), "ar");
+        assert_eq!(language("报告显示公司的增长速度超出预期。"), "zh");
+        assert_eq!(language("報告書によると、会社は予想以上に成長しました。"), "ja");
+        assert_eq!(language("보고서에 따르면 회사는 예상보다 빠르게 성장했습니다."), "ko");
+        assert_eq!(language("Η έκθεση δείχνει ότι η εταιρεία αναπτύχθηκε ταχύτερα."), "el");
+    }
+
+    #[test]
+    fn test_confidence_reflects_evidence() {
+        assert!(detect("12345 -- 678").is_none());
+
+        let long = detect("The committee will publish the final results of the review after the board has approved the budget for next year.").unwrap();
+        let short = detect("the end").unwrap();
+        assert!(long.confidence > 0.5, "confidence was {}", long.confidence);
+        assert!(long.confidence <= 1.0);
+        assert!(short.confidence < long.confidence);
+    }
+
+    #[test]
+    fn test_aggregate_weights_by_length() {
+        let (language, confidence) = aggregate(vec![("en", 0.9, 1000), ("de", 0.9, 100)]).unwrap();
+        assert_eq!(language, "en");
+        assert!(confidence > 0.8);
+        assert!(aggregate(Vec::new()).is_none());
+    }
+}
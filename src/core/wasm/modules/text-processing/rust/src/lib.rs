@@ -1,43 +1,253 @@
 use wasm_bindgen::prelude::*;
 use unicode_segmentation::UnicodeSegmentation;
-use unicode_normalization::UnicodeNormalization;
 use regex::Regex;
 use lazy_static::lazy_static;
 use serde::{Serialize, Deserialize};
+use thiserror::Error;
 use std::mem;
 
+mod analysis;
+mod api;
+mod classify;
+mod diff;
+mod encoding;
+mod extraction;
+mod fingerprint;
+mod fuzzy;
+mod gazetteer;
+mod hnsw;
+mod html;
+mod keywords;
+mod language;
+mod normalize;
+mod readability;
+mod redaction;
+mod search;
+mod semantic;
+mod sentences;
+mod sentiment;
+mod streaming;
+mod structure;
+mod summarize;
+mod temporal;
+mod tokenizer;
+use classify::Classifier;
+use extraction::Utf16Offsets;
+use semantic::SemanticOptions;
+use sentences::Segmenter;
+use sentiment::{SentimentOptions, SentimentScore};
+use fingerprint::DuplicateIndex;
+use gazetteer::Gazetteer;
+use hnsw::VectorIndex;
+use html::HtmlOptions;
+use keywords::CorpusStats;
+use normalize::NormalizationOptions;
+use redaction::RedactionPolicy;
+use search::SearchIndex;
+use streaming::StreamingChunker;
+use tokenizer::{Tokenizer, TokenizerError, TokenizerFormat};
+
+#[derive(Error, Debug)]
+pub enum TextProcessingError {
+    #[error("Chunking by tokens requires a loaded tokenizer")]
+    TokenizerMissing,
+    #[error("Tokenizer loading failed: {0}")]
+    TokenizerError(#[from] TokenizerError),
+    #[error("Streaming supports only the fixed chunking strategy")]
+    UnsupportedStreamingStrategy,
+    #[error("No stream in progress; call begin_stream first")]
+    StreamNotStarted,
+    #[error("Invalid options: {0}")]
+    InvalidOptions(String),
+    #[error("Gazetteer loading failed: {0}")]
+    GazetteerError(#[from] aho_corasick::BuildError),
+    #[error("Streams cannot be redacted; redact the whole text instead")]
+    UnsupportedStreamingRedaction,
+    #[error("Streams cannot be normalized; normalize the whole text instead")]
+    UnsupportedStreamingNormalization,
+    #[error("Streams cannot convert HTML; convert the whole document instead")]
+    UnsupportedStreamingHtml,
+    #[error("Redacted text does not match its mapping")]
+    RedactionMismatch,
+    #[error("TF-IDF keywords require corpus statistics")]
+    CorpusMissing,
+    #[error("Search index could not be read: {0}")]
+    InvalidIndex(String),
+    #[error("Classifier model could not be read: {0}")]
+    InvalidModel(String),
+    #[error("Classification requires a trained or loaded classifier")]
+    ClassifierMissing,
+    #[error("Vector has {actual} dimensions but the index has {expected}")]
+    DimensionMismatch { expected: usize, actual: usize },
+    #[error("{len} bytes at {ptr} lie outside the processor's memory")]
+    OutOfBounds { ptr: usize, len: usize },
+}
+
 #[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct TextChunk {
     text: String,
     start: usize,
     end: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token_count: Option<usize>,
+    #[serde(default)] // optional in chunks coming from JS
     metadata: ChunkMetadata,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
 pub struct ChunkMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     language: Option<String>,
+    #[serde(default)]
     confidence: f64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    heading_path: Vec<String>, // enclosing Markdown headings, outermost first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sentiment: Option<SentimentScore>, // when the options asked for sentiment
+}
+
+/// A chunk as produced by a chunking strategy, in byte offsets, before
+/// token counting and language detection.
+struct ChunkSpan {
+    start: usize,
+    end: usize,
+    text: String,
+    token_count: Option<usize>,
+    heading_path: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SizeUnit {
+    #[default]
+    Graphemes,
+    Tokens,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ChunkStrategy {
+    /// Fixed-size windows ending at the last sentence boundary.
+    #[default]
+    Fixed,
+    /// Markdown blocks packed under their heading path.
+    Structural,
+    /// Sentences grouped until the topic shifts.
+    Semantic,
 }
 
+/// Chunking options in the shape of the TS `TextProcessingConfig['options']`;
+/// missing fields take their defaults.
 #[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct ProcessingConfig {
     chunk_size: usize,
     overlap: usize,
     preserve_whitespace: bool,
     preserve_newlines: bool,
     trim_chunks: bool,
+    size_unit: SizeUnit, // unit of chunk_size and overlap
+    strategy: ChunkStrategy,
+    semantic: SemanticOptions,
+    language: Option<String>, // chooses sentence boundary rules; detected when unset
+    encoding: Option<String>, // label of byte input; detected when unset or "auto"
+    html: Option<HtmlOptions>, // input is HTML, converted to text before anything else
+    normalization: Option<NormalizationOptions>, // applied first
+    redaction: Option<RedactionPolicy>, // applied before chunking
+    sentiment: Option<SentimentOptions>, // scores each chunk
+}
+
+impl Default for ProcessingConfig {
+    fn default() -> Self {
+        ProcessingConfig {
+            chunk_size: 1024,
+            overlap: 200,
+            preserve_whitespace: false,
+            preserve_newlines: true,
+            trim_chunks: true,
+            size_unit: SizeUnit::Graphemes,
+            strategy: ChunkStrategy::Fixed,
+            semantic: SemanticOptions::default(),
+            language: None,
+            encoding: None,
+            html: None,
+            normalization: None,
+            redaction: None,
+            sentiment: None,
+        }
+    }
 }
 
 lazy_static! {
-    static ref SENTENCE_BOUNDARY: Regex = Regex::new(r"[.!?]+\s+").unwrap();
     static ref PARAGRAPH_BOUNDARY: Regex = Regex::new(r"\n\s*\n").unwrap();
 }
 
+/// Exclusive end unit of the fixed window starting at `start`: the last
+/// sentence boundary past the overlap if the window is full, else its end.
+fn window_end(text: &str, units: &[(usize, usize)], start: usize, config: &ProcessingConfig) -> usize {
+    let end = (start + config.chunk_size.max(1)).min(units.len());
+
+    // Find natural boundary if possible
+    if end < units.len() {
+        // One unit of lookahead shows whether a sentence starts right at the window end
+        let (from, to) = (units[start].0, units[end].0);
+        let window = &text[from..units[end].1];
+        if let Some(boundary) = Segmenter::for_text(window, config.language.as_deref())
+            .spans(window)
+            .into_iter()
+            .map(|(s, _)| from + s)
+            .rfind(|&s| s > from && s <= to) {
+            let unit = units.partition_point(|&(s, _)| s < boundary);
+            if unit > start + config.overlap {
+                return unit;
+            }
+        }
+    }
+    end
+}
+
+/// Start of the window after `[start, chunk_end)`, backing up by `overlap`
+/// units as long as that still makes progress.
+fn next_window_start(start: usize, chunk_end: usize, overlap: usize) -> usize {
+    if chunk_end > start + overlap {
+        chunk_end - overlap
+    } else {
+        chunk_end
+    }
+}
+
+fn fixed_span(text: &str, units: &[(usize, usize)], start: usize, chunk_end: usize, config: &ProcessingConfig) -> ChunkSpan {
+    let (mut byte_start, mut byte_end) = (units[start].0, units[chunk_end - 1].1);
+    if config.trim_chunks {
+        // Offsets cover the trimmed text, so that they slice out `text`
+        let untrimmed = &text[byte_start..byte_end];
+        byte_start += untrimmed.len() - untrimmed.trim_start().len();
+        byte_end = byte_start + untrimmed.trim().len();
+    }
+
+    ChunkSpan {
+        start: byte_start,
+        end: byte_end,
+        text: text[byte_start..byte_end].to_string(),
+        token_count: (config.size_unit == SizeUnit::Tokens).then_some(chunk_end - start),
+        heading_path: Vec::new(),
+    }
+}
+
 #[wasm_bindgen]
 pub struct TextProcessor {
     memory: Vec<u8>,
     allocated: Vec<(usize, usize)>, // (ptr, size) pairs
+    tokenizer: Option<Tokenizer>,
+    stream: Option<StreamingChunker>,
+    gazetteer: Option<Gazetteer>,
+    corpus: Option<CorpusStats>,
+    index: SearchIndex,
+    vectors: VectorIndex,
+    duplicates: DuplicateIndex,
+    classifier: Option<Classifier>,
 }
 
 #[wasm_bindgen]
@@ -47,6 +257,14 @@ impl TextProcessor {
         TextProcessor {
             memory: Vec::with_capacity(1024 * 1024), // 1MB initial capacity
             allocated: Vec::new(),
+            tokenizer: None,
+            stream: None,
+            gazetteer: None,
+            corpus: None,
+            index: SearchIndex::new(),
+            vectors: VectorIndex::new(Default::default()),
+            duplicates: DuplicateIndex::new(&Default::default()),
+            classifier: None,
         }
     }
 
@@ -64,90 +282,230 @@ impl TextProcessor {
         }
     }
 
-    pub fn process_text(&mut self, text_ptr: usize) -> usize {
-        let text = self.read_string(text_ptr);
-        let chunks = self.chunk_text_impl(&text, &ProcessingConfig {
-            chunk_size: 1024,
-            overlap: 200,
-            preserve_whitespace: false,
-            preserve_newlines: true,
-            trim_chunks: true,
-        });
+    /// Legacy pointer API: takes `text_len` bytes of text written into `memory`,
+    /// in any encoding `processBytes` detects, and returns a pointer to the
+    /// result record. New callers use `process`.
+    pub fn process_text(&mut self, text_ptr: usize, text_len: usize) -> Result<usize, JsValue> {
+        let text = self.read_string(text_ptr, text_len, None)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let chunks = self.chunk_text_impl(&text, &ProcessingConfig::default())
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
 
-        self.write_chunks(&chunks)
+        Ok(self.write_chunks(&chunks))
     }
 
-    pub fn chunk_text(&mut self, text_ptr: usize, config_ptr: usize) -> usize {
-        let text = self.read_string(text_ptr);
-        let config = self.read_config(config_ptr);
-        let chunks = self.chunk_text_impl(&text, &config);
-        self.write_chunks(&chunks)
+    /// Legacy pointer API with a config record of nine i32s; see `chunk` and
+    /// `read_config`. The text is read in the record's encoding.
+    pub fn chunk_text(&mut self, text_ptr: usize, text_len: usize, config_ptr: usize) -> Result<usize, JsValue> {
+        let config = self.read_config(config_ptr)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let text = self.read_string(text_ptr, text_len, config.encoding.as_deref())
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let chunks = self.chunk_text_impl(&text, &config)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(self.write_chunks(&chunks))
     }
 
-    fn chunk_text_impl(&self, text: &str, config: &ProcessingConfig) -> Vec<TextChunk> {
-        let mut chunks = Vec::new();
-        let graphemes: Vec<_> = text.graphemes(true).collect();
-        let mut start = 0;
+    /// Loads the tokenizer used for token counts and token-sized chunks.
+    /// `format` is 0 for a WordPiece `vocab.txt`, 1 for a byte-level BPE
+    /// `merges.txt` and 2 for a HuggingFace `tokenizer.json`.
+    pub fn load_tokenizer(&mut self, data_ptr: usize, data_len: usize, format: u32) -> Result<(), JsValue> {
+        let data = self.read_string(data_ptr, data_len, None)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let tokenizer = TokenizerFormat::try_from(format)
+            .and_then(|format| Tokenizer::load(&data, format))
+            .map_err(|e| JsValue::from_str(&TextProcessingError::from(e).to_string()))?;
+        self.tokenizer = Some(tokenizer);
+        Ok(())
+    }
 
-        while start < graphemes.len() {
-            let end = (start + config.chunk_size).min(graphemes.len());
-            let mut chunk_end = end;
-
-            // Find natural boundary if possible
-            if end < graphemes.len() {
-                if let Some(boundary) = SENTENCE_BOUNDARY.find_iter(&graphemes[start..end].concat())
-                    .map(|m| start + m.end())
-                    .last() {
-                    chunk_end = boundary;
-                }
-            }
+    /// Starts chunking text that arrives in pieces through `push_text`.
+    /// Only the fixed strategy can be streamed; a stream in progress is discarded.
+    pub fn begin_stream(&mut self, config_ptr: usize) -> Result<(), JsValue> {
+        let config = self.read_config(config_ptr)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.begin_stream_impl(config)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
 
-            let chunk_text = graphemes[start..chunk_end].concat();
-            let chunk_text = if config.trim_chunks {
-                chunk_text.trim().to_string()
-            } else {
-                chunk_text
-            };
+    /// Appends text to the stream and returns the chunks completed so far,
+    /// with offsets counted from the start of the stream. Each piece is read
+    /// in the stream's encoding and should end on a character boundary.
+    pub fn push_text(&mut self, text_ptr: usize, text_len: usize) -> Result<usize, JsValue> {
+        let encoding = self.stream.as_ref().and_then(|stream| stream.config().encoding.clone());
+        let text = self.read_string(text_ptr, text_len, encoding.as_deref())
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let chunks = self.push_text_impl(&text)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(self.write_chunks(&chunks))
+    }
+
+    /// Ends the stream and returns its remaining chunks.
+    pub fn finish_stream(&mut self) -> Result<usize, JsValue> {
+        let chunks = self.finish_stream_impl()
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(self.write_chunks(&chunks))
+    }
+
+    fn begin_stream_impl(&mut self, config: ProcessingConfig) -> Result<(), TextProcessingError> {
+        if config.strategy != ChunkStrategy::Fixed {
+            return Err(TextProcessingError::UnsupportedStreamingStrategy);
+        }
+        if config.redaction.is_some() {
+            return Err(TextProcessingError::UnsupportedStreamingRedaction);
+        }
+        if config.normalization.is_some() {
+            return Err(TextProcessingError::UnsupportedStreamingNormalization);
+        }
+        if config.html.is_some() {
+            return Err(TextProcessingError::UnsupportedStreamingHtml);
+        }
+        if config.size_unit == SizeUnit::Tokens && self.tokenizer.is_none() {
+            return Err(TextProcessingError::TokenizerMissing);
+        }
+        self.stream = Some(StreamingChunker::new(config));
+        Ok(())
+    }
+
+    fn push_text_impl(&mut self, text: &str) -> Result<Vec<TextChunk>, TextProcessingError> {
+        let stream = self.stream.as_mut().ok_or(TextProcessingError::StreamNotStarted)?;
+        let chunks = stream.push(text, self.tokenizer.as_ref());
+        let sentiment = stream.config().sentiment.clone();
+        Ok(chunks.into_iter().map(|c| self.finish_chunk(c.span, c.start, c.end, sentiment.as_ref())).collect())
+    }
+
+    fn finish_stream_impl(&mut self) -> Result<Vec<TextChunk>, TextProcessingError> {
+        let mut stream = self.stream.take().ok_or(TextProcessingError::StreamNotStarted)?;
+        let chunks = stream.finish(self.tokenizer.as_ref());
+        Ok(chunks.into_iter().map(|c| self.finish_chunk(c.span, c.start, c.end, stream.config().sentiment.as_ref())).collect())
+    }
 
-            chunks.push(TextChunk {
-                text: chunk_text,
-                start,
-                end: chunk_end,
-                metadata: ChunkMetadata {
-                    language: None,
-                    confidence: 1.0,
-                },
-            });
-
-            start = chunk_end - config.overlap;
+    fn chunk_text_impl(&self, text: &str, config: &ProcessingConfig) -> Result<Vec<TextChunk>, TextProcessingError> {
+        if config.size_unit == SizeUnit::Tokens && self.tokenizer.is_none() {
+            return Err(TextProcessingError::TokenizerMissing);
         }
+        let graphemes: Vec<usize> = text.grapheme_indices(true)
+            .map(|(i, _)| i)
+            .chain(std::iter::once(text.len()))
+            .collect();
+
+        let spans = match config.strategy {
+            ChunkStrategy::Fixed => self.fixed_spans(text, &graphemes, config),
+            ChunkStrategy::Structural => structure::chunk(text, config, &|s| self.measure(s, config)),
+            ChunkStrategy::Semantic => semantic::chunk(text, config, &|s| self.measure(s, config)),
+        };
 
-        chunks
+        // Starts and ends each ascend, so one pass apiece converts them
+        let (mut starts, mut ends) = (Utf16Offsets::new(text), Utf16Offsets::new(text));
+        Ok(spans.into_iter().map(|span| {
+            let (start, end) = (starts.at(span.start), ends.at(span.end));
+            self.finish_chunk(span, start, end, config.sentiment.as_ref())
+        }).collect())
     }
 
-    fn read_string(&self, ptr: usize) -> String {
-        let mut len = 0;
-        while self.memory[ptr + len] != 0 {
-            len += 1;
+    /// Adds token count, language and, if asked for, sentiment to a span
+    /// whose UTF-16 offsets are known.
+    fn finish_chunk(&self, span: ChunkSpan, start: usize, end: usize, sentiment: Option<&SentimentOptions>) -> TextChunk {
+        let token_count = span.token_count
+            .or_else(|| self.tokenizer.as_ref().map(|t| t.count(&span.text)));
+        let detection = language::detect(&span.text);
+        let sentiment = sentiment.map(|options| sentiment::passage(&span.text, options));
+        TextChunk {
+            text: span.text,
+            start,
+            end,
+            token_count,
+            metadata: ChunkMetadata {
+                confidence: detection.as_ref().map_or(0.0, |d| d.confidence),
+                language: detection.map(|d| d.language),
+                heading_path: span.heading_path,
+                sentiment,
+            },
         }
-        String::from_utf8_lossy(&self.memory[ptr..ptr + len]).to_string()
     }
 
-    fn read_config(&self, ptr: usize) -> ProcessingConfig {
-        let slice = unsafe {
-            std::slice::from_raw_parts(
-                self.memory[ptr..].as_ptr() as *const i32,
-                5,
-            )
+    /// Size of `text` in the configured unit.
+    fn measure(&self, text: &str, config: &ProcessingConfig) -> usize {
+        match (config.size_unit, self.tokenizer.as_ref()) {
+            (SizeUnit::Tokens, Some(tokenizer)) => tokenizer.count(text),
+            _ => text.graphemes(true).count(),
+        }
+    }
+
+    fn fixed_spans(&self, text: &str, graphemes: &[usize], config: &ProcessingConfig) -> Vec<ChunkSpan> {
+        let mut spans = Vec::new();
+
+        // Byte spans of the units chunk_size and overlap are measured in
+        let units: Vec<(usize, usize)> = match (config.size_unit, self.tokenizer.as_ref()) {
+            (SizeUnit::Tokens, Some(tokenizer)) => tokenizer.tokenize(text)
+                .into_iter()
+                .map(|token| {
+                    // Byte-level tokens may end inside a character; widen to graphemes
+                    let start = graphemes[graphemes.partition_point(|&g| g <= token.start) - 1];
+                    let end = graphemes[graphemes.partition_point(|&g| g < token.end)];
+                    (start, end)
+                })
+                .collect(),
+            _ => graphemes.windows(2).map(|w| (w[0], w[1])).collect(),
         };
 
-        ProcessingConfig {
-            chunk_size: slice[0] as usize,
-            overlap: slice[1] as usize,
-            preserve_whitespace: slice[2] != 0,
-            preserve_newlines: slice[3] != 0,
-            trim_chunks: slice[4] != 0,
+        let mut start = 0;
+        while start < units.len() {
+            let chunk_end = window_end(text, &units, start, config);
+            spans.push(fixed_span(text, &units, start, chunk_end, config));
+
+            if chunk_end >= units.len() {
+                break;
+            }
+            start = next_window_start(start, chunk_end, config.overlap);
         }
+
+        spans
+    }
+
+    fn read_bytes(&self, ptr: usize, len: usize) -> Result<&[u8], TextProcessingError> {
+        ptr.checked_add(len)
+            .and_then(|end| self.memory.get(ptr..end))
+            .ok_or(TextProcessingError::OutOfBounds { ptr, len })
+    }
+
+    /// Reads `len` bytes at `ptr` as text in the encoding labelled `encoding`,
+    /// or the detected one when it is unset or "auto".
+    fn read_string(&self, ptr: usize, len: usize, encoding: Option<&str>) -> Result<String, TextProcessingError> {
+        Ok(encoding::decode(self.read_bytes(ptr, len)?, encoding)?.text)
+    }
+
+    /// Reads a config record of nine little-endian i32s: chunk size, overlap,
+    /// the three flags, size unit, strategy, then the pointer and length of an
+    /// ASCII encoding label, with length 0 to detect the encoding.
+    fn read_config(&self, ptr: usize) -> Result<ProcessingConfig, TextProcessingError> {
+        let record = self.read_bytes(ptr, 9 * 4)?;
+        let field = |i: usize| i32::from_le_bytes([record[4 * i], record[4 * i + 1], record[4 * i + 2], record[4 * i + 3]]);
+        let encoding = match field(8) {
+            0 => None,
+            len => Some(String::from_utf8_lossy(self.read_bytes(field(7) as usize, len as usize)?).into_owned()),
+        };
+
+        Ok(ProcessingConfig {
+            chunk_size: field(0) as usize,
+            overlap: field(1) as usize,
+            preserve_whitespace: field(2) != 0,
+            preserve_newlines: field(3) != 0,
+            trim_chunks: field(4) != 0,
+            size_unit: if field(5) == 1 { SizeUnit::Tokens } else { SizeUnit::Graphemes },
+            strategy: match field(6) {
+                1 => ChunkStrategy::Structural,
+                2 => ChunkStrategy::Semantic,
+                _ => ChunkStrategy::Fixed,
+            },
+            semantic: SemanticOptions::default(),
+            language: None,
+            encoding,
+            html: None,
+            normalization: None,
+            redaction: None,
+            sentiment: None,
+        })
     }
 
     fn write_chunks(&mut self, chunks: &[TextChunk]) -> usize {
@@ -157,10 +515,14 @@ impl TextProcessor {
         let chunks_data = chunks.iter().map(|chunk| {
             let text_ptr = self.write_string(&chunk.text);
             let metadata_ptr = self.write_metadata(&chunk.metadata);
-            (text_ptr, chunk.start, chunk.end, metadata_ptr)
+            (text_ptr, chunk.start, chunk.end, metadata_ptr, chunk.token_count)
         }).collect::<Vec<_>>();
 
         let chunks_ptr = self.write_chunk_data(&chunks_data);
+        let metadata_ptr = match Self::document_metadata(chunks) {
+            Some(metadata) => self.write_metadata(&metadata),
+            None => 0,
+        };
 
         // Write result structure
         let result_slice = unsafe {
@@ -171,12 +533,25 @@ impl TextProcessor {
         };
 
         result_slice[0] = chunks.len() as i32;
-        result_slice[1] = 0; // metadata_ptr (global metadata not implemented yet)
+        result_slice[1] = metadata_ptr as i32;
         result_slice[2] = chunks_ptr as i32;
 
         result_ptr
     }
 
+    fn document_metadata(chunks: &[TextChunk]) -> Option<ChunkMetadata> {
+        let (language, confidence) = language::aggregate(chunks.iter().filter_map(|chunk| {
+            let language = chunk.metadata.language.as_deref()?;
+            Some((language, chunk.metadata.confidence, chunk.end - chunk.start))
+        }))?;
+        Some(ChunkMetadata {
+            language: Some(language),
+            confidence,
+            heading_path: Vec::new(),
+            sentiment: None,
+        })
+    }
+
     fn write_string(&mut self, s: &str) -> usize {
         let bytes = s.as_bytes();
         let ptr = self.allocate(bytes.len() + 1);
@@ -186,26 +561,36 @@ impl TextProcessor {
     }
 
     fn write_metadata(&mut self, metadata: &ChunkMetadata) -> usize {
-        let ptr = self.allocate(16); // 8 bytes for language ptr, 8 bytes for confidence
+        // Write strings first: allocating may move `memory`
+        let language_ptr = match &metadata.language {
+            Some(lang) => self.write_string(lang),
+            None => 0,
+        };
+        let heading_path_ptr = if metadata.heading_path.is_empty() {
+            0
+        } else {
+            let json = serde_json::to_string(&metadata.heading_path).unwrap_or_default();
+            self.write_string(&json)
+        };
+
+        let ptr = self.allocate(24); // 8 bytes each for language ptr, confidence, heading path JSON ptr
         let slice = unsafe {
             std::slice::from_raw_parts_mut(
                 self.memory[ptr..].as_mut_ptr() as *mut u64,
-                2,
+                3,
             )
         };
 
-        slice[0] = match &metadata.language {
-            Some(lang) => self.write_string(lang) as u64,
-            None => 0,
-        };
+        slice[0] = language_ptr as u64;
         slice[1] = metadata.confidence.to_bits();
+        slice[2] = heading_path_ptr as u64;
 
         ptr
     }
 
-    fn write_chunk_data(&mut self, chunks_data: &[(usize, usize, usize, usize)]) -> usize {
+    fn write_chunk_data(&mut self, chunks_data: &[(usize, usize, usize, usize, Option<usize>)]) -> usize {
         let ptr = self.allocate(chunks_data.len() * 24); // 24 bytes per chunk
-        for (i, &(text_ptr, start, end, metadata_ptr)) in chunks_data.iter().enumerate() {
+        for (i, &(text_ptr, start, end, metadata_ptr, token_count)) in chunks_data.iter().enumerate() {
             let offset = ptr + i * 24;
             let slice = unsafe {
                 std::slice::from_raw_parts_mut(
@@ -217,6 +602,7 @@ impl TextProcessor {
             slice[1] = start as i32;
             slice[2] = end as i32;
             slice[3] = metadata_ptr as i32;
+            slice[4] = token_count.map_or(-1, |count| count as i32);
         }
         ptr
     }
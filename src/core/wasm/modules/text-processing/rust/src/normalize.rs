@@ -0,0 +1,318 @@
+use std::ops::Range;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Serialize, Deserialize};
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::extraction::Utf16Offsets;
+
+lazy_static! {
+    /// A hyphen and the line break after it; joined only between letters.
+    static ref LINE_END_HYPHEN: Regex = Regex::new(r"[-\u{00AD}\u{2010}][ \t]*\r?\n[ \t]*").unwrap();
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UnicodeForm {
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+}
+
+/// Pipeline stages in the shape of the TS `NormalizationOptions`. Whitespace
+/// is collapsed as the `preserveWhitespace` and `preserveNewlines` chunking
+/// options say.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NormalizationOptions {
+    /// Drops C0/C1 controls other than whitespace, and invisible format
+    /// characters such as soft hyphens, zero-width spaces and BOMs.
+    pub remove_control: bool,
+    /// Joins words hyphenated across a line break, as in OCR or PDF text.
+    pub repair_hyphenation: bool,
+    pub collapse_whitespace: bool,
+    /// Curly quotes to straight ones, dashes and minus signs to `-`.
+    pub fold_punctuation: bool,
+    /// Removes combining diacritics, so `é` becomes `e`.
+    pub strip_accents: bool,
+    /// Lowercases, also folding `ß` to `ss` and final sigma to `σ`.
+    pub case_fold: bool,
+    /// Applied last, so it also recomposes what the other stages left.
+    pub form: Option<UnicodeForm>,
+}
+
+impl Default for NormalizationOptions {
+    fn default() -> Self {
+        NormalizationOptions {
+            remove_control: true,
+            repair_hyphenation: true,
+            collapse_whitespace: true,
+            fold_punctuation: false,
+            strip_accents: false,
+            case_fold: false,
+            form: Some(UnicodeForm::Nfc),
+        }
+    }
+}
+
+/// Options of the standalone `normalize` export: the stages plus the
+/// whitespace flags that chunking options supply otherwise.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NormalizeOptions {
+    #[serde(flatten)]
+    pub stages: NormalizationOptions,
+    pub preserve_whitespace: bool,
+    pub preserve_newlines: bool,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        NormalizeOptions {
+            stages: NormalizationOptions::default(),
+            preserve_whitespace: false,
+            preserve_newlines: true,
+        }
+    }
+}
+
+/// Maps UTF-16 positions in normalized text back to the original. Each
+/// `(normalized, original)` anchor starts a run in which both advance
+/// together; positions inside a replaced span map to its original end.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct OffsetMap {
+    pub offsets: Vec<(usize, usize)>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Normalized {
+    pub text: String,
+    #[serde(flatten)]
+    pub map: OffsetMap,
+}
+
+/// Normalized text built piece by piece, with byte anchors wherever its
+/// offsets stop moving in step with the original.
+struct Builder {
+    text: String,
+    anchors: Vec<(usize, usize)>,
+}
+
+impl Builder {
+    fn push(&mut self, piece: &str, source: Range<usize>) {
+        self.anchor(source.start);
+        self.text.push_str(piece);
+        self.anchor(source.end);
+    }
+
+    fn anchor(&mut self, original: usize) {
+        let (normalized, from) = self.anchors.last().copied().unwrap_or((0, 0));
+        if self.text.len() - normalized != original - from {
+            self.anchors.push((self.text.len(), original));
+        }
+    }
+}
+
+pub fn normalize(text: &str, options: &NormalizationOptions, preserve_whitespace: bool, preserve_newlines: bool) -> Normalized {
+    let joins: Vec<Range<usize>> = if options.repair_hyphenation { hyphenation_breaks(text) } else { Vec::new() };
+    let mut builder = Builder { text: String::with_capacity(text.len()), anchors: Vec::new() };
+    let mut graphemes = text.grapheme_indices(true).peekable();
+    let mut join = joins.iter().peekable();
+
+    while let Some((start, grapheme)) = graphemes.next() {
+        let end = start + grapheme.len();
+        while join.next_if(|j| j.end <= start).is_some() {}
+        if join.peek().is_some_and(|j| j.start <= start) {
+            builder.push("", start..end);
+            continue;
+        }
+
+        if grapheme.chars().all(char::is_whitespace) {
+            let mut run_end = end;
+            while let Some((next, g)) = graphemes.next_if(|&(next, g)| {
+                g.chars().all(char::is_whitespace) && join.peek().is_none_or(|j| next < j.start)
+            }) {
+                run_end = next + g.len();
+            }
+            let run = &text[start..run_end];
+            if options.collapse_whitespace {
+                builder.push(&collapse(run, preserve_whitespace, preserve_newlines), start..run_end);
+            } else {
+                builder.push(run, start..run_end);
+            }
+            continue;
+        }
+
+        if options.remove_control && grapheme.chars().all(is_removable) {
+            builder.push("", start..end);
+            continue;
+        }
+        builder.push(&transform(grapheme, options), start..end);
+    }
+
+    let mut normalized = Utf16Offsets::new(&builder.text);
+    let mut original = Utf16Offsets::new(text);
+    let offsets = builder.anchors.iter().map(|&(n, o)| (normalized.at(n), original.at(o))).collect();
+    Normalized { text: builder.text, map: OffsetMap { offsets } }
+}
+
+/// Byte ranges of line-end hyphens to remove, with their line breaks, where
+/// a letter precedes and a lowercase letter follows.
+fn hyphenation_breaks(text: &str) -> Vec<Range<usize>> {
+    LINE_END_HYPHEN
+        .find_iter(text)
+        .filter(|m| {
+            text[..m.start()].chars().next_back().is_some_and(char::is_alphabetic)
+                && text[m.end()..].chars().next().is_some_and(char::is_lowercase)
+        })
+        .map(|m| m.range())
+        .collect()
+}
+
+fn collapse(run: &str, preserve_whitespace: bool, preserve_newlines: bool) -> String {
+    let is_break = |g: &str| g.contains(['\n', '\r', '\u{2028}', '\u{2029}']);
+    let breaks = run.graphemes(true).filter(|g| is_break(g)).count();
+    if preserve_whitespace {
+        if breaks == 0 || preserve_newlines {
+            run.to_string()
+        } else {
+            run.graphemes(true).map(|g| if is_break(g) { " " } else { g }).collect()
+        }
+    } else if breaks == 0 || !preserve_newlines {
+        " ".to_string()
+    } else {
+        "\n".repeat(breaks)
+    }
+}
+
+fn is_removable(c: char) -> bool {
+    (c.is_control() && !c.is_whitespace())
+        || matches!(c, '\u{00AD}' | '\u{200B}' | '\u{2060}' | '\u{FEFF}' | '\u{200E}' | '\u{200F}')
+        || ('\u{202A}'..='\u{202E}').contains(&c)
+        || ('\u{2066}'..='\u{2069}').contains(&c)
+}
+
+fn transform(grapheme: &str, options: &NormalizationOptions) -> String {
+    let mut text = grapheme.to_string();
+    if options.remove_control {
+        text.retain(|c| !is_removable(c));
+    }
+    if options.fold_punctuation {
+        text = text.chars().map(fold_punctuation).collect();
+    }
+    if options.strip_accents {
+        text = text.nfd().filter(|&c| !is_diacritic(c)).collect();
+    }
+    if options.case_fold {
+        text = text
+            .chars()
+            .flat_map(|c| match c {
+                'ß' | 'ẞ' => "ss".chars().collect::<Vec<_>>(),
+                'ς' => vec!['σ'],
+                _ => c.to_lowercase().collect(),
+            })
+            .collect();
+    }
+    match options.form {
+        Some(UnicodeForm::Nfc) => text.nfc().collect(),
+        Some(UnicodeForm::Nfd) => text.nfd().collect(),
+        Some(UnicodeForm::Nfkc) => text.nfkc().collect(),
+        Some(UnicodeForm::Nfkd) => text.nfkd().collect(),
+        None => text,
+    }
+}
+
+fn fold_punctuation(c: char) -> char {
+    match c {
+        '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' => '\'',
+        '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' => '"',
+        '\u{2010}'..='\u{2015}' | '\u{2212}' => '-',
+        _ => c,
+    }
+}
+
+/// Marks of the combining diacritics blocks; vowel signs and other marks
+/// that are part of a script's letters are kept.
+fn is_diacritic(c: char) -> bool {
+    matches!(c, '\u{0300}'..='\u{036F}' | '\u{1AB0}'..='\u{1AFF}' | '\u{1DC0}'..='\u{1DFF}' | '\u{20D0}'..='\u{20FF}' | '\u{FE20}'..='\u{FE2F}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What `toOriginalOffset` does on the TS side.
+    fn to_original(map: &OffsetMap, position: usize) -> usize {
+        let i = map.offsets.partition_point(|&(normalized, _)| normalized <= position);
+        let (normalized, original) = if i == 0 { (0, 0) } else { map.offsets[i - 1] };
+        let mapped = original + (position - normalized);
+        match map.offsets.get(i) {
+            Some(&(_, next)) => mapped.min(next),
+            None => mapped,
+        }
+    }
+
+    fn run(text: &str, options: &NormalizationOptions) -> String {
+        normalize(text, options, false, true).text
+    }
+
+    #[test]
+    fn test_stages() {
+        let all = NormalizationOptions {
+            fold_punctuation: true,
+            strip_accents: true,
+            case_fold: true,
+            form: Some(UnicodeForm::Nfkc),
+            ..Default::default()
+        };
+        assert_eq!(run("“Straße” – Café ﬁle\u{200B}\u{0007}", &all), "\"strasse\" - cafe file");
+        assert_eq!(run("Cafe\u{301} हिंदी", &NormalizationOptions::default()), "Café हिंदी");
+        assert_eq!(run("Cafe\u{301} हिंदी", &all), "cafe हिंदी");
+        assert_eq!(run("ΟΔΟΣ", &all), "οδοσ");
+
+        // Hyphenation is repaired only between letters, before a lowercase one
+        let text = "The docu-\nment was re-\n  viewed by the Jones-\nSmith team in 2019-\n2020.";
+        assert_eq!(run(text, &NormalizationOptions::default()), "The document was reviewed by the Jones-\nSmith team in 2019-\n2020.");
+        let keep = NormalizationOptions { repair_hyphenation: false, ..Default::default() };
+        assert_eq!(run("docu-\nment", &keep), "docu-\nment");
+    }
+
+    #[test]
+    fn test_whitespace_layouts() {
+        let text = "One  two\t\tthree \r\n\r\n  Four\nfive";
+        let options = NormalizationOptions::default();
+        assert_eq!(normalize(text, &options, false, true).text, "One two three\n\nFour\nfive");
+        assert_eq!(normalize(text, &options, false, false).text, "One two three Four five");
+        assert_eq!(normalize(text, &options, true, true).text, text);
+        assert_eq!(normalize(text, &options, true, false).text, "One  two\t\tthree     Four five");
+        let off = NormalizationOptions { collapse_whitespace: false, ..Default::default() };
+        assert_eq!(normalize(text, &off, false, false).text, text);
+    }
+
+    #[test]
+    fn test_offsets_map_back_to_original() {
+        let text = "ﬁrst  “quoted” 🎉 docu-\nment\u{00AD}s e\u{301}nd";
+        let options = NormalizationOptions { fold_punctuation: true, form: Some(UnicodeForm::Nfkc), ..Default::default() };
+        let normalized = normalize(text, &options, false, true);
+        assert_eq!(normalized.text, "first \"quoted\" 🎉 documents énd");
+
+        let original: Vec<u16> = text.encode_utf16().collect();
+        let slice = |word: &str| {
+            let start = normalized.text[..normalized.text.find(word).unwrap()].encode_utf16().count();
+            let end = start + word.encode_utf16().count();
+            let (start, end) = (to_original(&normalized.map, start), to_original(&normalized.map, end));
+            String::from_utf16(&original[start..end]).unwrap()
+        };
+        assert_eq!(slice("first"), "ﬁrst");
+        assert_eq!(slice("\"quoted\""), "“quoted”");
+        assert_eq!(slice("🎉"), "🎉");
+        assert_eq!(slice("documents"), "docu-\nment\u{00AD}s");
+        assert_eq!(slice("énd"), "e\u{301}nd");
+
+        let unchanged = normalize("plain text", &options, false, true);
+        assert!(unchanged.map.offsets.is_empty());
+        assert_eq!(to_original(&unchanged.map, 5), 5);
+    }
+}
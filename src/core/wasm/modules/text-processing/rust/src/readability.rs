@@ -0,0 +1,168 @@
+use std::collections::HashSet;
+use serde::{Serialize, Deserialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::sentences::Segmenter;
+use crate::PARAGRAPH_BOUNDARY;
+
+/// Options in the shape of the TS `ReadabilityOptions`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReadabilityOptions {
+    /// ISO 639-1 code choosing the sentence and syllable rules; detected when unset.
+    pub language: Option<String>,
+    /// Silent reading speed used for the reading time.
+    pub words_per_minute: f64,
+}
+
+impl Default for ReadabilityOptions {
+    fn default() -> Self {
+        ReadabilityOptions { language: None, words_per_minute: 238.0 }
+    }
+}
+
+/// Counts and readability scores in the shape of the TS `TextStatistics`.
+/// The formulas are calibrated on English; for other languages they only
+/// compare texts with each other.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TextStatistics {
+    pub word_count: usize,
+    pub sentence_count: usize,
+    pub paragraph_count: usize,
+    pub syllable_count: usize,
+    /// Words of three or more syllables.
+    pub polysyllable_count: usize,
+    pub flesch_reading_ease: f64,
+    pub flesch_kincaid_grade: f64,
+    pub gunning_fog: f64,
+    pub smog: f64,
+    /// Words per sentence.
+    pub average_sentence_length: f64,
+    /// Type-token ratio: distinct words, ignoring case, over words.
+    pub lexical_diversity: f64,
+    pub reading_time_minutes: f64,
+}
+
+pub fn statistics(text: &str, options: &ReadabilityOptions) -> TextStatistics {
+    let segmenter = Segmenter::for_text(text, options.language.as_deref());
+    let english = segmenter.language() == "en";
+    let words: Vec<&str> = text.unicode_words().collect();
+    if words.is_empty() {
+        return TextStatistics::default();
+    }
+
+    let syllables: Vec<usize> = words.iter().map(|word| syllables(word, english)).collect();
+    let word_count = words.len();
+    let sentence_count = segmenter
+        .spans(text)
+        .into_iter()
+        .filter(|&(start, end)| text[start..end].unicode_words().next().is_some())
+        .count()
+        .max(1);
+    let syllable_count: usize = syllables.iter().sum();
+    let polysyllable_count = syllables.iter().filter(|&&s| s >= 3).count();
+    let distinct: HashSet<String> = words.iter().map(|word| word.to_lowercase()).collect();
+
+    let (w, s) = (word_count as f64, sentence_count as f64);
+    let words_per_sentence = w / s;
+    let syllables_per_word = syllable_count as f64 / w;
+    let polysyllables = polysyllable_count as f64;
+    TextStatistics {
+        word_count,
+        sentence_count,
+        paragraph_count: PARAGRAPH_BOUNDARY.split(text).filter(|p| !p.trim().is_empty()).count(),
+        syllable_count,
+        polysyllable_count,
+        flesch_reading_ease: 206.835 - 1.015 * words_per_sentence - 84.6 * syllables_per_word,
+        flesch_kincaid_grade: 0.39 * words_per_sentence + 11.8 * syllables_per_word - 15.59,
+        gunning_fog: 0.4 * (words_per_sentence + 100.0 * polysyllables / w),
+        smog: 1.043 * (polysyllables * 30.0 / s).sqrt() + 3.1291,
+        average_sentence_length: words_per_sentence,
+        lexical_diversity: distinct.len() as f64 / w,
+        reading_time_minutes: if options.words_per_minute > 0.0 { w / options.words_per_minute } else { 0.0 },
+    }
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(
+        c,
+        'a' | 'e' | 'i' | 'o' | 'u' | 'y' | 'à' | 'á' | 'â' | 'ä' | 'å' | 'æ' | 'è' | 'é' | 'ê' | 'ë' | 'ì' | 'í'
+            | 'î' | 'ï' | 'ò' | 'ó' | 'ô' | 'ö' | 'ø' | 'ù' | 'ú' | 'û' | 'ü' | 'ý' | 'ÿ' | 'œ'
+    )
+}
+
+/// Estimated syllables of `word`: its vowel groups, less the silent endings
+/// of English. Words without Latin vowels, such as CJK characters, count one.
+fn syllables(word: &str, english: bool) -> usize {
+    let chars: Vec<char> = word.chars().flat_map(char::to_lowercase).collect();
+    let groups = chars
+        .iter()
+        .enumerate()
+        .filter(|&(i, &c)| is_vowel(c) && (i == 0 || !is_vowel(chars[i - 1])))
+        .count();
+    if !english || groups <= 1 {
+        return groups.max(1);
+    }
+
+    let n = chars.len();
+    let before = |back: usize| if n > back { chars[n - back - 1] } else { ' ' };
+    let silent = match (before(1), chars[n - 1]) {
+        // make, but not table
+        (c, 'e') if c != 'l' || is_vowel(before(2)) => !is_vowel(c),
+        // jumped, but not wanted
+        ('e', 'd') => !matches!(before(2), 't' | 'd') && !is_vowel(before(2)),
+        // makes, but not boxes or judges
+        ('e', 's') => !matches!(before(2), 's' | 'x' | 'z' | 'c' | 'g' | 'h') && !is_vowel(before(2)),
+        _ => false,
+    };
+    groups - usize::from(silent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn english() -> ReadabilityOptions {
+        ReadabilityOptions { language: Some("en".to_string()), ..Default::default() }
+    }
+
+    #[test]
+    fn test_syllable_estimates() {
+        let counts: Vec<usize> = ["the", "make", "table", "jumped", "wanted", "makes", "boxes", "readability", "beautiful", "rhythm"]
+            .iter()
+            .map(|w| syllables(w, true))
+            .collect();
+        assert_eq!(counts, vec![1, 1, 2, 1, 2, 1, 2, 5, 3, 1]);
+        // Without the English endings every vowel group counts
+        assert_eq!(syllables("Straße", false), 2);
+        assert_eq!(syllables("東", false), 1);
+    }
+
+    #[test]
+    fn test_scores_of_simple_text() {
+        let stats = statistics("The cat sat on the mat. The dog sat on the log.", &english());
+        assert_eq!((stats.word_count, stats.sentence_count, stats.syllable_count), (12, 2, 12));
+        assert_eq!(stats.paragraph_count, 1);
+        assert!((stats.flesch_reading_ease - 116.145).abs() < 1e-9);
+        assert!((stats.flesch_kincaid_grade - -1.45).abs() < 1e-9);
+        assert!((stats.gunning_fog - 2.4).abs() < 1e-9);
+        assert!((stats.lexical_diversity - 7.0 / 12.0).abs() < 1e-9);
+        assert!((stats.reading_time_minutes - 12.0 / 238.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_harder_text_scores_harder() {
+        let simple = statistics("We met at noon. We ate lunch. Then we went home.", &english());
+        let dense = statistics(
+            "Comprehensive organizational restructuring necessitates considerable administrative coordination.\n\n\
+             Institutional stakeholders consequently anticipate substantial operational improvements.",
+            &english(),
+        );
+        assert_eq!(dense.paragraph_count, 2);
+        assert!(dense.flesch_reading_ease < simple.flesch_reading_ease);
+        assert!(dense.flesch_kincaid_grade > simple.flesch_kincaid_grade);
+        assert!(dense.gunning_fog > simple.gunning_fog && dense.smog > simple.smog);
+        assert_eq!(statistics("", &english()), TextStatistics::default());
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use aho_corasick::{AhoCorasick, MatchKind};
+use hmac::{Hmac, Mac};
+use serde::{Serialize, Deserialize};
+use sha2::Sha256;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::extraction::{self, EntityKind, ExtractionOptions, Utf16Offsets};
+use crate::gazetteer::Gazetteer;
+use crate::TextProcessingError;
+
+/// Hex digits of the keyed hash kept in a pseudonym.
+const PSEUDONYM_DIGITS: usize = 12;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RedactionMode {
+    /// Every grapheme of the entity replaced by the mask character.
+    Mask,
+    /// `[EMAIL_1]`, numbered per type in order of first appearance.
+    #[default]
+    Placeholder,
+    /// `[EMAIL_3f9a1c2b04de]`, a keyed hash that is stable across documents.
+    Pseudonym,
+}
+
+/// Redaction policy in the shape of the TS `RedactionPolicy`; which entities
+/// to redact is chosen with the extraction options.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RedactionPolicy {
+    pub mode: RedactionMode,
+    pub mask_char: char,
+    /// Secret for pseudonyms; required in that mode.
+    pub key: String,
+    #[serde(flatten)]
+    pub extraction: ExtractionOptions,
+}
+
+impl Default for RedactionPolicy {
+    fn default() -> Self {
+        RedactionPolicy {
+            mode: RedactionMode::Placeholder,
+            mask_char: '*',
+            key: String::new(),
+            extraction: ExtractionOptions::default(),
+        }
+    }
+}
+
+/// One replaced entity. Offsets are UTF-16 code units, `start`/`end` in the
+/// redacted text and `originalStart`/`originalEnd` in the original.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Replacement {
+    #[serde(rename = "type")]
+    pub kind: EntityKind,
+    pub original: String,
+    pub replacement: String,
+    pub start: usize,
+    pub end: usize,
+    pub original_start: usize,
+    pub original_end: usize,
+}
+
+/// Everything needed to restore the original from the redacted text.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RedactionMapping {
+    pub mode: RedactionMode,
+    pub replacements: Vec<Replacement>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Redaction {
+    pub text: String,
+    pub mapping: RedactionMapping,
+}
+
+/// Replaces the entities selected by `policy` according to its mode.
+pub fn redact(text: &str, policy: &RedactionPolicy, gazetteer: Option<&Gazetteer>) -> Result<Redaction, TextProcessingError> {
+    if policy.mode == RedactionMode::Pseudonym && policy.key.is_empty() {
+        return Err(TextProcessingError::InvalidOptions("pseudonym redaction requires a key".to_string()));
+    }
+
+    let spans = extraction::find(text, &policy.extraction, gazetteer);
+    let mut redacted = String::with_capacity(text.len());
+    let mut replacements = Vec::with_capacity(spans.len());
+    let mut numbers: HashMap<(EntityKind, String), usize> = HashMap::new();
+    let mut counts: HashMap<EntityKind, usize> = HashMap::new();
+    let mut original_utf16 = Utf16Offsets::new(text);
+    let mut redacted_utf16 = 0;
+    let mut cursor = 0;
+
+    for span in spans {
+        // Overlapping dictionary matches: the first one wins
+        if span.start < cursor {
+            continue;
+        }
+        let original = &text[span.start..span.end];
+        let identity = span.metadata.normalized.clone().unwrap_or_else(|| original.to_string());
+        let replacement = match policy.mode {
+            RedactionMode::Mask => original.graphemes(true).map(|_| policy.mask_char).collect(),
+            RedactionMode::Placeholder => {
+                let number = *numbers.entry((span.kind, identity)).or_insert_with(|| {
+                    let count = counts.entry(span.kind).or_insert(0);
+                    *count += 1;
+                    *count
+                });
+                format!("[{}_{}]", label(span.kind), number)
+            }
+            RedactionMode::Pseudonym => format!("[{}_{}]", label(span.kind), pseudonym(&policy.key, span.kind, &identity)),
+        };
+
+        redacted_utf16 += text[cursor..span.start].encode_utf16().count();
+        redacted.push_str(&text[cursor..span.start]);
+        let start = redacted_utf16;
+        redacted_utf16 += replacement.encode_utf16().count();
+        redacted.push_str(&replacement);
+        cursor = span.end;
+
+        replacements.push(Replacement {
+            kind: span.kind,
+            original: original.to_string(),
+            replacement,
+            start,
+            end: redacted_utf16,
+            original_start: original_utf16.at(span.start),
+            original_end: original_utf16.at(span.end),
+        });
+    }
+    redacted.push_str(&text[cursor..]);
+
+    Ok(Redaction {
+        text: redacted,
+        mapping: RedactionMapping { mode: policy.mode, replacements },
+    })
+}
+
+/// Puts the originals back. The unchanged redacted text is restored exactly
+/// by position. In text derived from it, such as a summary, placeholders and
+/// pseudonyms are replaced wherever they occur, each by the first original it
+/// stood for; masks cannot be located there.
+pub fn restore(text: &str, mapping: &RedactionMapping) -> Result<String, TextProcessingError> {
+    if mapping.replacements.is_empty() {
+        return Ok(text.to_string());
+    }
+    match restore_positions(text, &mapping.replacements) {
+        Err(TextProcessingError::RedactionMismatch) if mapping.mode != RedactionMode::Mask => {}
+        restored => return restored,
+    }
+
+    let mut originals: HashMap<&str, &str> = HashMap::new();
+    for r in &mapping.replacements {
+        originals.entry(r.replacement.as_str()).or_insert(r.original.as_str());
+    }
+    let (tokens, values): (Vec<&str>, Vec<&str>) = originals.into_iter().unzip();
+    let automaton = AhoCorasick::builder()
+        .match_kind(MatchKind::LeftmostLongest)
+        .build(&tokens)
+        .map_err(TextProcessingError::from)?;
+    Ok(automaton.replace_all(text, &values))
+}
+
+fn restore_positions(text: &str, replacements: &[Replacement]) -> Result<String, TextProcessingError> {
+    let mut restored = String::with_capacity(text.len());
+    let mut utf16 = 0;
+    let mut cursor = 0; // bytes
+    let mut chars = text.char_indices().peekable();
+
+    // Byte offset of UTF-16 offset `target`, advancing monotonically
+    let mut byte_at = |target: usize| -> Option<usize> {
+        while utf16 < target {
+            let (_, c) = chars.next()?;
+            utf16 += c.len_utf16();
+        }
+        (utf16 == target).then(|| chars.peek().map_or(text.len(), |&(i, _)| i))
+    };
+
+    let mut sorted: Vec<&Replacement> = replacements.iter().collect();
+    sorted.sort_by_key(|r| r.start);
+    for r in sorted {
+        let (Some(start), Some(end)) = (byte_at(r.start), byte_at(r.end)) else {
+            return Err(TextProcessingError::RedactionMismatch);
+        };
+        if start < cursor || text[start..end] != r.replacement {
+            return Err(TextProcessingError::RedactionMismatch);
+        }
+        restored.push_str(&text[cursor..start]);
+        restored.push_str(&r.original);
+        cursor = end;
+    }
+    restored.push_str(&text[cursor..]);
+    Ok(restored)
+}
+
+/// Placeholder label of an entity type, e.g. `CREDIT_CARD`.
+fn label(kind: EntityKind) -> String {
+    let mut label = String::new();
+    for c in format!("{:?}", kind).chars() {
+        if c.is_uppercase() && !label.is_empty() {
+            label.push('_');
+        }
+        label.push(c.to_ascii_uppercase());
+    }
+    label
+}
+
+fn pseudonym(key: &str, kind: EntityKind, identity: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(label(kind).as_bytes());
+    mac.update(&[0]);
+    mac.update(identity.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>()[..PSEUDONYM_DIGITS]
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "Mail ana@example.com or ANA@example.com, card 4111 1111 1111 1111, from bob@example.org. Café ☕ 12%";
+
+    fn policy(mode: RedactionMode) -> RedactionPolicy {
+        RedactionPolicy {
+            mode,
+            key: "secret".to_string(),
+            extraction: ExtractionOptions {
+                entity_types: vec![EntityKind::Email, EntityKind::CreditCard, EntityKind::Percentage],
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_placeholders_round_trip() {
+        let redaction = redact(TEXT, &policy(RedactionMode::Placeholder), None).unwrap();
+        assert_eq!(redaction.text, "Mail [EMAIL_1] or [EMAIL_1], card [CREDIT_CARD_1], from [EMAIL_2]. Café ☕ [PERCENTAGE_1]");
+        let last = redaction.mapping.replacements.last().unwrap();
+        let utf16: Vec<u16> = redaction.text.encode_utf16().collect();
+        assert_eq!(String::from_utf16(&utf16[last.start..last.end]).unwrap(), "[PERCENTAGE_1]");
+        assert_eq!(restore(&redaction.text, &redaction.mapping).unwrap(), TEXT);
+
+        // Derived text keeps working
+        let summary = "[EMAIL_2] was charged to [CREDIT_CARD_1].";
+        assert_eq!(restore(summary, &redaction.mapping).unwrap(), "bob@example.org was charged to 4111 1111 1111 1111.");
+    }
+
+    #[test]
+    fn test_pseudonyms_are_keyed_and_stable() {
+        let first = redact(TEXT, &policy(RedactionMode::Pseudonym), None).unwrap();
+        let again = redact("Contact: ana@example.com", &policy(RedactionMode::Pseudonym), None).unwrap();
+        let token = &first.mapping.replacements[0].replacement;
+        assert!(token.starts_with("[EMAIL_") && token.len() == "[EMAIL_]".len() + PSEUDONYM_DIGITS);
+        assert_eq!(&again.mapping.replacements[0].replacement, token);
+        assert_eq!(&first.mapping.replacements[1].replacement, token);
+
+        let other_key = RedactionPolicy { key: "other".to_string(), ..policy(RedactionMode::Pseudonym) };
+        assert_ne!(&redact(TEXT, &other_key, None).unwrap().mapping.replacements[0].replacement, token);
+        assert_eq!(restore(&first.text, &first.mapping).unwrap(), TEXT);
+
+        let keyless = RedactionPolicy { key: String::new(), ..policy(RedactionMode::Pseudonym) };
+        assert!(redact(TEXT, &keyless, None).is_err());
+    }
+
+    #[test]
+    fn test_masks_restore_by_position() {
+        let redaction = redact(TEXT, &policy(RedactionMode::Mask), None).unwrap();
+        assert!(redaction.text.starts_with("Mail *************** or"));
+        assert_eq!(restore(&redaction.text, &redaction.mapping).unwrap(), TEXT);
+        assert!(matches!(
+            restore(&redaction.text[5..], &redaction.mapping),
+            Err(TextProcessingError::RedactionMismatch)
+        ));
+    }
+}
@@ -0,0 +1,350 @@
+use std::collections::{HashMap, HashSet};
+use serde::{Serialize, Deserialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::api::IdentifiedChunk;
+use crate::extraction::{Position, Utf16Offsets};
+use crate::fuzzy::TermDictionary;
+use crate::TextProcessingError;
+
+/// First byte of a saved index, bumped whenever its layout changes.
+const FORMAT_VERSION: u8 = 1;
+
+/// Query options in the shape of the TS `SearchOptions`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SearchOptions {
+    pub limit: usize,
+    /// Term frequency saturation.
+    pub k1: f64,
+    /// Document length normalization, from none (0.0) to full (1.0).
+    pub b: f64,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions { limit: 10, k1: 1.2, b: 0.75 }
+    }
+}
+
+/// A matching chunk with the UTF-16 positions of query terms in its text.
+#[derive(Serialize)]
+pub struct SearchHit<'a> {
+    pub chunk: &'a IdentifiedChunk,
+    pub score: f64,
+    pub highlights: Vec<Position>,
+}
+
+/// Inverted index over chunks, ranked with BM25. Terms are lowercased
+/// Unicode words; the index is saved as a MessagePack blob. Their collection
+/// frequencies also feed a dictionary for fuzzy lookups.
+#[derive(Serialize, Deserialize, Default)]
+pub struct SearchIndex {
+    documents: Vec<Option<IdentifiedChunk>>, // None once removed or replaced
+    lengths: Vec<u32>,                       // terms per document
+    total_length: u64,
+    postings: HashMap<String, Vec<(u32, u32)>>, // term -> (document, frequency)
+    #[serde(skip)]
+    slots: HashMap<String, u32>, // chunk id -> document
+    #[serde(skip)]
+    dictionary: TermDictionary,
+}
+
+impl SearchIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads an index written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TextProcessingError> {
+        let Some((&FORMAT_VERSION, body)) = bytes.split_first() else {
+            return Err(TextProcessingError::InvalidIndex("unknown format version".to_string()));
+        };
+        let mut index: SearchIndex = rmp_serde::from_slice(body)
+            .map_err(|e| TextProcessingError::InvalidIndex(e.to_string()))?;
+        index.slots = index
+            .documents
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, document)| Some((document.as_ref()?.id.clone(), slot as u32)))
+            .collect();
+        if index.slots.len() != index.documents.iter().flatten().count() {
+            return Err(TextProcessingError::InvalidIndex("chunk ids are not unique".to_string()));
+        }
+        let live = |document: u32| index.documents.get(document as usize).is_some_and(Option::is_some);
+        let consistent = index.lengths.len() == index.documents.len()
+            && index.total_length == index.lengths.iter().map(|&length| u64::from(length)).sum::<u64>()
+            && index.postings.values().flatten().all(|&(document, _)| live(document));
+        if !consistent {
+            return Err(TextProcessingError::InvalidIndex("postings do not match the documents".to_string()));
+        }
+        for (term, postings) in &index.postings {
+            let frequency = postings.iter().map(|&(_, frequency)| u64::from(frequency)).sum();
+            index.dictionary.add(term, frequency);
+        }
+        Ok(index)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![FORMAT_VERSION];
+        rmp_serde::encode::write_named(&mut bytes, self).expect("writing to a Vec cannot fail");
+        bytes
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// The indexed chunks, oldest first.
+    pub fn chunks(&self) -> impl Iterator<Item = &IdentifiedChunk> {
+        self.documents.iter().flatten()
+    }
+
+    /// Indexed terms with their frequencies across all chunks.
+    pub fn dictionary(&self) -> &TermDictionary {
+        &self.dictionary
+    }
+
+    /// Indexes `chunk`, replacing any chunk with the same id.
+    pub fn add(&mut self, chunk: IdentifiedChunk) {
+        self.remove(&chunk.id);
+        let slot = self.documents.len() as u32;
+        let mut frequencies: HashMap<String, u32> = HashMap::new();
+        let mut length = 0;
+        for (_, term) in terms(&chunk.chunk.text) {
+            *frequencies.entry(term).or_insert(0) += 1;
+            length += 1;
+        }
+        for (term, frequency) in frequencies {
+            self.dictionary.add(&term, u64::from(frequency));
+            self.postings.entry(term).or_default().push((slot, frequency));
+        }
+        self.slots.insert(chunk.id.clone(), slot);
+        self.documents.push(Some(chunk));
+        self.lengths.push(length);
+        self.total_length += u64::from(length);
+    }
+
+    /// Removes the chunk with `id`; returns whether there was one.
+    pub fn remove(&mut self, id: &str) -> bool {
+        let Some(slot) = self.slots.remove(id) else {
+            return false;
+        };
+        let Some(chunk) = self.documents[slot as usize].take() else {
+            return false;
+        };
+        let unique: HashSet<String> = terms(&chunk.chunk.text).map(|(_, term)| term).collect();
+        for term in unique {
+            if let Some(postings) = self.postings.get_mut(&term) {
+                if let Some(i) = postings.iter().position(|&(document, _)| document == slot) {
+                    self.dictionary.remove(&term, u64::from(postings.remove(i).1));
+                }
+                if postings.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+        self.total_length -= u64::from(std::mem::take(&mut self.lengths[slot as usize]));
+        if self.documents.len() - self.len() > self.len() {
+            self.compact();
+        }
+        true
+    }
+
+    /// Renumbers the live documents once removed ones outnumber them, so
+    /// that re-indexing does not grow the saved index without bound.
+    fn compact(&mut self) {
+        let mut renumbered = vec![None; self.documents.len()];
+        let mut documents = Vec::with_capacity(self.len());
+        let mut lengths = Vec::with_capacity(self.len());
+        for (slot, document) in self.documents.drain(..).enumerate() {
+            if let Some(document) = document {
+                renumbered[slot] = Some(documents.len() as u32);
+                self.slots.insert(document.id.clone(), documents.len() as u32);
+                documents.push(Some(document));
+                lengths.push(self.lengths[slot]);
+            }
+        }
+        for postings in self.postings.values_mut() {
+            for (document, _) in postings.iter_mut() {
+                *document = renumbered[*document as usize].expect("postings only point to live documents");
+            }
+        }
+        self.documents = documents;
+        self.lengths = lengths;
+    }
+
+    /// The best `options.limit` chunks for `query`, best first.
+    pub fn search(&self, query: &str, options: &SearchOptions) -> Vec<SearchHit<'_>> {
+        let query: HashSet<String> = terms(query).map(|(_, term)| term).collect();
+        if self.is_empty() || query.is_empty() {
+            return Vec::new();
+        }
+
+        let documents = self.len() as f64;
+        let average_length = (self.total_length as f64 / documents).max(1.0);
+        let mut scores: HashMap<u32, f64> = HashMap::new();
+        for term in &query {
+            let Some(postings) = self.postings.get(term) else {
+                continue;
+            };
+            let frequency = postings.len() as f64;
+            let idf = (1.0 + (documents - frequency + 0.5) / (frequency + 0.5)).ln();
+            for &(document, tf) in postings {
+                let tf = f64::from(tf);
+                let length = f64::from(self.lengths[document as usize]);
+                let norm = options.k1 * (1.0 - options.b + options.b * length / average_length);
+                *scores.entry(document).or_insert(0.0) += idf * tf * (options.k1 + 1.0) / (tf + norm);
+            }
+        }
+
+        let mut ranked: Vec<(u32, f64)> = scores.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked.truncate(options.limit);
+        ranked
+            .into_iter()
+            .filter_map(|(document, score)| {
+                let chunk = self.documents[document as usize].as_ref()?;
+                Some(SearchHit { chunk, score, highlights: highlights(&chunk.chunk.text, &query) })
+            })
+            .collect()
+    }
+}
+
+/// Byte offsets and lowercased forms of the words of `text`.
+fn terms(text: &str) -> impl Iterator<Item = (usize, String)> + '_ {
+    text.unicode_word_indices().map(|(offset, word)| (offset, word.to_lowercase()))
+}
+
+fn highlights(text: &str, query: &HashSet<String>) -> Vec<Position> {
+    let mut utf16 = Utf16Offsets::new(text);
+    text.unicode_word_indices()
+        .filter(|(_, word)| query.contains(&word.to_lowercase()))
+        .map(|(offset, word)| Position { start: utf16.at(offset), end: utf16.at(offset + word.len()) })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: &str, text: &str) -> IdentifiedChunk {
+        let json = serde_json::json!({
+            "id": id,
+            "text": text,
+            "start": 0,
+            "end": text.chars().count(),
+            "metadata": { "confidence": 1.0, "headingPath": ["Contracts"] },
+        });
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn index() -> SearchIndex {
+        let mut index = SearchIndex::new();
+        index.add(chunk("a", "The invoice is due in thirty days."));
+        index.add(chunk("b", "Invoice disputes go to the billing team. Late invoice fees apply."));
+        index.add(chunk("c", "The contract renews every year unless cancelled in writing."));
+        index.add(chunk("d", "Rückfragen zur Rechnung beantwortet das Team. Invoice numbers are unique."));
+        index
+    }
+
+    fn ids<'a>(hits: &'a [SearchHit<'a>]) -> Vec<&'a str> {
+        hits.iter().map(|h| h.chunk.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_bm25_ranking_and_highlights() {
+        let index = index();
+        let hits = index.search("invoice team", &SearchOptions::default());
+        assert_eq!(ids(&hits), vec!["b", "d", "a"]);
+        assert!(hits.windows(2).all(|w| w[0].score >= w[1].score));
+
+        // UTF-16 positions within the chunk text
+        let text = &hits[1].chunk.chunk.text;
+        let utf16: Vec<u16> = text.encode_utf16().collect();
+        let words: Vec<String> = hits[1]
+            .highlights
+            .iter()
+            .map(|p| String::from_utf16(&utf16[p.start..p.end]).unwrap())
+            .collect();
+        assert_eq!(words, vec!["Team", "Invoice"]);
+
+        let options = SearchOptions { limit: 1, ..Default::default() };
+        assert_eq!(ids(&index.search("INVOICE", &options)), vec!["b"]);
+        assert!(index.search("warranty", &options).is_empty());
+    }
+
+    #[test]
+    fn test_replace_and_remove() {
+        let mut index = index();
+        index.add(chunk("c", "The invoice template was updated."));
+        assert_eq!(index.len(), 4);
+        assert!(index.search("contract", &SearchOptions::default()).is_empty());
+        assert_eq!(index.dictionary().frequency("contract"), 0);
+        assert_eq!(index.dictionary().frequency("invoice"), 5);
+        assert_eq!(ids(&index.search("template", &SearchOptions::default())), vec!["c"]);
+
+        assert!(index.remove("a"));
+        assert!(!index.remove("a"));
+        assert_eq!(index.len(), 3);
+        assert!(!ids(&index.search("invoice", &SearchOptions::default())).contains(&"a"));
+
+        // Re-indexing the same chunks leaves no trail of removed documents
+        for _ in 0..10 {
+            index.add(chunk("b", "Invoice disputes go to the billing team."));
+            index.add(chunk("d", "Invoice numbers are unique."));
+        }
+        assert!(index.documents.len() <= 2 * index.len());
+        let hits = index.search("invoice", &SearchOptions::default());
+        let mut found = ids(&hits);
+        found.sort();
+        assert_eq!(found, vec!["b", "c", "d"]);
+        assert_eq!(index.dictionary().frequency("invoice"), 3);
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let mut index = index();
+        index.remove("c");
+        let bytes = index.to_bytes();
+        let restored = SearchIndex::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.len(), 3);
+        assert_eq!(restored.dictionary().frequency("invoice"), 4);
+
+        let expected = index.search("invoice team", &SearchOptions::default());
+        let hits = restored.search("invoice team", &SearchOptions::default());
+        assert_eq!(ids(&hits), ids(&expected));
+        assert_eq!(hits[0].score, expected[0].score);
+        let json = serde_json::to_value(&hits[0]).unwrap();
+        assert_eq!(json["chunk"]["metadata"]["headingPath"][0], "Contracts");
+
+        assert!(SearchIndex::from_bytes(&bytes[..bytes.len() / 2]).is_err());
+        assert!(SearchIndex::from_bytes(&[FORMAT_VERSION + 1]).is_err());
+        assert!(SearchIndex::from_bytes(&[]).is_err());
+    }
+
+    #[test]
+    fn test_corrupt_indexes_rejected() {
+        let corrupt = |damage: fn(&mut SearchIndex)| {
+            let mut index = index();
+            index.remove("c");
+            damage(&mut index);
+            SearchIndex::from_bytes(&index.to_bytes()).err()
+        };
+        assert!(corrupt(|_| {}).is_none());
+        // Postings past the documents would index out of bounds
+        assert!(corrupt(|index| index.postings.get_mut("invoice").unwrap().push((99, 1))).is_some());
+        assert!(corrupt(|index| {
+            index.lengths.pop();
+        })
+        .is_some());
+        assert!(corrupt(|index| index.total_length += 1).is_some());
+        // and postings to removed documents would break compaction
+        assert!(corrupt(|index| index.postings.get_mut("invoice").unwrap().push((2, 1))).is_some());
+        assert!(corrupt(|index| index.documents[1].as_mut().unwrap().id = "a".to_string()).is_some());
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+
+use crate::{ChunkSpan, ProcessingConfig};
+use crate::sentences::Segmenter;
+use crate::structure::split_oversized;
+
+/// Dimensions of the hashing vectorizer.
+const EMBEDDING_DIMENSIONS: u64 = 1 << 12;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SemanticOptions {
+    /// Sentences on each side of a gap that are averaged before comparing.
+    pub window: usize,
+    /// Cut wherever adjacent windows are less similar than this.
+    pub threshold: Option<f64>,
+    /// Without a threshold, cut at gaps in this lowest percentile of similarity.
+    pub percentile: f64,
+}
+
+impl Default for SemanticOptions {
+    fn default() -> Self {
+        SemanticOptions {
+            window: 2,
+            threshold: None,
+            percentile: 20.0,
+        }
+    }
+}
+
+pub(crate) type SparseVector = HashMap<u64, f64>;
+
+/// Splits `text` at topic shifts: sentences are embedded with a hashed
+/// TF-IDF vectorizer and cut where neighbouring windows diverge. Segments
+/// larger than `chunk_size` are split again at their weakest gap.
+pub fn chunk(text: &str, config: &ProcessingConfig, measure: &dyn Fn(&str) -> usize) -> Vec<ChunkSpan> {
+    let segmenter = Segmenter::for_text(text, config.language.as_deref());
+    let sentences = segmenter.spans(text);
+    if sentences.is_empty() {
+        return Vec::new();
+    }
+
+    let embeddings = embed(sentences.iter().map(|&(start, end)| &text[start..end]));
+    let similarities = gap_similarities(&embeddings, config.semantic.window.max(1));
+    // A percentile cutoff is itself one of the similarities, so it cuts
+    // inclusively, unless no gap is more similar and nothing stands out
+    let (cutoff, inclusive) = match config.semantic.threshold {
+        Some(threshold) => (threshold, false),
+        None => {
+            let cutoff = percentile(&similarities, config.semantic.percentile);
+            (cutoff, similarities.iter().any(|&similarity| similarity > cutoff))
+        }
+    };
+
+    let mut segments = Vec::new();
+    let mut segment_start = 0;
+    for (gap, &similarity) in similarities.iter().enumerate() {
+        if similarity < cutoff || (inclusive && similarity == cutoff) {
+            segments.push((segment_start, gap + 1));
+            segment_start = gap + 1;
+        }
+    }
+    segments.push((segment_start, sentences.len()));
+
+    let limit = config.chunk_size.max(1);
+    let mut spans = Vec::new();
+    for (first, last) in segments {
+        enforce_limit(text, &sentences, &similarities, first, last, limit, &segmenter, measure, &mut |start, end| {
+            let (mut start, mut end) = (start, end);
+            if config.trim_chunks {
+                let untrimmed = &text[start..end];
+                start += untrimmed.len() - untrimmed.trim_start().len();
+                end = start + untrimmed.trim().len();
+            }
+            let slice = &text[start..end];
+            if !slice.is_empty() {
+                spans.push(ChunkSpan {
+                    start,
+                    end,
+                    text: slice.to_string(),
+                    token_count: None,
+                    heading_path: Vec::new(),
+                });
+            }
+        });
+    }
+    spans
+}
+
+/// Emits sentence ranges `[first, last)` as chunks, recursively splitting at
+/// the least similar gap while a range exceeds `limit`.
+#[allow(clippy::too_many_arguments)]
+fn enforce_limit(
+    text: &str,
+    sentences: &[(usize, usize)],
+    similarities: &[f64],
+    first: usize,
+    last: usize,
+    limit: usize,
+    segmenter: &Segmenter,
+    measure: &dyn Fn(&str) -> usize,
+    emit: &mut dyn FnMut(usize, usize),
+) {
+    let (start, end) = (sentences[first].0, sentences[last - 1].1);
+    if measure(&text[start..end]) <= limit {
+        emit(start, end);
+        return;
+    }
+    if last - first == 1 {
+        for (piece_start, piece_end) in split_oversized(text, start, end, limit, segmenter, measure) {
+            emit(piece_start, piece_end);
+        }
+        return;
+    }
+
+    // Gap g separates sentence g from g + 1
+    let weakest = (first..last - 1)
+        .min_by(|&a, &b| similarities[a].total_cmp(&similarities[b]))
+        .unwrap_or(first);
+    enforce_limit(text, sentences, similarities, first, weakest + 1, limit, segmenter, measure, emit);
+    enforce_limit(text, sentences, similarities, weakest + 1, last, limit, segmenter, measure, emit);
+}
+
+/// Hashed TF-IDF vectors, L2-normalized, one per sentence.
+pub(crate) fn embed<'a>(sentences: impl Iterator<Item = &'a str>) -> Vec<SparseVector> {
+    let counts: Vec<HashMap<u64, f64>> = sentences
+        .map(|sentence| {
+            let mut counts = HashMap::new();
+            for word in sentence.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+                let word = word.to_lowercase();
+                *counts.entry(fnv1a(word.as_bytes()) % EMBEDDING_DIMENSIONS).or_insert(0.0) += 1.0;
+            }
+            counts
+        })
+        .collect();
+
+    let mut document_frequency: HashMap<u64, f64> = HashMap::new();
+    for sentence in &counts {
+        for &feature in sentence.keys() {
+            *document_frequency.entry(feature).or_insert(0.0) += 1.0;
+        }
+    }
+    let n = counts.len() as f64;
+
+    counts
+        .into_iter()
+        .map(|sentence| {
+            let mut vector: SparseVector = sentence
+                .into_iter()
+                .map(|(feature, tf)| {
+                    let idf = ((1.0 + n) / (1.0 + document_frequency[&feature])).ln();
+                    (feature, (1.0 + tf.ln()) * idf)
+                })
+                .collect();
+            let norm = vector.values().map(|v| v * v).sum::<f64>().sqrt();
+            if norm > 0.0 {
+                vector.values_mut().for_each(|v| *v /= norm);
+            }
+            vector
+        })
+        .collect()
+}
+
+/// Cosine similarity between the windows before and after each sentence gap.
+fn gap_similarities(embeddings: &[SparseVector], window: usize) -> Vec<f64> {
+    (1..embeddings.len())
+        .map(|gap| {
+            let before = sum(&embeddings[gap.saturating_sub(window)..gap]);
+            let after = sum(&embeddings[gap..(gap + window).min(embeddings.len())]);
+            cosine(&before, &after)
+        })
+        .collect()
+}
+
+pub(crate) fn sum(vectors: &[SparseVector]) -> SparseVector {
+    let mut total = SparseVector::new();
+    for vector in vectors {
+        for (&feature, &value) in vector {
+            *total.entry(feature).or_insert(0.0) += value;
+        }
+    }
+    total
+}
+
+pub(crate) fn cosine(a: &SparseVector, b: &SparseVector) -> f64 {
+    let (small, large) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    let dot: f64 = small.iter().filter_map(|(k, v)| large.get(k).map(|w| v * w)).sum();
+    let norms = a.values().map(|v| v * v).sum::<f64>().sqrt() * b.values().map(|v| v * v).sum::<f64>().sqrt();
+    if norms > 0.0 { dot / norms } else { 0.0 }
+}
+
+fn percentile(values: &[f64], percentile: f64) -> f64 {
+    if values.is_empty() {
+        return f64::NEG_INFINITY;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let rank = (percentile.clamp(0.0, 100.0) / 100.0 * (sorted.len() - 1) as f64).round() as usize;
+    sorted[rank]
+}
+
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChunkStrategy;
+
+    const CONTRACT: &str = "The tenant shall pay rent on the first day of each month. Rent is payable by bank transfer. Late rent payments incur a fee of five percent of the monthly rent. \
+        The landlord shall maintain the roof and the heating system. Repairs to the heating system and roof are the landlord's duty. The landlord must inspect the roof every year. \
+        Either party may terminate this agreement with three months notice. Notice of termination must be given in writing. Termination notice ends all obligations.";
+
+    fn graphemes(s: &str) -> usize {
+        s.chars().count()
+    }
+
+    fn config(chunk_size: usize, threshold: Option<f64>) -> ProcessingConfig {
+        ProcessingConfig {
+            chunk_size,
+            strategy: ChunkStrategy::Semantic,
+            semantic: SemanticOptions { window: 2, threshold, percentile: 20.0 },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_splits_at_topic_shifts() {
+        let spans = chunk(CONTRACT, &config(10_000, Some(0.07)), &graphemes);
+        let texts: Vec<&str> = spans.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts.len(), 3, "{:?}", texts);
+        assert!(texts[0].starts_with("The tenant") && texts[0].ends_with("monthly rent."));
+        assert!(texts[1].starts_with("The landlord shall") && texts[1].ends_with("every year."));
+        assert_eq!(chunk(CONTRACT, &config(10_000, None), &graphemes).len(), 3);
+        assert!(texts[2].starts_with("Either party"));
+
+        // Equally similar gaps give no shift to cut at
+        let repeated = "Rent is due monthly. ".repeat(6);
+        assert_eq!(chunk(&repeated, &config(10_000, None), &graphemes).len(), 1);
+    }
+
+    #[test]
+    fn test_respects_chunk_size() {
+        let spans = chunk(CONTRACT, &config(120, None), &graphemes);
+        assert!(spans.len() >= 4);
+        assert!(spans.iter().all(|s| graphemes(&s.text) <= 120));
+        // Chunks still cover whole sentences
+        assert!(spans.iter().all(|s| s.text.ends_with('.')));
+        assert_eq!(spans.first().unwrap().start, 0);
+        assert_eq!(spans.last().unwrap().end, CONTRACT.len());
+    }
+
+    #[test]
+    fn test_percentile() {
+        assert_eq!(percentile(&[0.9, 0.1, 0.5, 0.3, 0.7], 0.0), 0.1);
+        assert_eq!(percentile(&[0.9, 0.1, 0.5, 0.3, 0.7], 50.0), 0.5);
+        assert_eq!(percentile(&[], 50.0), f64::NEG_INFINITY);
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::{HashMap, HashSet};
+use lazy_static::lazy_static;
+use serde::{Serialize, Deserialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::extraction::Utf16Offsets;
+use crate::language;
+
+// Abbreviations that are normally followed by a capitalized word, such as a
+// name, so a period after them does not end the sentence. Stored lowercase
+// without the final period; single letters and dotted letter groups such as
+// "e.g." or "z.B." are recognized without being listed.
+const ABBREVIATIONS: &[(&str, &str)] = &[
+    ("en", "mr mrs ms mx dr prof rev hon gen col lt sgt capt cmdr gov sen rep pres st mt ft fig figs vol eq ref vs cf approx dept univ ave blvd"),
+    ("de", "hr hrn fr dr prof nr bzw vgl ca evtl ggf inkl bzgl sog geb gest jh dipl ing mio mrd str abs abt"),
+    ("fr", "m mm mme mmes mlle mlles dr pr me st ste av bd env cf vol fig éd"),
+    ("es", "sr sres sra srta dr dra dña lic ing prof av avda pág núm tel ud uds vd vds aprox"),
+    ("it", "sig sigg dott dott.ssa prof ing avv arch geom pag ca"),
+    ("pt", "sr sra srta dr dra prof eng av pág tel ex"),
+    ("nl", "dhr mevr mr dr drs prof ir ing bijv blz nr ca"),
+];
+
+/// Languages writing ordinal numbers with a period, as in "am 3. Oktober".
+const ORDINAL_PERIOD_LANGUAGES: &[&str] = &["de", "da", "no", "nb", "fi", "cs", "sk", "pl", "hu"];
+
+lazy_static! {
+    static ref RULES: HashMap<&'static str, Rules> = ABBREVIATIONS
+        .iter()
+        .map(|&(language, words)| (language, Rules::new(language, words.split_whitespace().collect())))
+        .chain(
+            ORDINAL_PERIOD_LANGUAGES
+                .iter()
+                .filter(|language| !ABBREVIATIONS.iter().any(|(l, _)| l == *language))
+                .map(|&language| (language, Rules::new(language, HashSet::new()))),
+        )
+        .collect();
+}
+
+struct Rules {
+    abbreviations: HashSet<&'static str>,
+    ordinal_period: bool,
+}
+
+impl Rules {
+    fn new(language: &str, abbreviations: HashSet<&'static str>) -> Self {
+        Rules { abbreviations, ordinal_period: ORDINAL_PERIOD_LANGUAGES.contains(&language) }
+    }
+}
+
+/// Options in the shape of the TS `SentenceOptions`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct SentenceOptions {
+    /// ISO 639-1 code choosing the abbreviation list; detected when unset.
+    pub language: Option<String>,
+}
+
+/// A sentence without its surrounding whitespace, in UTF-16 offsets.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Sentence {
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+pub fn sentences(text: &str, options: &SentenceOptions) -> Vec<Sentence> {
+    let segmenter = Segmenter::for_text(text, options.language.as_deref());
+    let mut utf16 = Utf16Offsets::new(text);
+    segmenter
+        .spans(text)
+        .into_iter()
+        .filter_map(|(start, end)| {
+            let sentence = &text[start..end];
+            let trimmed = sentence.trim_start();
+            let start = start + sentence.len() - trimmed.len();
+            let trimmed = trimmed.trim_end();
+            (!trimmed.is_empty()).then(|| Sentence {
+                text: trimmed.to_string(),
+                start: utf16.at(start),
+                end: utf16.at(start + trimmed.len()),
+            })
+        })
+        .collect()
+}
+
+/// Sentence segmentation: the UAX #29 sentence boundaries, minus those after
+/// abbreviations, inside URLs and at single line breaks, plus spaces between
+/// Thai sentences, which have no terminal punctuation. CJK terminals need no
+/// following space under UAX #29 already.
+pub(crate) struct Segmenter {
+    language: String,
+    rules: Option<&'static Rules>,
+}
+
+impl Segmenter {
+    /// Segmenter for `language`, or for the language detected in `text` when unset.
+    pub fn for_text(text: &str, language: Option<&str>) -> Self {
+        let detected;
+        let language = match language {
+            Some(language) => language,
+            None => {
+                detected = language::detect(text).map(|d| d.language);
+                detected.as_deref().unwrap_or_default()
+            }
+        };
+        let language = language.split(['-', '_']).next().unwrap_or_default().to_lowercase();
+        Segmenter { rules: RULES.get(language.as_str()), language }
+    }
+
+    /// The primary language subtag in use, or "" if none was given or detected.
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Byte spans of the sentences of `text`, each including its trailing
+    /// whitespace, covering the whole text.
+    pub fn spans(&self, text: &str) -> Vec<(usize, usize)> {
+        let mut spans: Vec<(usize, usize)> = Vec::new();
+        for (start, segment) in text.split_sentence_bound_indices() {
+            match spans.last_mut() {
+                Some(last) if self.joins(&text[last.0..start], segment) => last.1 = start + segment.len(),
+                _ => spans.push((start, start + segment.len())),
+            }
+        }
+        spans.into_iter().flat_map(|(start, end)| thai_spans(text, start, end)).collect()
+    }
+
+    /// Whether the UAX #29 boundary between `before` and `after` is a false one.
+    fn joins(&self, before: &str, after: &str) -> bool {
+        let sentence = before.trim_end();
+        let gap = &before[sentence.len()..];
+        if sentence.is_empty() || after.trim().is_empty() {
+            return true;
+        }
+        let last = sentence.chars().next_back().unwrap_or_default();
+        // A break inside unbroken ASCII, such as the `?` of a URL query
+        if gap.is_empty() && last.is_ascii_graphic() && after.starts_with(|c: char| c.is_ascii_graphic()) {
+            return true;
+        }
+        // A quotation ending in a terminal and carried on, as in 「はい。」と言った
+        if gap.is_empty() && is_closer(last) && after.starts_with(char::is_alphabetic) {
+            return true;
+        }
+        // Without terminal punctuation the break is a line break; only blank lines end sentences
+        if !ends_with_terminal(sentence) {
+            return gap.matches('\n').count() < 2;
+        }
+        if last != '.' {
+            return false;
+        }
+
+        let word = sentence.rsplit(char::is_whitespace).next().unwrap_or_default();
+        let word = word.trim_start_matches(|c: char| !c.is_alphanumeric());
+        let Some(stem) = word.strip_suffix('.').filter(|stem| !stem.is_empty()) else {
+            return false;
+        };
+        // Initials and dotted abbreviations: "J. Smith", "e.g. This", "U.S. Army"
+        if stem.split('.').all(|part| part.chars().count() == 1 && part.chars().all(char::is_alphabetic)) {
+            return true;
+        }
+        // Numbered list markers, and ordinals where written with a period
+        if stem.len() <= 3 && stem.chars().all(|c| c.is_ascii_digit()) {
+            return sentence.trim_start() == word || self.rules.is_some_and(|r| r.ordinal_period);
+        }
+        self.rules.is_some_and(|r| r.abbreviations.contains(stem.to_lowercase().as_str()))
+    }
+}
+
+/// Whether `sentence` ends with terminal punctuation, ignoring closing quotes
+/// and brackets.
+fn ends_with_terminal(sentence: &str) -> bool {
+    sentence
+        .chars()
+        .rev()
+        .find(|&c| !is_closer(c))
+        .is_some_and(|c| {
+            matches!(c, '.' | '!' | '?' | '…' | '‼' | '⁇' | '⁈' | '⁉' | '。' | '｡' | '．' | '！' | '？' | '؟' | '۔' | '।' | '॥' | '።')
+        })
+}
+
+fn is_closer(c: char) -> bool {
+    matches!(c, '"' | '\'' | ')' | ']' | '}' | '”' | '’' | '»' | '」' | '』' | '）' | '】' | '〕' | '》' | '〉')
+}
+
+fn is_thai(c: char) -> bool {
+    ('\u{0E00}'..='\u{0E7F}').contains(&c)
+}
+
+/// Splits `[start, end)` at whitespace between Thai letters, which separates
+/// Thai sentences.
+fn thai_spans(text: &str, start: usize, end: usize) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut cursor = start;
+    let mut previous = None;
+    let mut space_end = None; // end of the whitespace run after a Thai letter
+    for (i, c) in text[start..end].char_indices() {
+        let position = start + i;
+        if c.is_whitespace() {
+            if previous.is_some_and(is_thai) {
+                space_end = Some(position + c.len_utf8());
+            }
+            continue;
+        }
+        if let Some(boundary) = space_end.take() {
+            if is_thai(c) {
+                spans.push((cursor, boundary));
+                cursor = boundary;
+            }
+        }
+        previous = Some(c);
+    }
+    spans.push((cursor, end));
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(text: &str, language: &str) -> Vec<String> {
+        let options = SentenceOptions { language: Some(language.to_string()) };
+        sentences(text, &options).into_iter().map(|s| s.text).collect()
+    }
+
+    #[test]
+    fn test_abbreviations_numbers_and_urls() {
+        assert_eq!(
+            split("Dr. Smith paid $3.50 at example.com, e.g. for coffee. See https://a.io/s?q=1 now! Mr. J. R. Doe left.", "en"),
+            vec![
+                "Dr. Smith paid $3.50 at example.com, e.g. for coffee.",
+                "See https://a.io/s?q=1 now!",
+                "Mr. J. R. Doe left.",
+            ]
+        );
+        assert_eq!(
+            split("Wir treffen uns am 3. Oktober bei Hr. Meier, z.B. im Büro. Danach geht es weiter.", "de"),
+            vec!["Wir treffen uns am 3. Oktober bei Hr. Meier, z.B. im Büro.", "Danach geht es weiter."]
+        );
+        // Only the English list knows "Prof."
+        assert_eq!(split("Ask Prof. Lee.", "en"), vec!["Ask Prof. Lee."]);
+        assert_eq!(split("Ask Prof. Lee.", "xx"), vec!["Ask Prof.", "Lee."]);
+    }
+
+    #[test]
+    fn test_cjk_thai_and_line_breaks() {
+        assert_eq!(split("今天天气很好。我们去公园吧！你来吗？", "zh"), vec!["今天天气很好。", "我们去公园吧！", "你来吗？"]);
+        assert_eq!(split("「はい。」と言った。", "ja"), vec!["「はい。」と言った。"]);
+        assert_eq!(split("วันนี้อากาศดี พรุ่งนี้ฝนตก", "th"), vec!["วันนี้อากาศดี", "พรุ่งนี้ฝนตก"]);
+        assert_eq!(
+            split("A wrapped\nline of text.\n1. First item\n\nHeading\n\nBody", "en"),
+            vec!["A wrapped\nline of text.", "1. First item", "Heading", "Body"]
+        );
+    }
+
+    #[test]
+    fn test_spans_cover_text_with_utf16_offsets() {
+        let text = "  Café? Oui. 🎉 Fête.  ";
+        let segmenter = Segmenter::for_text(text, Some("fr"));
+        let spans = segmenter.spans(text);
+        assert_eq!(spans.first().map(|s| s.0), Some(0));
+        assert_eq!(spans.last().map(|s| s.1), Some(text.len()));
+        assert!(spans.windows(2).all(|w| w[0].1 == w[1].0));
+
+        let utf16: Vec<u16> = text.encode_utf16().collect();
+        let found = sentences(text, &SentenceOptions::default());
+        assert_eq!(found.len(), 3);
+        for sentence in found {
+            assert_eq!(String::from_utf16(&utf16[sentence.start..sentence.end]).unwrap(), sentence.text);
+        }
+    }
+}
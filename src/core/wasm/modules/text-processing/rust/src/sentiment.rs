@@ -0,0 +1,403 @@
+use std::collections::{HashMap, HashSet};
+use lazy_static::lazy_static;
+use serde::{Serialize, Deserialize};
+
+use crate::sentences::{self, SentenceOptions};
+
+/// Mean valence ratings from -4 (most negative) to 4 (most positive) in
+/// the manner of the VADER lexicon, for common English words, emoji and
+/// emoticons.
+const LEXICON: &[(&str, f64)] = &[
+    // Positive
+    ("admire", 2.1), ("agree", 1.5), ("amazing", 2.8), ("appreciate", 1.7), ("appreciated", 2.3),
+    ("awesome", 3.1), ("beautiful", 2.9), ("best", 3.2), ("better", 1.9), ("blessed", 2.9), ("bonus", 1.2),
+    ("brave", 2.4), ("brilliant", 2.8), ("calm", 1.3), ("caring", 2.2), ("celebrate", 2.7),
+    ("cheerful", 2.5), ("clean", 1.7), ("clever", 2.0), ("comfortable", 2.3), ("confident", 2.2),
+    ("congrats", 2.4), ("congratulations", 2.9), ("convenient", 1.8), ("cool", 1.3), ("cute", 2.0),
+    ("delight", 2.9), ("delighted", 2.9), ("delightful", 2.9), ("eager", 1.5), ("easy", 1.9),
+    ("effective", 2.1), ("efficient", 1.8), ("elegant", 2.1), ("encouraging", 2.4), ("enjoy", 2.2),
+    ("enjoyed", 2.3), ("excellent", 2.7), ("excited", 1.4), ("exciting", 2.2), ("fabulous", 2.4),
+    ("fair", 1.3), ("fantastic", 2.6), ("favorite", 2.0), ("favourite", 2.0), ("fine", 0.8),
+    ("flawless", 2.3), ("free", 2.3), ("fresh", 1.3), ("friendly", 2.2), ("fun", 2.3), ("generous", 2.3),
+    ("gift", 1.9), ("glad", 2.0), ("good", 1.9), ("gorgeous", 3.0), ("grateful", 2.0), ("great", 3.1),
+    ("haha", 2.0), ("happy", 2.7), ("helpful", 1.8), ("hero", 2.6), ("honest", 2.3), ("hope", 1.9),
+    ("hopeful", 1.6), ("ideal", 2.4), ("impressed", 2.1), ("impressive", 2.3), ("improve", 1.9),
+    ("improved", 2.1), ("improvement", 2.0), ("interesting", 1.7), ("joy", 2.8), ("kind", 2.4),
+    ("laugh", 2.6), ("liked", 1.8), ("likes", 1.8), ("lol", 2.9), ("love", 3.2), ("loved", 2.9),
+    ("lovely", 2.8), ("loves", 2.7), ("loving", 2.9), ("luck", 2.0), ("lucky", 1.8), ("marvelous", 2.9),
+    ("nice", 1.8), ("ok", 1.2), ("okay", 0.9), ("outstanding", 3.0), ("peace", 2.5), ("peaceful", 2.2),
+    ("perfect", 2.7), ("pleasant", 2.3), ("pleased", 1.9), ("pleasure", 2.7), ("polite", 1.8),
+    ("popular", 1.8), ("positive", 2.6), ("praise", 2.6), ("pretty", 2.2), ("proud", 2.1),
+    ("recommend", 1.5), ("recommended", 1.7), ("reliable", 1.9), ("relieved", 1.6), ("resolved", 1.6),
+    ("safe", 1.9), ("satisfied", 1.8), ("satisfying", 2.0), ("smart", 1.7), ("smile", 1.5),
+    ("splendid", 2.8), ("strong", 2.3), ("success", 2.7), ("successful", 2.8), ("super", 2.9),
+    ("superb", 3.1), ("supportive", 1.9), ("sweet", 2.0), ("terrific", 2.1),
+    ("thank", 1.5), ("thankful", 2.7), ("thanks", 1.9), ("thrilled", 1.9), ("trust", 2.3), ("useful", 1.9),
+    ("valuable", 2.1), ("victory", 2.8), ("warm", 0.9), ("welcome", 2.0), ("win", 2.8), ("winner", 2.8),
+    ("wins", 2.7), ("wonderful", 2.7), ("worth", 0.9), ("worthy", 1.9), ("wow", 2.8), ("yay", 2.4),
+    ("yes", 1.7), ("yummy", 2.4),
+    // Negative
+    ("abuse", -3.2), ("afraid", -2.2), ("angry", -2.3), ("annoyed", -1.6), ("annoying", -1.7),
+    ("attack", -2.1), ("awful", -2.0), ("awkward", -1.0), ("bad", -2.5), ("bitter", -1.8), ("blame", -1.4),
+    ("bored", -1.1), ("boring", -1.3), ("broken", -2.1), ("buggy", -1.5), ("careless", -1.5),
+    ("chaos", -2.7), ("cheat", -2.0), ("complain", -1.5), ("complaint", -1.5), ("confused", -1.3),
+    ("confusing", -1.3), ("crap", -1.6), ("crash", -1.7), ("crashes", -1.6), ("cruel", -2.8), ("cry", -2.1),
+    ("damage", -2.2), ("damaged", -1.9), ("damn", -1.7), ("dead", -3.3), ("delay", -1.3), ("delayed", -0.9),
+    ("desperate", -1.3), ("difficult", -1.5), ("dirty", -1.9), ("disappointed", -1.9),
+    ("disappointing", -2.2), ("disappointment", -2.3), ("disaster", -3.1), ("disgusting", -2.4),
+    ("dislike", -1.6), ("doubt", -1.5), ("dumb", -2.3), ("embarrassed", -1.5), ("error", -1.7),
+    ("errors", -1.4), ("evil", -3.4), ("expensive", -0.9), ("fail", -2.5), ("failed", -2.3), ("fails", -1.8),
+    ("failure", -2.3), ("fake", -2.1), ("fault", -1.7), ("fear", -2.2), ("fraud", -2.8), ("frustrated", -2.4),
+    ("frustrating", -1.9), ("frustration", -2.1), ("furious", -2.7), ("guilty", -1.8), ("hate", -2.7),
+    ("hated", -3.2), ("hates", -1.9), ("hopeless", -2.0), ("horrible", -2.5), ("horrific", -3.4),
+    ("hostile", -1.6), ("hurt", -2.4), ("idiot", -2.3), ("ignored", -1.5), ("inadequate", -1.7),
+    ("incompetent", -2.0), ("insult", -2.3), ("irritating", -2.0), ("lack", -1.3), ("lame", -1.8),
+    ("liar", -3.0), ("lie", -1.6), ("lied", -1.6), ("lonely", -1.5), ("lose", -1.7), ("losing", -1.6),
+    ("loss", -1.3), ("lost", -1.3), ("mad", -2.2), ("mediocre", -1.0), ("mess", -1.5), ("messy", -1.7),
+    ("miserable", -2.2), ("missing", -1.2), ("nasty", -2.6), ("negative", -2.7), ("nervous", -1.1),
+    ("nightmare", -2.3), ("no", -1.2), ("outrage", -2.3), ("overpriced", -1.8), ("pain", -2.3),
+    ("painful", -1.9), ("pathetic", -2.0), ("poor", -2.1), ("poorly", -1.6), ("problem", -1.7),
+    ("problems", -1.7), ("regret", -1.8), ("reject", -1.7), ("rejected", -2.3), ("ridiculous", -1.5),
+    ("risk", -1.1), ("rude", -2.0), ("sad", -2.1), ("scam", -2.7), ("scared", -1.9), ("shame", -2.1),
+    ("shit", -2.6), ("shocked", -1.3), ("sick", -2.3), ("slow", -1.0), ("sorry", -0.3), ("steal", -2.2),
+    ("stolen", -2.2), ("stress", -1.8), ("stressful", -2.2), ("struggle", -1.5), ("stupid", -2.4),
+    ("suck", -1.9), ("sucks", -1.5), ("suffer", -2.5), ("terrible", -2.1), ("threat", -2.4), ("tired", -1.9),
+    ("trouble", -1.7), ("ugh", -1.8), ("ugly", -2.3), ("unacceptable", -2.0), ("unfair", -2.1),
+    ("unfortunately", -1.5), ("unhappy", -1.8), ("unhelpful", -1.7), ("unreliable", -1.5),
+    ("unusable", -2.0), ("upset", -1.6), ("useless", -1.8), ("waste", -1.8), ("wasted", -2.2),
+    ("weak", -1.9), ("weird", -0.7), ("worried", -1.2), ("worry", -1.9), ("worse", -2.1), ("worst", -3.1),
+    ("worthless", -1.9), ("wrong", -2.1), ("wtf", -2.8), ("yuck", -1.8),
+    // Emoji and emoticons
+    ("😀", 2.2), ("😃", 2.2), ("😄", 2.4), ("😁", 2.0), ("😂", 1.8), ("😊", 2.3), ("🙂", 1.5), ("😍", 2.8),
+    ("🥰", 2.8), ("😘", 2.1), ("👍", 1.8), ("👏", 1.8), ("🎉", 2.2), ("❤", 2.6), ("💯", 2.0), ("🙏", 1.0),
+    ("😢", -2.1), ("😭", -2.3), ("😞", -2.2), ("😟", -1.8), ("🙁", -1.5), ("☹", -1.9), ("😒", -1.6),
+    ("😠", -2.5), ("😡", -2.7), ("🤬", -3.0), ("😤", -1.6), ("😩", -2.0), ("😫", -2.0), ("😱", -1.9),
+    ("🤮", -2.5), ("👎", -1.8), ("💔", -2.6),
+    (":)", 2.0), (":-)", 1.3), (":D", 2.3), (":-D", 2.3), (";)", 0.9), (";-)", 1.0), ("<3", 1.9), (":P", 1.4),
+    (":(", -1.9), (":-(", -1.5), (":'(", -2.2), (":/", -1.4), (":-/", -1.2), (">:(", -2.5),
+];
+
+/// Words that strengthen or weaken the sentiment word after them.
+const BOOSTERS: &[(&str, f64)] = &[
+    ("absolutely", B_INCR), ("amazingly", B_INCR), ("awfully", B_INCR), ("completely", B_INCR),
+    ("considerably", B_INCR), ("decidedly", B_INCR), ("deeply", B_INCR), ("enormously", B_INCR),
+    ("entirely", B_INCR), ("especially", B_INCR), ("exceptionally", B_INCR), ("extremely", B_INCR),
+    ("fabulously", B_INCR), ("fully", B_INCR), ("greatly", B_INCR), ("highly", B_INCR), ("hugely", B_INCR),
+    ("incredibly", B_INCR), ("intensely", B_INCR), ("majorly", B_INCR), ("more", B_INCR), ("most", B_INCR),
+    ("particularly", B_INCR), ("purely", B_INCR), ("quite", B_INCR), ("really", B_INCR),
+    ("remarkably", B_INCR), ("so", B_INCR), ("substantially", B_INCR),
+    ("thoroughly", B_INCR), ("totally", B_INCR), ("tremendously", B_INCR), ("unbelievably", B_INCR),
+    ("unusually", B_INCR), ("utterly", B_INCR), ("very", B_INCR),
+    ("almost", B_DECR), ("barely", B_DECR), ("hardly", B_DECR), ("kinda", B_DECR), ("less", B_DECR),
+    ("little", B_DECR), ("marginally", B_DECR), ("occasionally", B_DECR), ("partly", B_DECR),
+    ("scarcely", B_DECR), ("slightly", B_DECR), ("somewhat", B_DECR), ("sorta", B_DECR),
+];
+
+const NEGATIONS: &[&str] = &[
+    "aint", "arent", "cannot", "cant", "couldnt", "didnt", "doesnt", "dont", "hadnt", "hasnt", "havent",
+    "isnt", "mightnt", "mustnt", "neednt", "neither", "never", "none", "nope", "nor", "not", "nothing",
+    "nowhere", "shouldnt", "wasnt", "werent", "without", "wont", "wouldnt", "rarely", "seldom", "despite",
+];
+
+/// Boost of a booster word, and of an all-caps word in mixed-case text.
+const B_INCR: f64 = 0.293;
+const B_DECR: f64 = -0.293;
+const C_INCR: f64 = 0.733;
+/// Scales a negated valence.
+const N_SCALAR: f64 = -0.74;
+/// Spreads sums of valences over (-1, 1) in the compound score.
+const ALPHA: f64 = 15.0;
+/// Compound score beyond which a text counts as positive or negative.
+const TONE_THRESHOLD: f64 = 0.05;
+
+lazy_static! {
+    static ref VALENCES: HashMap<&'static str, f64> = LEXICON.iter().copied().collect();
+    static ref BOOSTS: HashMap<&'static str, f64> = BOOSTERS.iter().copied().collect();
+    static ref NEGATION_WORDS: HashSet<&'static str> = NEGATIONS.iter().copied().collect();
+}
+
+/// Options in the shape of the TS `SentimentOptions`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct SentimentOptions {
+    /// Valences from -4 to 4 by lowercase word, overriding or extending the
+    /// built-in English lexicon; 0 takes a word out.
+    pub lexicon: HashMap<String, f64>,
+    /// ISO 639-1 code choosing the sentence rules; detected when unset.
+    pub language: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Tone {
+    Positive,
+    Negative,
+    /// Strong positive and negative sentiment that cancel out.
+    Mixed,
+    #[default]
+    Neutral,
+}
+
+/// VADER scores in the shape of the TS `SentimentScore`: `compound` from -1
+/// to 1, and the shares of positive, negative and neutral sentiment.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct SentimentScore {
+    pub compound: f64,
+    pub positive: f64,
+    pub negative: f64,
+    pub neutral: f64,
+    pub tone: Tone,
+}
+
+impl SentimentScore {
+    fn new(compound: f64, positive: f64, negative: f64, neutral: f64) -> Self {
+        let tone = if compound >= TONE_THRESHOLD {
+            Tone::Positive
+        } else if compound <= -TONE_THRESHOLD {
+            Tone::Negative
+        } else if positive >= 0.1 && negative >= 0.1 {
+            Tone::Mixed
+        } else {
+            Tone::Neutral
+        };
+        SentimentScore { compound, positive, negative, neutral, tone }
+    }
+}
+
+/// A sentence and its scores, in UTF-16 offsets.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SentenceSentiment {
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+    pub score: SentimentScore,
+}
+
+/// The TS `Sentiment`: each sentence scored, and the mean of those scores
+/// for the whole text.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Sentiment {
+    pub document: SentimentScore,
+    pub sentences: Vec<SentenceSentiment>,
+}
+
+pub fn sentiment(text: &str, options: &SentimentOptions) -> Sentiment {
+    let sentences: Vec<SentenceSentiment> = sentences::sentences(text, &SentenceOptions { language: options.language.clone() })
+        .into_iter()
+        .map(|s| SentenceSentiment { score: score(&s.text, options), text: s.text, start: s.start, end: s.end })
+        .collect();
+    Sentiment { document: mean(sentences.iter().map(|s| &s.score)), sentences }
+}
+
+/// Scores of a chunk or other passage: the mean over its sentences.
+pub(crate) fn passage(text: &str, options: &SentimentOptions) -> SentimentScore {
+    sentiment(text, options).document
+}
+
+fn mean<'a>(scores: impl Iterator<Item = &'a SentimentScore>) -> SentimentScore {
+    let (mut sum, mut n) = ([0.0; 4], 0.0);
+    for score in scores {
+        for (total, value) in sum.iter_mut().zip([score.compound, score.positive, score.negative, score.neutral]) {
+            *total += value;
+        }
+        n += 1.0;
+    }
+    if n == 0.0 {
+        return SentimentScore::default();
+    }
+    let [compound, positive, negative, neutral] = sum.map(|total| total / n);
+    SentimentScore::new(compound, positive, negative, neutral)
+}
+
+/// VADER's rules for one sentence: valences of lexicon words, boosted by
+/// booster words and capitals, flipped by negations up to three words
+/// back, shifted towards the clause after "but", and amplified by `!` and `?`.
+fn score(sentence: &str, options: &SentimentOptions) -> SentimentScore {
+    let words = words(sentence);
+    // Emoticons such as ":D" are matched verbatim, everything else lowercased
+    let lower: Vec<String> = words
+        .iter()
+        .map(|w| if VALENCES.contains_key(w.as_str()) { w.clone() } else { w.to_lowercase() })
+        .collect();
+    let valence = |word: &str| match options.lexicon.get(word) {
+        Some(&valence) => Some(valence).filter(|&v| v != 0.0),
+        None => VALENCES.get(word).copied(),
+    };
+    // Capitals only stress a word when the rest of the text is not shouting
+    let shouted: Vec<bool> = words
+        .iter()
+        .map(|w| w.chars().any(char::is_alphabetic) && !w.chars().any(char::is_lowercase) && !VALENCES.contains_key(w.as_str()))
+        .collect();
+    let mixed_case = shouted.contains(&true) && shouted.contains(&false);
+
+    let mut sentiments = Vec::with_capacity(words.len());
+    for (i, word) in lower.iter().enumerate() {
+        let kind_of = word == "kind" && lower.get(i + 1).is_some_and(|w| w == "of");
+        let (Some(mut value), false) = (valence(word), kind_of || BOOSTS.contains_key(word.as_str())) else {
+            sentiments.push(0.0);
+            continue;
+        };
+        if mixed_case && shouted[i] {
+            value += C_INCR.copysign(value);
+        }
+        for distance in 1..=3.min(i) {
+            let prior = lower[i - distance].as_str();
+            if valence(prior).is_some() {
+                continue;
+            }
+            if let Some(&boost) = BOOSTS.get(prior) {
+                let mut boost = if value < 0.0 { -boost } else { boost };
+                if mixed_case && shouted[i - distance] {
+                    boost += C_INCR.copysign(value);
+                }
+                value += boost * [1.0, 0.95, 0.9][distance - 1];
+            }
+            if is_negation(prior) {
+                let never_so = prior == "never" && distance > 1 && matches!(lower[i - distance + 1].as_str(), "so" | "this");
+                let without_doubt = prior == "without" && lower[i - distance + 1] == "doubt";
+                if never_so {
+                    value *= 1.25;
+                } else if !without_doubt {
+                    value *= N_SCALAR;
+                }
+            }
+        }
+        // "least good" but not "at least good"
+        if i > 0 && lower[i - 1] == "least" && (i < 2 || !matches!(lower[i - 2].as_str(), "at" | "very")) {
+            value *= N_SCALAR;
+        }
+        sentiments.push(value);
+    }
+
+    if let Some(but) = lower.iter().position(|w| w == "but") {
+        for (i, value) in sentiments.iter_mut().enumerate() {
+            *value *= if i < but { 0.5 } else if i > but { 1.5 } else { 1.0 };
+        }
+    }
+    if sentiments.iter().all(|&v| v == 0.0) {
+        return SentimentScore::new(0.0, 0.0, 0.0, if sentiments.is_empty() { 0.0 } else { 1.0 });
+    }
+
+    let emphasis = punctuation_emphasis(sentence);
+    let sum: f64 = sentiments.iter().sum();
+    let sum = sum + emphasis.copysign(sum);
+    let compound = sum / (sum * sum + ALPHA).sqrt();
+
+    let mut positive = sentiments.iter().filter(|&&v| v > 0.0).fold(0.0, |sum, v| sum + v + 1.0);
+    let mut negative = sentiments.iter().filter(|&&v| v < 0.0).fold(0.0, |sum, v| sum - v + 1.0);
+    let neutral = sentiments.iter().filter(|&&v| v == 0.0).count() as f64;
+    if positive > negative {
+        positive += emphasis;
+    } else if positive < negative {
+        negative += emphasis;
+    }
+    let total = positive + negative + neutral;
+    SentimentScore::new(compound.clamp(-1.0, 1.0), positive / total, negative / total, neutral / total)
+}
+
+/// Words and emoticons: whitespace-separated tokens stripped of surrounding
+/// punctuation unless they are emoticons, with each emoji a word of its own.
+fn words(sentence: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for token in sentence.split_whitespace() {
+        if VALENCES.contains_key(token) {
+            words.push(token.to_string());
+            continue;
+        }
+        let mut word = String::new();
+        let flush = |word: &mut String, words: &mut Vec<String>| {
+            let stripped = word.trim_matches(|c: char| !c.is_alphanumeric());
+            if !stripped.is_empty() {
+                words.push(stripped.to_string());
+            }
+            word.clear();
+        };
+        for c in token.chars().filter(|&c| c != '\u{FE0F}') {
+            if is_emoji(c) {
+                flush(&mut word, &mut words);
+                words.push(c.to_string());
+            } else {
+                word.push(if c == '’' { '\'' } else { c });
+            }
+        }
+        flush(&mut word, &mut words);
+    }
+    words
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32, 0x1F300..=0x1FAFF | 0x2600..=0x27BF)
+}
+
+fn is_negation(word: &str) -> bool {
+    NEGATION_WORDS.contains(word) || NEGATION_WORDS.contains(word.replace('\'', "").as_str()) || word.ends_with("n't")
+}
+
+/// Intensity added by exclamation marks, up to four, and by two or more
+/// question marks.
+fn punctuation_emphasis(sentence: &str) -> f64 {
+    let exclamations = sentence.matches('!').count().min(4) as f64 * 0.292;
+    let questions = match sentence.matches('?').count() {
+        0 | 1 => 0.0,
+        n @ 2..=3 => n as f64 * 0.18,
+        _ => 0.96,
+    };
+    exclamations + questions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compound(text: &str) -> f64 {
+        score(text, &SentimentOptions::default()).compound
+    }
+
+    #[test]
+    fn test_rules_shift_the_compound() {
+        let good = compound("The support team was good.");
+        assert!(good > 0.3);
+        assert!(compound("The support team was very good.") > good);
+        assert!(compound("The support team was VERY good.") > compound("The support team was very good."));
+        assert!(compound("The support team was good!!!") > good);
+        assert!(compound("The support team was slightly good.") < good);
+        assert!(compound("The support team was not good.") < 0.0);
+        assert!(compound("The support team wasn't bad.") > 0.0);
+        assert!(compound("The support team was good 👍") > good);
+        assert!(compound("I waited an hour :(") < 0.0);
+        for emoticon in [":D", ":-D", ":P"] {
+            assert!(compound(&format!("Thanks {}", emoticon)) > compound("Thanks"), "{}", emoticon);
+        }
+        // The clause after "but" weighs more
+        assert!(compound("The food was great, but the service was terrible.") < 0.0);
+        assert!(compound("The service was terrible, but the food was great.") > 0.0);
+
+        let scores = score("Not great, not terrible.", &SentimentOptions::default());
+        assert!((scores.positive + scores.negative + scores.neutral - 1.0).abs() < 1e-9);
+        assert_eq!(score("The invoice is attached.", &SentimentOptions::default()).tone, Tone::Neutral);
+    }
+
+    #[test]
+    fn test_sentences_and_document() {
+        let text = "I love this product. The delivery was awful. It works.";
+        let found = sentiment(text, &SentimentOptions::default());
+        let tones: Vec<Tone> = found.sentences.iter().map(|s| s.score.tone).collect();
+        assert_eq!(tones, vec![Tone::Positive, Tone::Negative, Tone::Neutral]);
+        assert_eq!((found.sentences[1].start, found.sentences[1].end), (21, 44));
+        let mean = found.sentences.iter().map(|s| s.score.compound).sum::<f64>() / 3.0;
+        assert!((found.document.compound - mean).abs() < 1e-9);
+        assert_eq!(sentiment("", &SentimentOptions::default()).document, SentimentScore::default());
+    }
+
+    #[test]
+    fn test_lexicon_overrides() {
+        let text = "The checkout is snappy but the dashboard is slow.";
+        assert!(compound(text) < 0.0);
+        let options = SentimentOptions {
+            lexicon: HashMap::from([("snappy".to_string(), 2.5), ("slow".to_string(), 0.0)]),
+            ..Default::default()
+        };
+        assert!(score(text, &options).compound > 0.0);
+        assert_eq!(score("It is slow.", &options).tone, Tone::Neutral);
+    }
+}
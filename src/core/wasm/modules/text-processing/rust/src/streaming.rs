@@ -0,0 +1,223 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::tokenizer::Tokenizer;
+use crate::{fixed_span, next_window_start, window_end, ChunkSpan, ProcessingConfig, SizeUnit};
+
+/// Unbroken text (no whitespace) held back before it is chunked regardless.
+const MAX_UNBROKEN_BYTES: usize = 64 * 1024;
+/// Initial guess of UTF-8 bytes per token when sizing a tokenization window.
+const BYTES_PER_TOKEN: usize = 8;
+
+/// A finished chunk with UTF-16 offsets relative to the start of the stream.
+pub struct StreamedChunk {
+    pub span: ChunkSpan,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Fixed-window chunker fed with text in pieces. Only the text from the start
+/// of the current window onward is buffered, so memory stays proportional to
+/// the chunk size rather than to the input.
+pub struct StreamingChunker {
+    config: ProcessingConfig,
+    pending: String,
+    byte_offset: usize,  // stream offset of pending[0] in bytes
+    utf16_offset: usize, // and in UTF-16 code units
+}
+
+impl StreamingChunker {
+    pub fn new(config: ProcessingConfig) -> Self {
+        StreamingChunker {
+            config,
+            pending: String::new(),
+            byte_offset: 0,
+            utf16_offset: 0,
+        }
+    }
+
+    pub fn config(&self) -> &ProcessingConfig {
+        &self.config
+    }
+
+    /// Appends `text` and returns the chunks that can no longer change.
+    pub fn push(&mut self, text: &str, tokenizer: Option<&Tokenizer>) -> Vec<StreamedChunk> {
+        self.pending.push_str(text);
+        self.drain(false, tokenizer)
+    }
+
+    /// Flushes the remaining text as final chunks.
+    pub fn finish(&mut self, tokenizer: Option<&Tokenizer>) -> Vec<StreamedChunk> {
+        let chunks = self.drain(true, tokenizer);
+        self.pending.clear();
+        chunks
+    }
+
+    fn drain(&mut self, last: bool, tokenizer: Option<&Tokenizer>) -> Vec<StreamedChunk> {
+        let mut chunks = Vec::new();
+        let mut cursor = 0;
+        let mut cursor_utf16 = self.utf16_offset;
+        let stable = if last { self.pending.len() } else { stable_end(&self.pending) };
+
+        while cursor < stable {
+            let window = &self.pending[cursor..stable];
+            let units = units(window, &self.config, tokenizer);
+            let complete = units.len() > self.config.chunk_size.max(1);
+            if units.is_empty() || (!complete && !last) {
+                break;
+            }
+
+            let chunk_end = window_end(window, &units, 0, &self.config);
+            let mut span = fixed_span(window, &units, 0, chunk_end, &self.config);
+            let start = cursor_utf16 + window[..span.start].encode_utf16().count();
+            let end = start + window[span.start..span.end].encode_utf16().count();
+            span.start += self.byte_offset + cursor;
+            span.end += self.byte_offset + cursor;
+            chunks.push(StreamedChunk { span, start, end });
+
+            if !complete {
+                cursor = stable;
+                break;
+            }
+            let advance = units[next_window_start(0, chunk_end, self.config.overlap)].0;
+            cursor_utf16 += window[..advance].encode_utf16().count();
+            cursor += advance;
+        }
+
+        self.pending.drain(..cursor);
+        self.byte_offset += cursor;
+        self.utf16_offset = cursor_utf16;
+        chunks
+    }
+}
+
+/// Length of the prefix of `text` whose graphemes and tokens cannot change
+/// when more text is appended: everything before the trailing word and the
+/// whitespace run preceding it.
+fn stable_end(text: &str) -> usize {
+    let end = text.trim_end_matches(|c: char| !c.is_whitespace()).trim_end().len();
+    if text.len() - end > MAX_UNBROKEN_BYTES {
+        let mut end = text.len() - MAX_UNBROKEN_BYTES / 2;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        return end;
+    }
+    end
+}
+
+/// The first `chunk_size + 1` units of `window`; one unit past a full window
+/// tells that the window is complete.
+fn units(window: &str, config: &ProcessingConfig, tokenizer: Option<&Tokenizer>) -> Vec<(usize, usize)> {
+    let wanted = config.chunk_size.max(1) + 1;
+    match (config.size_unit, tokenizer) {
+        (SizeUnit::Tokens, Some(tokenizer)) => {
+            let mut limit = wanted * BYTES_PER_TOKEN;
+            loop {
+                let prefix = if limit >= window.len() {
+                    window.len()
+                } else {
+                    match stable_end(&window[..floor_char_boundary(window, limit)]) {
+                        0 => floor_char_boundary(window, limit),
+                        end => end,
+                    }
+                };
+                let tokens = tokenizer.tokenize(&window[..prefix]);
+                if tokens.len() >= wanted || prefix == window.len() {
+                    return tokens
+                        .into_iter()
+                        .take(wanted)
+                        .map(|t| (floor_char_boundary(window, t.start), ceil_char_boundary(window, t.end)))
+                        .collect();
+                }
+                limit *= 2;
+            }
+        }
+        _ => window
+            .grapheme_indices(true)
+            .take(wanted)
+            .map(|(i, g)| (i, i + g.len()))
+            .collect(),
+    }
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn ceil_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextProcessor;
+
+    const TEXT: &str = "Streaming keeps offsets global. Each piece is appended to a buffer! \
+        Finished chunks leave it again? Only a window stays in memory.\r\n\r\nUnicode like café, 👋🏽 and 日本語 must survive piece borders. \
+        The last sentence has no terminator";
+
+    fn stream(processor: &TextProcessor, config: ProcessingConfig, piece_len: usize) -> Vec<(String, usize, usize)> {
+        let mut chunker = StreamingChunker::new(config);
+        let mut chunks = Vec::new();
+        let mut rest = TEXT;
+        while !rest.is_empty() {
+            let cut = ceil_char_boundary(rest, piece_len.min(rest.len()));
+            chunks.extend(chunker.push(&rest[..cut], processor.tokenizer.as_ref()));
+            assert!(chunker.pending.len() < 3 * 40 + piece_len + 16);
+            rest = &rest[cut..];
+        }
+        chunks.extend(chunker.finish(processor.tokenizer.as_ref()));
+        chunks.into_iter().map(|c| (c.span.text, c.start, c.end)).collect()
+    }
+
+    #[test]
+    fn test_matches_whole_text_chunking() {
+        let processor = TextProcessor::new();
+        let config = || ProcessingConfig { chunk_size: 40, overlap: 8, ..Default::default() };
+        let expected: Vec<(String, usize, usize)> = processor
+            .chunk_text_impl(TEXT, &config())
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.text, c.start, c.end))
+            .collect();
+        // Offsets slice the text the way JS strings do
+        let utf16: Vec<u16> = TEXT.encode_utf16().collect();
+        assert!(expected.iter().all(|(text, start, end)| String::from_utf16(&utf16[*start..*end]).unwrap() == *text));
+
+        for piece_len in [1, 3, 7, 64, TEXT.len()] {
+            assert_eq!(stream(&processor, config(), piece_len), expected, "piece length {}", piece_len);
+        }
+    }
+
+    #[test]
+    fn test_streams_by_tokens() {
+        let mut processor = TextProcessor::new();
+        let vocab: String = ["[UNK]", "."].iter().map(|t| format!("{}\n", t)).collect::<String>()
+            + &TEXT.split(|c: char| !c.is_alphanumeric()).map(|w| format!("{}\n", w.to_lowercase())).collect::<String>();
+        processor.tokenizer = Some(Tokenizer::load(&vocab, crate::tokenizer::TokenizerFormat::WordPiece).unwrap());
+        let config = || ProcessingConfig { chunk_size: 12, overlap: 2, size_unit: SizeUnit::Tokens, ..Default::default() };
+        let expected: Vec<(String, usize, usize)> = processor
+            .chunk_text_impl(TEXT, &config())
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.text, c.start, c.end))
+            .collect();
+
+        assert_eq!(stream(&processor, config(), 5), expected);
+    }
+
+    #[test]
+    fn test_stable_end() {
+        assert_eq!(stable_end("one two thr"), 7);
+        assert_eq!(stable_end("one two \r"), 7);
+        assert_eq!(stable_end("unbroken"), 0);
+        assert!(stable_end(&"x".repeat(MAX_UNBROKEN_BYTES + 1)) > 0);
+    }
+}
@@ -0,0 +1,342 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::{ChunkSpan, ProcessingConfig};
+use crate::sentences::Segmenter;
+
+lazy_static! {
+    static ref ATX_HEADING: Regex = Regex::new(r"^ {0,3}(#{1,6})(?:[ \t]+(.*?))?(?:[ \t]+#+)?[ \t]*$").unwrap();
+    static ref SETEXT_UNDERLINE: Regex = Regex::new(r"^ {0,3}(=+|-+)[ \t]*$").unwrap();
+    static ref FENCE: Regex = Regex::new(r"^ {0,3}(`{3,}|~{3,})").unwrap();
+    static ref LIST_ITEM: Regex = Regex::new(r"^[ \t]*(?:[-*+]|\d{1,9}[.)])[ \t]+\S").unwrap();
+    static ref TABLE_DELIMITER: Regex = Regex::new(r"^[ \t]*\|?[ \t]*:?-+:?[ \t]*(?:\|[ \t]*:?-+:?[ \t]*)*\|?[ \t]*$").unwrap();
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockKind {
+    Heading { level: usize, title: String },
+    Paragraph,
+    ListItem,
+    Code,
+    /// One table row; the header row also carries its delimiter row.
+    TableRow,
+}
+
+/// A Markdown block with byte offsets covering its lines, line endings included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub kind: BlockKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl BlockKind {
+    /// Blocks whose text is kept verbatim and never split.
+    fn is_verbatim(&self) -> bool {
+        matches!(self, BlockKind::Code | BlockKind::TableRow)
+    }
+}
+
+/// Splits Markdown (or plain text, which parses as paragraphs) into blocks.
+pub fn parse_blocks(text: &str) -> Vec<Block> {
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut open: Option<Block> = None; // paragraph or list item still accepting lines
+    let mut fence: Option<(char, usize, usize)> = None; // (marker, length, block start)
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        let start = offset;
+        let end = offset + line.len();
+        offset = end;
+        let content = line.trim_end_matches(['\n', '\r']);
+
+        if let Some((marker, len, fence_start)) = fence {
+            let trimmed = content.trim_start();
+            let closing = trimmed.chars().take_while(|&c| c == marker).count();
+            if closing >= len && trimmed[closing * marker.len_utf8()..].trim().is_empty() {
+                blocks.push(Block { kind: BlockKind::Code, start: fence_start, end });
+                fence = None;
+            }
+            continue;
+        }
+
+        if content.trim().is_empty() {
+            blocks.extend(open.take());
+            continue;
+        }
+
+        if let Some(caps) = FENCE.captures(content) {
+            blocks.extend(open.take());
+            let marker = &caps[1];
+            fence = Some((marker.chars().next().unwrap_or('`'), marker.len(), start));
+            continue;
+        }
+
+        if let Some(caps) = ATX_HEADING.captures(content) {
+            blocks.extend(open.take());
+            blocks.push(Block {
+                kind: BlockKind::Heading {
+                    level: caps[1].len(),
+                    title: caps.get(2).map_or("", |m| m.as_str()).trim().to_string(),
+                },
+                start,
+                end,
+            });
+            continue;
+        }
+
+        if let Some(caps) = SETEXT_UNDERLINE.captures(content) {
+            if let Some(paragraph) = open.as_ref().filter(|b| b.kind == BlockKind::Paragraph) {
+                let title = text[paragraph.start..paragraph.end].split_whitespace().collect::<Vec<_>>().join(" ");
+                let level = if caps[1].starts_with('=') { 1 } else { 2 };
+                blocks.push(Block {
+                    kind: BlockKind::Heading { level, title },
+                    start: paragraph.start,
+                    end,
+                });
+                open = None;
+                continue;
+            }
+        }
+
+        let in_table = matches!(blocks.last(), Some(b) if b.kind == BlockKind::TableRow && b.end == start);
+        let starts_table = content.trim_start().starts_with('|')
+            || (content.contains('|')
+                && text[end..].lines().next().is_some_and(|next| {
+                    next.contains('|') && TABLE_DELIMITER.is_match(next)
+                }));
+        if open.is_none() && (in_table || starts_table) && content.contains('|') {
+            if in_table && TABLE_DELIMITER.is_match(content) {
+                if let Some(header) = blocks.last_mut() {
+                    header.end = end;
+                }
+            } else {
+                blocks.push(Block { kind: BlockKind::TableRow, start, end });
+            }
+            continue;
+        }
+
+        if LIST_ITEM.is_match(content) {
+            blocks.extend(open.take());
+            open = Some(Block { kind: BlockKind::ListItem, start, end });
+            continue;
+        }
+
+        match open.as_mut() {
+            // Indented or lazy continuation lines belong to the open block
+            Some(block) => block.end = end,
+            None => open = Some(Block { kind: BlockKind::Paragraph, start, end }),
+        }
+    }
+
+    if let Some((_, _, fence_start)) = fence {
+        blocks.push(Block { kind: BlockKind::Code, start: fence_start, end: text.len() });
+    }
+    blocks.extend(open);
+    blocks
+}
+
+/// Packs blocks into chunks of at most `chunk_size` (as counted by `measure`).
+/// Every heading starts a new chunk, code blocks and table rows are never
+/// split, and oversized paragraphs are split at sentence and then word
+/// boundaries. Chunks follow block edges, so `overlap` does not apply. Their
+/// text is the source text as is; whitespace is collapsed by the
+/// `normalization` stage, before chunking.
+pub fn chunk(text: &str, config: &ProcessingConfig, measure: &dyn Fn(&str) -> usize) -> Vec<ChunkSpan> {
+    let limit = config.chunk_size.max(1);
+    let segmenter = Segmenter::for_text(text, config.language.as_deref());
+    let mut spans = Vec::new();
+    let mut path: Vec<(usize, String)> = Vec::new();
+    let mut current: Vec<(usize, usize)> = Vec::new();
+    let mut size = 0;
+
+    for block in parse_blocks(text) {
+        if let BlockKind::Heading { level, title } = &block.kind {
+            flush(text, config, &path, &mut current, &mut spans);
+            size = 0;
+            while path.last().is_some_and(|&(l, _)| l >= *level) {
+                path.pop();
+            }
+            path.push((*level, title.clone()));
+        }
+
+        let verbatim = block.kind.is_verbatim();
+        let pieces = if verbatim || measure(&text[block.start..block.end]) <= limit {
+            vec![(block.start, block.end)]
+        } else {
+            split_oversized(text, block.start, block.end, limit, &segmenter, measure)
+        };
+
+        for (start, end) in pieces {
+            let gap_start = current.last().map_or(start, |&(_, e)| e);
+            let piece_size = measure(&text[gap_start..end]);
+            if !current.is_empty() && size + piece_size > limit {
+                flush(text, config, &path, &mut current, &mut spans);
+                size = measure(&text[start..end]);
+            } else {
+                size += piece_size;
+            }
+            current.push((start, end));
+        }
+    }
+    flush(text, config, &path, &mut current, &mut spans);
+
+    spans
+}
+
+fn flush(
+    text: &str,
+    config: &ProcessingConfig,
+    path: &[(usize, String)],
+    current: &mut Vec<(usize, usize)>,
+    spans: &mut Vec<ChunkSpan>,
+) {
+    let (Some(&(mut start, _)), Some(&(_, mut end))) = (current.first(), current.last()) else {
+        return;
+    };
+    current.clear();
+
+    if config.trim_chunks {
+        let untrimmed = &text[start..end];
+        start += untrimmed.len() - untrimmed.trim_start().len();
+        end = start + untrimmed.trim().len();
+    }
+    if start == end {
+        return;
+    }
+    spans.push(ChunkSpan {
+        start,
+        end,
+        text: text[start..end].to_string(),
+        token_count: None,
+        heading_path: path.iter().map(|(_, title)| title.clone()).collect(),
+    });
+}
+
+/// Splits an oversized prose block at sentence boundaries, falling back to
+/// word boundaries for sentences that are too long on their own.
+pub(crate) fn split_oversized(
+    text: &str,
+    start: usize,
+    end: usize,
+    limit: usize,
+    segmenter: &Segmenter,
+    measure: &dyn Fn(&str) -> usize,
+) -> Vec<(usize, usize)> {
+    let sentences = segmenter.spans(&text[start..end]).into_iter().map(|(s, e)| (start + s, start + e));
+
+    let mut pieces = Vec::new();
+    for (sentence_start, sentence_end) in sentences {
+        if measure(&text[sentence_start..sentence_end]) <= limit {
+            pieces.push((sentence_start, sentence_end));
+            continue;
+        }
+        let mut piece_start = sentence_start;
+        let mut last_break = None;
+        for (i, c) in text[sentence_start..sentence_end].char_indices() {
+            let position = sentence_start + i;
+            if c.is_whitespace() && position > piece_start {
+                if measure(&text[piece_start..position]) > limit {
+                    if let Some(cut) = last_break.take() {
+                        pieces.push((piece_start, cut));
+                        piece_start = cut;
+                    }
+                }
+                last_break = Some(position + c.len_utf8());
+            }
+        }
+        if measure(&text[piece_start..sentence_end]) > limit {
+            if let Some(cut) = last_break.filter(|&cut| cut > piece_start && cut < sentence_end) {
+                pieces.push((piece_start, cut));
+                piece_start = cut;
+            }
+        }
+        pieces.push((piece_start, sentence_end));
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unicode_segmentation::UnicodeSegmentation;
+
+    const DOCUMENT: &str = "# Chapter 2\n\nIntro text.\n\n## Pricing\n\nPrices are listed below.\n\n| Plan | Price |\n|------|-------|\n| Basic | $10 |\n| Pro | $20 |\n\n```rust\nfn main() {\n\n    println!(\"hi\");\n}\n```\n\n## Support\n\n- Email us\n- Call us\n  any time\n";
+
+    fn graphemes(s: &str) -> usize {
+        s.graphemes(true).count()
+    }
+
+    #[test]
+    fn test_parse_blocks() {
+        let kinds: Vec<BlockKind> = parse_blocks(DOCUMENT).into_iter().map(|b| b.kind).collect();
+        assert_eq!(kinds, vec![
+            BlockKind::Heading { level: 1, title: "Chapter 2".to_string() },
+            BlockKind::Paragraph,
+            BlockKind::Heading { level: 2, title: "Pricing".to_string() },
+            BlockKind::Paragraph,
+            BlockKind::TableRow,
+            BlockKind::TableRow,
+            BlockKind::TableRow,
+            BlockKind::Code,
+            BlockKind::Heading { level: 2, title: "Support".to_string() },
+            BlockKind::ListItem,
+            BlockKind::ListItem,
+        ]);
+
+        let setext = parse_blocks("Title\n=====\nBody\n");
+        assert_eq!(setext[0].kind, BlockKind::Heading { level: 1, title: "Title".to_string() });
+        assert_eq!(setext[1].kind, BlockKind::Paragraph);
+    }
+
+    #[test]
+    fn test_chunks_carry_heading_path() {
+        let config = ProcessingConfig { chunk_size: 200, ..Default::default() };
+        let spans = chunk(DOCUMENT, &config, &graphemes);
+        let paths: Vec<Vec<String>> = spans.iter().map(|s| s.heading_path.clone()).collect();
+        assert_eq!(paths, vec![
+            vec!["Chapter 2".to_string()],
+            vec!["Chapter 2".to_string(), "Pricing".to_string()],
+            vec!["Chapter 2".to_string(), "Support".to_string()],
+        ]);
+        assert!(spans[1].text.starts_with("## Pricing"));
+        assert!(spans[1].text.contains("fn main() {\n\n    println!"));
+    }
+
+    #[test]
+    fn test_never_splits_code_or_table_rows() {
+        let config = ProcessingConfig { chunk_size: 20, ..Default::default() };
+        let spans = chunk(DOCUMENT, &config, &graphemes);
+
+        let code = spans.iter().filter(|s| s.text.contains("```")).collect::<Vec<_>>();
+        assert_eq!(code.len(), 1);
+        assert!(code[0].text.starts_with("```rust") && code[0].text.ends_with("```"));
+        for span in spans.iter().filter(|s| s.text.contains('|')) {
+            assert!(span.text.lines().all(|line| line.starts_with('|') && line.ends_with('|')));
+        }
+        assert!(spans.iter().any(|s| s.text.starts_with("| Plan | Price |\n|------|")));
+    }
+
+    #[test]
+    fn test_text_is_the_source_slice() {
+        let text = "Some   spaced\ntext  here.\n\n\n\nNext paragraph.\n\n- item\n";
+        for (preserve_whitespace, preserve_newlines) in [(false, false), (true, true)] {
+            let config = ProcessingConfig { preserve_whitespace, preserve_newlines, ..Default::default() };
+            let spans = chunk(text, &config, &graphemes);
+            assert_eq!(spans[0].text, text.trim_end());
+            assert!(spans.iter().all(|s| s.text == text[s.start..s.end]));
+        }
+        let spans = chunk(DOCUMENT, &ProcessingConfig { chunk_size: 20, ..Default::default() }, &graphemes);
+        assert!(spans.iter().all(|s| s.text == DOCUMENT[s.start..s.end]));
+    }
+
+    #[test]
+    fn test_splits_oversized_paragraphs() {
+        let text = "One sentence here. Another sentence there. A third one follows.";
+        let config = ProcessingConfig { chunk_size: 25, ..Default::default() };
+        let spans = chunk(text, &config, &graphemes);
+        let texts: Vec<&str> = spans.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec!["One sentence here.", "Another sentence there.", "A third one follows."]);
+        assert_eq!(&text[spans[1].start..spans[1].end], "Another sentence there.");
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+
+use crate::extraction::{Position, Utf16Offsets};
+use crate::html::HtmlOptions;
+use crate::keywords::page_rank;
+use crate::semantic::{cosine, embed, sum, SparseVector};
+use crate::sentences::Segmenter;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SummaryAlgorithm {
+    /// PageRank over the sentence graph weighted by similarity.
+    #[default]
+    TextRank,
+    /// PageRank over the graph of sentences more similar than a threshold.
+    LexRank,
+    /// Similarity to the mean of all sentences.
+    Centroid,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LengthUnit {
+    #[default]
+    Words,
+    Tokens,
+}
+
+/// Summary options in the shape of the TS `SummaryOptions`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SummaryOptions {
+    pub algorithm: SummaryAlgorithm,
+    /// Target summary length in `unit`s.
+    pub max_length: usize,
+    pub unit: LengthUnit,
+    /// MMR trade-off between relevance (1.0) and novelty (0.0).
+    pub diversity: f64,
+    /// Sentences at least this similar to a chosen one are never added.
+    pub duplicate_threshold: f64,
+    /// LexRank edge threshold.
+    pub lexrank_threshold: f64,
+    /// ISO 639-1 code choosing the sentence boundary rules; detected when unset.
+    pub language: Option<String>,
+    /// Treat the input as HTML and summarize its text; positions then refer
+    /// to the converted text.
+    pub html: Option<HtmlOptions>,
+}
+
+impl Default for SummaryOptions {
+    fn default() -> Self {
+        SummaryOptions {
+            algorithm: SummaryAlgorithm::TextRank,
+            max_length: 100,
+            unit: LengthUnit::Words,
+            diversity: 0.7,
+            duplicate_threshold: 0.8,
+            lexrank_threshold: 0.1,
+            language: None,
+            html: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SummarySentence {
+    pub text: String,
+    /// Index of the sentence in the document.
+    pub index: usize,
+    pub score: f64,
+    pub position: Position,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Summary {
+    pub summary: String,
+    /// Chosen sentences in document order.
+    pub sentences: Vec<SummarySentence>,
+}
+
+/// Picks the best-scoring sentences of `text` that fit in `max_length`, as
+/// measured by `measure`, skipping near-duplicates of sentences already
+/// picked. The top sentence is kept even if it alone is too long.
+pub fn summarize(text: &str, options: &SummaryOptions, measure: &dyn Fn(&str) -> usize) -> Summary {
+    let spans: Vec<(usize, usize)> = Segmenter::for_text(text, options.language.as_deref())
+        .spans(text)
+        .into_iter()
+        .filter_map(|(start, end)| {
+            let sentence = &text[start..end];
+            let trimmed = sentence.trim_start();
+            let start = start + sentence.len() - trimmed.len();
+            let end = start + trimmed.trim_end().len();
+            (end > start).then_some((start, end))
+        })
+        .collect();
+    let vectors = embed(spans.iter().map(|&(start, end)| &text[start..end]));
+    let similarity: Vec<Vec<f64>> = vectors
+        .iter()
+        .map(|a| vectors.iter().map(|b| cosine(a, b)).collect())
+        .collect();
+
+    let scores = match options.algorithm {
+        SummaryAlgorithm::TextRank => rank(&similarity, |s| s),
+        SummaryAlgorithm::LexRank => rank(&similarity, |s| if s >= options.lexrank_threshold { 1.0 } else { 0.0 }),
+        SummaryAlgorithm::Centroid => centroid_scores(&vectors),
+    };
+
+    let chosen = select(&spans, &scores, &similarity, options, &|(start, end)| measure(&text[start..end]));
+    let mut utf16 = Utf16Offsets::new(text);
+    let sentences: Vec<SummarySentence> = chosen
+        .into_iter()
+        .map(|index| {
+            let (start, end) = spans[index];
+            SummarySentence {
+                text: text[start..end].to_string(),
+                index,
+                score: scores[index],
+                position: Position { start: utf16.at(start), end: utf16.at(end) },
+            }
+        })
+        .collect();
+
+    Summary {
+        summary: sentences.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" "),
+        sentences,
+    }
+}
+
+/// PageRank over the sentence graph, with edge weights from `weight(similarity)`.
+fn rank(similarity: &[Vec<f64>], weight: impl Fn(f64) -> f64) -> Vec<f64> {
+    let edges: Vec<HashMap<usize, f64>> = similarity
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .map(|(j, &s)| (j, weight(s)))
+                .filter(|&(_, w)| w > 0.0)
+                .collect()
+        })
+        .collect();
+    page_rank(&edges)
+}
+
+fn centroid_scores(vectors: &[SparseVector]) -> Vec<f64> {
+    let centroid = sum(vectors);
+    vectors.iter().map(|v| cosine(v, &centroid)).collect()
+}
+
+/// Maximal marginal relevance: repeatedly takes the sentence with the best
+/// trade-off between its score and its similarity to those already taken.
+/// Returns sentence indices in document order.
+fn select(
+    spans: &[(usize, usize)],
+    scores: &[f64],
+    similarity: &[Vec<f64>],
+    options: &SummaryOptions,
+    measure: &dyn Fn((usize, usize)) -> usize,
+) -> Vec<usize> {
+    let top = scores.iter().cloned().fold(0.0, f64::max);
+    let relevance: Vec<f64> = scores.iter().map(|&s| if top > 0.0 { s / top } else { 0.0 }).collect();
+    let mut remaining: Vec<usize> = (0..spans.len()).collect();
+    let mut chosen: Vec<usize> = Vec::new();
+    let mut length = 0;
+
+    while !remaining.is_empty() {
+        let redundancy = |i: usize| chosen.iter().map(|&c| similarity[i][c]).fold(0.0, f64::max);
+        let (slot, &best) = remaining
+            .iter()
+            .enumerate()
+            .max_by(|&(_, &a), &(_, &b)| {
+                let mmr = |i: usize| options.diversity * relevance[i] - (1.0 - options.diversity) * redundancy(i);
+                mmr(a).total_cmp(&mmr(b)).then_with(|| b.cmp(&a))
+            })
+            .unwrap();
+        remaining.remove(slot);
+
+        if redundancy(best) >= options.duplicate_threshold {
+            continue;
+        }
+        let size = measure(spans[best]);
+        if !chosen.is_empty() && length + size > options.max_length {
+            continue;
+        }
+        chosen.push(best);
+        length += size;
+        if length >= options.max_length {
+            break;
+        }
+    }
+    chosen.sort_unstable();
+    chosen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unicode_segmentation::UnicodeSegmentation;
+
+    const REPORT: &str = "Quarterly revenue grew twelve percent on strong cloud sales. \
+        Cloud sales drove most of the quarterly revenue growth. \
+        Cloud sales drove most of the quarterly revenue growth! \
+        The office cafeteria introduced a new menu. \
+        Operating costs fell as the company consolidated data centers. \
+        Revenue from cloud sales is expected to keep growing next quarter.";
+
+    fn words(s: &str) -> usize {
+        s.unicode_words().count()
+    }
+
+    #[test]
+    fn test_algorithms_prefer_central_sentences() {
+        for algorithm in [SummaryAlgorithm::TextRank, SummaryAlgorithm::LexRank, SummaryAlgorithm::Centroid] {
+            // A budget below any sentence still yields the top one
+            let options = SummaryOptions { algorithm, max_length: 5, ..Default::default() };
+            let summary = summarize(REPORT, &options, &words);
+            assert_eq!(summary.sentences.len(), 1);
+            assert!(summary.sentences[0].text.contains("loud sales"), "{:?}: {}", algorithm, summary.summary);
+
+            let options = SummaryOptions { algorithm, max_length: 25, ..Default::default() };
+            let summary = summarize(REPORT, &options, &words);
+            assert!(summary.sentences.iter().map(|s| words(&s.text)).sum::<usize>() <= 25);
+        }
+    }
+
+    #[test]
+    fn test_skips_duplicates_and_keeps_offsets() {
+        let options = SummaryOptions { max_length: 60, ..Default::default() };
+        let summary = summarize(REPORT, &options, &words);
+        let duplicates = summary.sentences.iter().filter(|s| s.text.starts_with("Cloud sales drove")).count();
+        assert_eq!(duplicates, 1);
+        assert!(summary.sentences.windows(2).all(|w| w[0].index < w[1].index));
+        for sentence in &summary.sentences {
+            let utf16: Vec<u16> = REPORT.encode_utf16().collect();
+            assert_eq!(String::from_utf16(&utf16[sentence.position.start..sentence.position.end]).unwrap(), sentence.text);
+        }
+        assert!(summary.summary.starts_with(&summary.sentences[0].text));
+    }
+
+    #[test]
+    fn test_empty_text() {
+        let summary = summarize("", &SummaryOptions::default(), &words);
+        assert!(summary.sentences.is_empty());
+        assert_eq!(summary.summary, "");
+    }
+}
@@ -0,0 +1,1260 @@
+use std::collections::HashMap;
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde::{Serialize, Deserialize};
+
+use crate::extraction::{alternation, sweep_overlaps, Position, Utf16Offsets, MONTHS, MONTH_NUMBERS};
+use crate::language;
+use crate::TextProcessingError;
+
+/// Weekday names by lowercase spelling, Monday first, for English, German,
+/// French and Spanish.
+const WEEKDAYS: &[(&str, u32)] = &[
+    ("monday", 0), ("montag", 0), ("lundi", 0), ("lunes", 0),
+    ("tuesday", 1), ("dienstag", 1), ("mardi", 1), ("martes", 1),
+    ("wednesday", 2), ("mittwoch", 2), ("mercredi", 2), ("miércoles", 2), ("miercoles", 2),
+    ("thursday", 3), ("donnerstag", 3), ("jeudi", 3), ("jueves", 3),
+    ("friday", 4), ("freitag", 4), ("vendredi", 4), ("viernes", 4),
+    ("saturday", 5), ("samstag", 5), ("sonnabend", 5), ("samedi", 5), ("sábado", 5), ("sabado", 5),
+    ("sunday", 6), ("sonntag", 6), ("dimanche", 6), ("domingo", 6),
+];
+
+/// Abbreviations only trusted in front of a date, as in "Tue, 4 March". The
+/// two-letter German ones are English words too ("do", "so") and need a "."
+/// or ",", as in "Do., 4. März".
+const WEEKDAY_ABBREVIATIONS: &[(&str, u32)] = &[
+    ("mon", 0), ("mo", 0), ("tue", 1), ("tues", 1), ("di", 1), ("wed", 2), ("mi", 2), ("thu", 3), ("thur", 3),
+    ("thurs", 3), ("do", 3), ("fri", 4), ("fr", 4), ("sat", 5), ("sa", 5), ("sun", 6), ("so", 6),
+];
+
+/// Day words by language with their offset from the reference date.
+const DAY_WORDS: &[(&str, &str, i64)] = &[
+    ("en", "today", 0), ("en", "tonight", 0), ("en", "tomorrow", 1), ("en", "yesterday", -1),
+    ("en", "the day after tomorrow", 2), ("en", "the day before yesterday", -2),
+    ("de", "heute", 0), ("de", "morgen", 1), ("de", "gestern", -1), ("de", "übermorgen", 2), ("de", "vorgestern", -2),
+    ("fr", "aujourd'hui", 0), ("fr", "aujourd’hui", 0), ("fr", "demain", 1), ("fr", "hier", -1),
+    ("fr", "après-demain", 2), ("fr", "avant-hier", -2),
+    ("es", "hoy", 0), ("es", "mañana", 1), ("es", "ayer", -1), ("es", "pasado mañana", 2), ("es", "anteayer", -2),
+    ("zh", "今天", 0), ("zh", "明天", 1), ("zh", "昨天", -1), ("zh", "后天", 2), ("zh", "前天", -2),
+    ("ja", "今日", 0), ("ja", "明日", 1), ("ja", "昨日", -1), ("ja", "明後日", 2), ("ja", "一昨日", -2),
+];
+
+/// Day words that also mean "morning", with the words that make them so
+/// when they come first, as in "heute Morgen" or "esta mañana".
+const MORNING_WORDS: &[(&str, &[&str])] = &[
+    ("morgen", &["heute", "gestern", "vorgestern", "morgen", "am", "den", "jeden", "einen", "guten", "diesen"]),
+    ("mañana", &["esta", "la", "una", "cada", "hoy", "ayer", "de", "media", "toda"]),
+];
+
+/// Words placing a weekday or period in the next (1), last (-1) or current (0) week.
+const DIRECTIONS: &[(&str, i64)] = &[
+    ("next", 1), ("coming", 1), ("nächste", 1), ("nächsten", 1), ("nächster", 1), ("nächstes", 1),
+    ("kommende", 1), ("kommenden", 1), ("kommendes", 1), ("prochain", 1), ("prochaine", 1),
+    ("próximo", 1), ("próxima", 1), ("que viene", 1),
+    ("last", -1), ("past", -1), ("previous", -1), ("letzte", -1), ("letzten", -1), ("letzter", -1),
+    ("letztes", -1), ("vergangene", -1), ("vergangenen", -1), ("vergangenes", -1), ("dernier", -1),
+    ("dernière", -1), ("passé", -1), ("passée", -1), ("pasado", -1), ("pasada", -1),
+    ("this", 0), ("diese", 0), ("diesen", 0), ("dieses", 0), ("este", 0), ("esta", 0),
+];
+
+/// Directions written after the weekday or period, as in "vendredi prochain".
+const TRAILING_DIRECTIONS: &[&str] = &[
+    "prochain", "prochaine", "dernier", "dernière", "passé", "passée", "próximo", "próxima", "pasado", "pasada",
+    "que viene",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Year,
+    Month,
+    Week,
+    Day,
+    Hour,
+    Minute,
+    Second,
+}
+
+const UNITS: &[(&str, Unit)] = &[
+    ("years", Unit::Year), ("year", Unit::Year), ("yrs", Unit::Year), ("yr", Unit::Year), ("jahre", Unit::Year),
+    ("jahren", Unit::Year), ("jahr", Unit::Year), ("années", Unit::Year), ("année", Unit::Year), ("ans", Unit::Year),
+    ("an", Unit::Year), ("años", Unit::Year), ("año", Unit::Year),
+    ("months", Unit::Month), ("month", Unit::Month), ("monate", Unit::Month), ("monaten", Unit::Month),
+    ("monat", Unit::Month), ("mois", Unit::Month), ("meses", Unit::Month), ("mes", Unit::Month),
+    ("weeks", Unit::Week), ("week", Unit::Week), ("wks", Unit::Week), ("wk", Unit::Week), ("wochen", Unit::Week),
+    ("woche", Unit::Week), ("semaines", Unit::Week), ("semaine", Unit::Week), ("semanas", Unit::Week),
+    ("semana", Unit::Week),
+    ("days", Unit::Day), ("day", Unit::Day), ("tage", Unit::Day), ("tagen", Unit::Day), ("tag", Unit::Day),
+    ("jours", Unit::Day), ("jour", Unit::Day), ("días", Unit::Day), ("día", Unit::Day),
+    ("hours", Unit::Hour), ("hour", Unit::Hour), ("hrs", Unit::Hour), ("hr", Unit::Hour), ("h", Unit::Hour),
+    ("stunden", Unit::Hour), ("stunde", Unit::Hour), ("heures", Unit::Hour), ("heure", Unit::Hour),
+    ("horas", Unit::Hour), ("hora", Unit::Hour),
+    ("minutes", Unit::Minute), ("minute", Unit::Minute), ("mins", Unit::Minute), ("min", Unit::Minute),
+    ("minuten", Unit::Minute), ("minutos", Unit::Minute), ("minuto", Unit::Minute),
+    ("seconds", Unit::Second), ("second", Unit::Second), ("secs", Unit::Second), ("sec", Unit::Second),
+    ("sekunden", Unit::Second), ("sekunde", Unit::Second), ("secondes", Unit::Second), ("seconde", Unit::Second),
+    ("segundos", Unit::Second), ("segundo", Unit::Second),
+];
+
+const NUMBER_WORDS: &[(&str, f64)] = &[
+    ("half an", 0.5), ("half a", 0.5), ("a", 1.0), ("an", 1.0), ("one", 1.0), ("two", 2.0), ("three", 3.0),
+    ("four", 4.0), ("five", 5.0), ("six", 6.0), ("seven", 7.0), ("eight", 8.0), ("nine", 9.0), ("ten", 10.0),
+    ("twelve", 12.0), ("fifteen", 15.0), ("twenty", 20.0), ("thirty", 30.0),
+    ("ein", 1.0), ("eine", 1.0), ("einem", 1.0), ("einer", 1.0), ("zwei", 2.0), ("drei", 3.0), ("vier", 4.0),
+    ("fünf", 5.0), ("sechs", 6.0), ("sieben", 7.0), ("acht", 8.0), ("neun", 9.0), ("zehn", 10.0),
+    ("un", 1.0), ("une", 1.0), ("deux", 2.0), ("trois", 3.0), ("quatre", 4.0), ("cinq", 5.0), ("sept", 7.0),
+    ("huit", 8.0), ("neuf", 9.0), ("dix", 10.0),
+    ("uno", 1.0), ("una", 1.0), ("dos", 2.0), ("tres", 3.0), ("cuatro", 4.0), ("cinco", 5.0), ("seis", 6.0),
+    ("siete", 7.0), ("ocho", 8.0), ("nueve", 9.0), ("diez", 10.0),
+];
+
+/// Zone abbreviations with their UTC offset in minutes, and whether other
+/// zones share the abbreviation.
+const ZONES: &[(&str, i32, bool)] = &[
+    ("UTC", 0, false), ("GMT", 0, false), ("WET", 0, false), ("WEST", 60, false), ("CET", 60, false),
+    ("CEST", 120, false), ("EET", 120, false), ("EEST", 180, false), ("MSK", 180, false), ("BST", 60, true),
+    ("IST", 330, true), ("JST", 540, false), ("KST", 540, false), ("AEST", 600, false), ("AEDT", 660, false),
+    ("NZST", 720, false), ("NZDT", 780, false), ("HST", -600, false), ("AKST", -540, false), ("AKDT", -480, false),
+    ("PST", -480, false), ("PDT", -420, false), ("MST", -420, false), ("MDT", -360, false), ("CST", -360, true),
+    ("CDT", -300, false), ("EST", -300, false), ("EDT", -240, false),
+];
+
+const ORDINAL: &str = r"(?:st|nd|rd|th|er|\.|º)?";
+
+lazy_static! {
+    static ref MONTH: String = alternation(MONTHS.iter().map(|m| m.0));
+    static ref WEEKDAY: String = alternation(WEEKDAYS.iter().map(|w| w.0));
+    static ref ANY_WEEKDAY: String = alternation(WEEKDAYS.iter().chain(WEEKDAY_ABBREVIATIONS).map(|w| w.0).filter(|w| w.len() > 2));
+    static ref SHORT_WEEKDAY: String = alternation(WEEKDAY_ABBREVIATIONS.iter().map(|w| w.0).filter(|w| w.len() <= 2));
+    static ref WEEKDAY_PREFIX: String = format!(r"(?:(?P<wd>{})\.?,?\s+|(?P<short_wd>{})(?:\.,?|,)\s+)?", *ANY_WEEKDAY, *SHORT_WEEKDAY);
+    static ref QUANTITY: String = format!(
+        r"(?:\d+(?:[.,]\d+)?\s*|(?:{})\s+)(?:{})\b",
+        alternation(NUMBER_WORDS.iter().map(|n| n.0)),
+        alternation(UNITS.iter().map(|u| u.0)),
+    );
+    static ref QUANTITY_PARTS: Regex = Regex::new(&format!(
+        r"(?i)(?:(?P<n>\d+(?:[.,]\d+)?)\s*|(?P<word>{})\s+)(?P<unit>{})\b",
+        alternation(NUMBER_WORDS.iter().map(|n| n.0)),
+        alternation(UNITS.iter().map(|u| u.0)),
+    )).unwrap();
+
+    static ref ISO_DATE_TIME: Regex = Regex::new(
+        r"\b(?P<y>\d{4})-(?P<m>\d{2})-(?P<d>\d{2})(?:T(?P<h>\d{2}):(?P<min>\d{2})(?::(?P<s>\d{2})(?:\.\d+)?)?(?P<zone>Z|[+-]\d{2}:?\d{2})?)?\b"
+    ).unwrap();
+    static ref YMD_DATE: Regex = Regex::new(r"\b(?P<y>\d{4})(?P<sep>[/.])(?P<m>\d{1,2})[/.](?P<d>\d{1,2})\b").unwrap();
+    static ref NUMERIC_DATE: Regex = Regex::new(
+        r"\b(?P<a>\d{1,2})(?P<sep>[/.-])(?P<b>\d{1,2})(?P<sep2>[/.-])(?P<y>\d{4}|\d{2})\b"
+    ).unwrap();
+    static ref DAY_MONTH_DATE: Regex = Regex::new(&format!(
+        r"(?i)\b{}(?P<d>\d{{1,2}}){ORDINAL}\s+(?:of\s+|de\s+)?(?P<month>{})\b\.?(?:,?\s+(?:de\s+)?(?P<y>\d{{4}})\b)?",
+        *WEEKDAY_PREFIX, *MONTH,
+    )).unwrap();
+    static ref MONTH_DAY_DATE: Regex = Regex::new(&format!(
+        r"(?i)\b{}(?P<month>{})\.?\s+(?P<d>\d{{1,2}}){ORDINAL}\b(?:,?\s+(?P<y>\d{{4}})\b)?",
+        *WEEKDAY_PREFIX, *MONTH,
+    )).unwrap();
+    static ref MONTH_YEAR: Regex = Regex::new(&format!(r"(?i)\b(?P<month>{})\.?,?\s+(?:de\s+)?(?P<y>\d{{4}})\b", *MONTH)).unwrap();
+    static ref CJK_DATE: Regex = Regex::new(
+        r"(?:(?P<y>\d{4})\s*[年년]\s*)?(?P<m>\d{1,2})\s*[月월]\s*(?P<d>\d{1,2})\s*[日일](?:\s*[(（](?P<wd>[月火水木金土日])[)）])?"
+    ).unwrap();
+    static ref DAY_RANGE: Regex = Regex::new(&format!(
+        r"(?i)\b(?:(?P<between>between|zwischen|entre)\s+)?(?P<d1>\d{{1,2}}){ORDINAL}\s*(?:-|–|—|to|bis|au|al|(?P<and>and|und|et|y))\s*(?P<d2>\d{{1,2}}){ORDINAL}\s+(?:of\s+|de\s+)?(?P<month>{})\b\.?(?:,?\s+(?:de\s+)?(?P<y>\d{{4}})\b)?",
+        *MONTH,
+    )).unwrap();
+    static ref MONTH_DAY_RANGE: Regex = Regex::new(&format!(
+        r"(?i)\b(?P<month>{})\.?\s+(?P<d1>\d{{1,2}}){ORDINAL}\s*(?:-|–|—|to|through)\s*(?P<d2>\d{{1,2}}){ORDINAL}\b(?:,?\s+(?P<y>\d{{4}})\b)?",
+        *MONTH,
+    )).unwrap();
+
+    static ref DAY_WORD: Regex = Regex::new(&format!(
+        r"(?i)\b(?:{})\b|{}",
+        alternation(DAY_WORDS.iter().filter(|w| !is_cjk(w.0)).map(|w| w.1)).replace(' ', r"\s+"),
+        alternation(DAY_WORDS.iter().filter(|w| is_cjk(w.0)).map(|w| w.1)),
+    )).unwrap();
+    static ref RELATIVE_WEEKDAY: Regex = Regex::new(&format!(
+        r"(?i)\b(?:(?P<dir>{})\s+)?(?P<wd>{})\b(?:\s+(?P<after>{})\b)?",
+        alternation(DIRECTIONS.iter().map(|d| d.0).filter(|d| !d.contains(' '))),
+        *WEEKDAY,
+        alternation(TRAILING_DIRECTIONS.iter().copied()).replace(' ', r"\s+"),
+    )).unwrap();
+    static ref RELATIVE_PERIOD: Regex = Regex::new(&format!(
+        r"(?i)\b(?:(?P<dir>{dir})\s+(?P<period>{period})|(?P<period2>{period})\s+(?P<after>{after}))\b",
+        dir = alternation(DIRECTIONS.iter().map(|d| d.0).filter(|d| !d.contains(' '))),
+        period = "week|month|year|woche|monat|jahr|semaine|mois|année|semana|mes|año",
+        after = alternation(TRAILING_DIRECTIONS.iter().copied()).replace(' ', r"\s+"),
+    )).unwrap();
+    static ref AFTER: Regex = Regex::new(&format!(
+        r"(?i)\b(?:(?:in|within|dans|en|dentro\s+de)\s+(?P<q>{q})|(?P<q2>{q})\s+(?:from\s+now|later|hence)\b)",
+        q = *QUANTITY,
+    )).unwrap();
+    static ref BEFORE: Regex = Regex::new(&format!(
+        r"(?i)\b(?:(?:vor|il\s+y\s+a|hace)\s+(?P<q>{q})|(?P<q2>{q})\s+(?:ago|earlier)\b)",
+        q = *QUANTITY,
+    )).unwrap();
+    static ref DURATION: Regex = Regex::new(&format!(
+        r"(?i)\b{q}(?:\s*,?\s*(?:(?:and|und|et|y)\s+)?{q})*",
+        q = *QUANTITY,
+    )).unwrap();
+
+    static ref CLOCK: Regex = Regex::new(
+        r"(?i)\b(?P<h>[01]?\d|2[0-3])(?::|h)(?P<min>[0-5]\d)(?::(?P<s>[0-5]\d))?(?:\s?(?P<ampm>[ap]\.\s?m\.|[ap]\s?m\b))?(?:\s?uhr\b)?"
+    ).unwrap();
+    static ref HOUR_MERIDIEM: Regex = Regex::new(r"(?i)\b(?P<h>1[0-2]|0?[1-9])\s?(?P<ampm>[ap]\.\s?m\.|[ap]\s?m\b)").unwrap();
+    static ref HOUR_WORD: Regex = Regex::new(r"(?i)\b(?P<h>[01]?\d|2[0-3])\s?(?P<word>uhr|o['’]?clock)\b").unwrap();
+    static ref AT_HOUR: Regex = Regex::new(r"(?i)\b(?:at|@)\s+(?P<h>1[0-2]|0?[1-9])\b").unwrap();
+    static ref AT_HOUR_FOLLOWER: Regex = Regex::new(r"(?i)^(?:\s*$|\s*[,.;:!?)]|\s+(?:on|tomorrow|today|tonight|next|this)\b)").unwrap();
+    static ref NAMED_TIME: Regex = Regex::new(r"(?i)\b(?:noon|midday|midnight|mittags?|mitternacht|midi|minuit|mediodía|medianoche)\b").unwrap();
+    static ref ZONE: Regex = Regex::new(&format!(
+        r"^(?:\s?(?P<abbr>{})(?P<shift>\s?[+-]\d{{1,2}}(?::?\d{{2}})?)?|(?P<offset>\s?[+-]\d{{2}}:?\d{{2}})|(?P<z>Z))\b",
+        alternation(ZONES.iter().map(|z| z.0)),
+    )).unwrap();
+
+    static ref DATE_TIME_GAP: Regex = Regex::new(r"(?i)^\s*,?\s*(?:(?:at|um|à|a\s+las?|@)\s+)?$").unwrap();
+    static ref TIME_DATE_GAP: Regex = Regex::new(r"(?i)^\s*,?\s*(?:(?:on|am|le|el)\s+)?$").unwrap();
+    static ref RANGE_GAP: Regex = Regex::new(
+        r"(?i)^\s*(?:-|–|—|~|〜|～|to|until|till|til|through|thru|bis|au|jusqu['’]au|à|a|al|hasta|(?P<and>and|und|et|y))\s*$"
+    ).unwrap();
+    static ref RANGE_PREFIX: Regex = Regex::new(r"(?i)\b(?:from|von|vom|du|de|del|desde|(?P<between>between|zwischen|entre))\s+$").unwrap();
+    static ref REFERENCE: Regex = Regex::new(r"^(\d{4})-(\d{2})-(\d{2})(?:[T ](\d{2}):(\d{2})(?::(\d{2}))?)?").unwrap();
+
+    static ref WEEKDAY_NUMBERS: HashMap<&'static str, u32> = WEEKDAYS.iter().chain(WEEKDAY_ABBREVIATIONS).copied().collect();
+    static ref UNIT_NAMES: HashMap<&'static str, Unit> = UNITS.iter().copied().collect();
+    static ref NUMBER_NAMES: HashMap<&'static str, f64> = NUMBER_WORDS.iter().copied().collect();
+}
+
+/// Options in the shape of the TS `TemporalOptions`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TemporalOptions {
+    /// `YYYY-MM-DD` or `YYYY-MM-DDTHH:MM[:SS]` that relative expressions are
+    /// resolved against; the current UTC time when unset.
+    pub reference: Option<String>,
+    /// Read ambiguous numeric dates such as `03/04/2025` as day first.
+    pub day_first: bool,
+    /// Resolve dates without a year and bare weekdays to their next
+    /// occurrence rather than the latest one.
+    pub prefer_future: bool,
+    /// ISO 639-1 code choosing the relative day words; detected when unset.
+    pub language: Option<String>,
+}
+
+impl Default for TemporalOptions {
+    fn default() -> Self {
+        TemporalOptions { reference: None, day_first: false, prefer_future: true, language: None }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TemporalKind {
+    /// A day, or a month, ISO week or year for coarser expressions.
+    Date,
+    Time,
+    DateTime,
+    Range,
+    Duration,
+}
+
+/// Why a value may not be what the writer meant; `alternative` holds the
+/// other reading where there is one.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Ambiguity {
+    /// A numeric date that reads both day first and month first.
+    DayMonthOrder,
+    /// A two-digit year, placed in the century nearest the reference.
+    TwoDigitYear,
+    /// A date without a year, which came from the reference.
+    MissingYear,
+    /// An hour without am or pm.
+    Meridiem,
+    /// "next Friday" said early in the week, or a bare weekday naming today.
+    RelativeWeekday,
+    /// A zone abbreviation shared by several zones, such as CST.
+    TimeZone,
+    /// The weekday written next to the date is not the date's.
+    WeekdayMismatch,
+}
+
+/// The TS `TemporalExpression`: `value` is ISO 8601, an interval
+/// `start/end` for ranges, and positions are UTF-16 offsets.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TemporalExpression {
+    pub kind: TemporalKind,
+    pub text: String,
+    pub position: Position,
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
+    /// Resolved against the reference date.
+    pub relative: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ambiguities: Vec<Ambiguity>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alternative: Option<String>,
+}
+
+/// Finds dates, times, ranges and durations in `text`, in text order. `now`,
+/// in milliseconds since the epoch, stands in for an unset reference.
+pub fn extract(text: &str, options: &TemporalOptions, now: f64) -> Result<Vec<TemporalExpression>, TextProcessingError> {
+    let reference = match &options.reference {
+        Some(reference) => Moment::parse(reference)
+            .ok_or_else(|| TextProcessingError::InvalidOptions(format!("reference is not an ISO 8601 date: {reference}")))?,
+        None => Moment::from_millis(now),
+    };
+    let detected;
+    let language = match options.language.as_deref() {
+        Some(language) => language,
+        None => {
+            detected = language::detect(text).map(|d| d.language);
+            detected.as_deref().unwrap_or("en")
+        }
+    };
+    let context = Context {
+        reference,
+        day_first: options.day_first,
+        prefer_future: options.prefer_future,
+        language: language.split(['-', '_']).next().unwrap_or_default().to_lowercase(),
+    };
+
+    let mut candidates = Vec::new();
+    context.absolute_dates(text, &mut candidates);
+    context.relative_dates(text, &mut candidates);
+    context.times(text, &mut candidates);
+    durations(text, &mut candidates);
+    let candidates = resolve_overlaps(candidates);
+    let candidates = ranges(text, merge(text, ranges(text, candidates)));
+
+    let mut utf16 = Utf16Offsets::new(text);
+    Ok(candidates
+        .into_iter()
+        .map(|c| {
+            let (start, end) = match &c.value {
+                Value::Range(from, to) => (Some(from.iso()), Some(to.iso())),
+                _ => (None, None),
+            };
+            TemporalExpression {
+                kind: c.value.kind(),
+                text: text[c.start..c.end].to_string(),
+                position: Position { start: utf16.at(c.start), end: utf16.at(c.end) },
+                value: c.value.iso(),
+                start,
+                end,
+                relative: c.relative,
+                ambiguities: c.ambiguities,
+                alternative: c.alternative.map(|v| v.iso()),
+            }
+        })
+        .collect())
+}
+
+/// A proleptic Gregorian date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Date {
+    year: i32,
+    month: u32,
+    day: u32,
+}
+
+impl Date {
+    fn new(year: i32, month: u32, day: u32) -> Option<Date> {
+        ((1..=9999).contains(&year) && (1..=12).contains(&month) && (1..=days_in_month(year, month)).contains(&day))
+            .then_some(Date { year, month, day })
+    }
+
+    /// The date `days` after 1970-01-01.
+    fn from_days(days: i64) -> Date {
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        Date {
+            year: (yoe + era * 400 + i64::from(month <= 2)) as i32,
+            month: month as u32,
+            day: (doy - (153 * mp + 2) / 5 + 1) as u32,
+        }
+    }
+
+    /// Days since 1970-01-01.
+    fn days(self) -> i64 {
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let month = i64::from(self.month);
+        let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(self.day) - 1;
+        era * 146_097 + yoe * 365 + yoe / 4 - yoe / 100 + doy - 719_468
+    }
+
+    /// 0 for Monday through 6 for Sunday.
+    fn weekday(self) -> u32 {
+        (self.days() + 3).rem_euclid(7) as u32
+    }
+
+    /// `None` past the years 1 to 9999 that ISO 8601 dates are written in.
+    fn add_days(self, days: i64) -> Option<Date> {
+        let days = self.days().checked_add(days)?;
+        let range = Date { year: 1, month: 1, day: 1 }.days()..=Date { year: 9999, month: 12, day: 31 }.days();
+        range.contains(&days).then(|| Date::from_days(days))
+    }
+
+    /// Moves by whole months, keeping the day where the month has it.
+    fn add_months(self, months: i64) -> Option<Date> {
+        let index = (i64::from(self.year) * 12 + i64::from(self.month) - 1).checked_add(months)?;
+        let (year, month) = (i32::try_from(index.div_euclid(12)).ok()?, index.rem_euclid(12) as u32 + 1);
+        Date::new(year, month, self.day.min(days_in_month(year, month)))
+    }
+
+    /// ISO 8601 week-numbering year and week.
+    fn iso_week(self) -> (i32, u32) {
+        let thursday = Date::from_days(self.days() + 3 - i64::from(self.weekday()));
+        let ordinal = thursday.days() - Date { year: thursday.year, month: 1, day: 1 }.days();
+        (thursday.year, (ordinal / 7 + 1) as u32)
+    }
+
+    fn iso(self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Time {
+    hour: u32,
+    minute: u32,
+    second: u32,
+    offset: Option<i32>, // minutes east of UTC
+}
+
+impl Time {
+    fn iso(self) -> String {
+        let mut iso = format!("{:02}:{:02}:{:02}", self.hour, self.minute, self.second);
+        match self.offset {
+            Some(0) => iso.push('Z'),
+            Some(offset) => {
+                let sign = if offset < 0 { '-' } else { '+' };
+                iso.push_str(&format!("{}{:02}:{:02}", sign, offset.abs() / 60, offset.abs() % 60));
+            }
+            None => {}
+        }
+        iso
+    }
+}
+
+/// The reference instant, without a zone.
+#[derive(Debug, Clone, Copy)]
+struct Moment {
+    date: Date,
+    seconds: i64, // since midnight
+}
+
+impl Moment {
+    fn parse(text: &str) -> Option<Moment> {
+        let caps = REFERENCE.captures(text.trim())?;
+        let part = |i: usize| caps.get(i).map_or(Some(0), |m| m.as_str().parse::<u32>().ok());
+        let date = Date::new(part(1)? as i32, part(2)?, part(3)?)?;
+        let (hour, minute, second) = (part(4)?, part(5)?, part(6)?);
+        (hour < 24 && minute < 60 && second < 60)
+            .then(|| Moment { date, seconds: i64::from(hour * 3600 + minute * 60 + second) })
+    }
+
+    fn from_millis(millis: f64) -> Moment {
+        let seconds = (millis / 1000.0).floor() as i64;
+        Moment { date: Date::from_days(seconds.div_euclid(86_400)), seconds: seconds.rem_euclid(86_400) }
+    }
+
+    fn add_seconds(self, seconds: i64) -> Option<(Date, Time)> {
+        let total = self.seconds.checked_add(seconds)?;
+        let of_day = total.rem_euclid(86_400) as u32;
+        let time = Time { hour: of_day / 3600, minute: of_day / 60 % 60, second: of_day % 60, offset: None };
+        Some((self.date.add_days(total.div_euclid(86_400))?, time))
+    }
+}
+
+/// Amounts per unit, in `Unit` order.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+struct Duration([f64; 7]);
+
+impl Duration {
+    fn iso(&self) -> String {
+        let mut parts = self.0;
+        // Weeks only stand alone in ISO 8601
+        if parts[2] != 0.0 && parts.iter().enumerate().any(|(i, &n)| i != 2 && n != 0.0) {
+            parts[3] += parts[2] * 7.0;
+            parts[2] = 0.0;
+        }
+        let mut iso = String::from("P");
+        for (n, designator) in parts[..4].iter().zip(['Y', 'M', 'W', 'D']) {
+            if *n != 0.0 {
+                iso.push_str(&format!("{n}{designator}"));
+            }
+        }
+        if parts[4..].iter().any(|&n| n != 0.0) {
+            iso.push('T');
+            for (n, designator) in parts[4..].iter().zip(['H', 'M', 'S']) {
+                if *n != 0.0 {
+                    iso.push_str(&format!("{n}{designator}"));
+                }
+            }
+        }
+        iso
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Date(Date),
+    Month(i32, u32),
+    Week(i32, u32),
+    Year(i32),
+    Time(Time),
+    DateTime(Date, Time),
+    Duration(Duration),
+    Range(Box<Value>, Box<Value>),
+}
+
+impl Value {
+    fn kind(&self) -> TemporalKind {
+        match self {
+            Value::Date(_) | Value::Month(..) | Value::Week(..) | Value::Year(_) => TemporalKind::Date,
+            Value::Time(_) => TemporalKind::Time,
+            Value::DateTime(..) => TemporalKind::DateTime,
+            Value::Duration(_) => TemporalKind::Duration,
+            Value::Range(..) => TemporalKind::Range,
+        }
+    }
+
+    fn iso(&self) -> String {
+        match self {
+            Value::Date(date) => date.iso(),
+            Value::Month(year, month) => format!("{year:04}-{month:02}"),
+            Value::Week(year, week) => format!("{year:04}-W{week:02}"),
+            Value::Year(year) => format!("{year:04}"),
+            Value::Time(time) => time.iso(),
+            Value::DateTime(date, time) => format!("{}T{}", date.iso(), time.iso()),
+            Value::Duration(duration) => duration.iso(),
+            Value::Range(from, to) => format!("{}/{}", from.iso(), to.iso()),
+        }
+    }
+
+    /// Puts a time, or both ends of a time range, on `date`.
+    fn on_date(&self, date: Date) -> Option<Value> {
+        match self {
+            Value::Time(time) => Some(Value::DateTime(date, *time)),
+            Value::Range(from, to) => Some(Value::Range(Box::new(from.on_date(date)?), Box::new(to.on_date(date)?))),
+            _ => None,
+        }
+    }
+
+    /// The range from `self` to `to`, if the ends are alike; an end time
+    /// alone falls on the start's date.
+    fn until(&self, to: &Value) -> Option<Value> {
+        let is_date = |v: &Value| matches!(v, Value::Date(_) | Value::Month(..) | Value::Week(..) | Value::Year(_));
+        let to = match (self, to) {
+            (Value::DateTime(date, _), Value::Time(time)) => Value::DateTime(*date, *time),
+            (Value::Time(_), Value::Time(_)) | (Value::DateTime(..), Value::DateTime(..)) => to.clone(),
+            (from, to) if is_date(from) && is_date(to) => to.clone(),
+            _ => return None,
+        };
+        Some(Value::Range(Box::new(self.clone()), Box::new(to)))
+    }
+}
+
+/// A recognized expression in byte offsets.
+#[derive(Debug, Clone)]
+struct Candidate {
+    start: usize,
+    end: usize,
+    value: Value,
+    alternative: Option<Value>,
+    relative: bool,
+    ambiguities: Vec<Ambiguity>,
+    rank: usize, // breaks ties between equally long matches; lower wins
+}
+
+impl Candidate {
+    fn new(start: usize, end: usize, value: Value, rank: usize) -> Self {
+        Candidate { start, end, value, alternative: None, relative: false, ambiguities: Vec::new(), rank }
+    }
+
+    fn relative(mut self) -> Self {
+        self.relative = true;
+        self
+    }
+
+    fn ambiguous(mut self, ambiguity: Ambiguity, alternative: Option<Value>) -> Self {
+        self.ambiguities.push(ambiguity);
+        if alternative.is_some() {
+            self.alternative = alternative;
+        }
+        self
+    }
+
+    /// `self` and `other` as one expression over both spans, worth `value`;
+    /// the alternative combines whichever parts have one.
+    fn join(self, other: Candidate, combine: impl Fn(&Value, &Value) -> Option<Value>) -> Option<Candidate> {
+        let value = combine(&self.value, &other.value)?;
+        let alternative = match (&self.alternative, &other.alternative) {
+            (None, None) => None,
+            (a, b) => combine(a.as_ref().unwrap_or(&self.value), b.as_ref().unwrap_or(&other.value)),
+        };
+        let mut ambiguities = self.ambiguities;
+        ambiguities.extend(other.ambiguities.into_iter().filter(|a| !ambiguities.contains(a)).collect::<Vec<_>>());
+        Some(Candidate {
+            start: self.start,
+            end: other.end,
+            value,
+            alternative,
+            relative: self.relative || other.relative,
+            ambiguities,
+            rank: self.rank.min(other.rank),
+        })
+    }
+}
+
+struct Context {
+    reference: Moment,
+    day_first: bool,
+    prefer_future: bool,
+    language: String,
+}
+
+impl Context {
+    /// `month`/`day` in `year`, or in the reference year (the next one when
+    /// that date has passed and the future is preferred) when missing.
+    fn date(&self, year: Option<i32>, month: u32, day: u32) -> Option<(Date, bool)> {
+        if let Some(year) = year {
+            return Date::new(year, month, day).map(|date| (date, false));
+        }
+        let reference = self.reference.date;
+        let date = Date::new(reference.year, month, day)?;
+        if self.prefer_future && date < reference {
+            return Date::new(reference.year + 1, month, day).map(|date| (date, true));
+        }
+        Some((date, true))
+    }
+
+    /// The year of a two-digit year nearest the reference's.
+    fn century(&self, year: i32) -> i32 {
+        let reference = self.reference.date.year;
+        let mut full = reference - reference % 100 + year;
+        if full > reference + 50 {
+            full -= 100;
+        } else if full <= reference - 50 {
+            full += 100;
+        }
+        full
+    }
+
+    fn absolute_dates(&self, text: &str, out: &mut Vec<Candidate>) {
+        for caps in ISO_DATE_TIME.captures_iter(text) {
+            let whole = caps.get(0).unwrap();
+            let (Some(y), Some(m), Some(d)) = (number(&caps, "y"), number(&caps, "m"), number(&caps, "d")) else {
+                continue;
+            };
+            let Some(date) = Date::new(y as i32, m, d) else {
+                continue;
+            };
+            let value = match (number(&caps, "h"), number(&caps, "min")) {
+                (Some(hour), Some(minute)) if hour < 24 && minute < 60 => {
+                    let offset = caps.name("zone").map(|z| parse_offset(z.as_str()));
+                    let second = number(&caps, "s").unwrap_or(0).min(59);
+                    Value::DateTime(date, Time { hour, minute, second, offset: offset.flatten() })
+                }
+                (Some(_), _) => continue,
+                _ => Value::Date(date),
+            };
+            out.push(Candidate::new(whole.start(), whole.end(), value, 0));
+        }
+
+        for caps in YMD_DATE.captures_iter(text) {
+            let whole = caps.get(0).unwrap();
+            if whole.as_str().matches(&caps["sep"]).count() != 2 {
+                continue;
+            }
+            if let (Some(y), Some(m), Some(d)) = (number(&caps, "y"), number(&caps, "m"), number(&caps, "d")) {
+                if let Some(date) = Date::new(y as i32, m, d) {
+                    out.push(Candidate::new(whole.start(), whole.end(), Value::Date(date), 1));
+                }
+            }
+        }
+
+        for caps in NUMERIC_DATE.captures_iter(text) {
+            let whole = caps.get(0).unwrap();
+            let (Some(a), Some(b), Some(y)) = (number(&caps, "a"), number(&caps, "b"), number(&caps, "y")) else {
+                continue;
+            };
+            if caps["sep"] != caps["sep2"] {
+                continue;
+            }
+            let two_digit = caps["y"].len() == 2;
+            let year = if two_digit { self.century(y as i32) } else { y as i32 };
+            // Dotted dates are day first across Europe
+            let day_first = a > 12 || (b <= 12 && (self.day_first || &caps["sep"] == "."));
+            let (day, month) = if day_first { (a, b) } else { (b, a) };
+            let Some(date) = Date::new(year, month, day) else {
+                continue;
+            };
+            let mut candidate = Candidate::new(whole.start(), whole.end(), Value::Date(date), 2);
+            if a <= 12 && b <= 12 && a != b {
+                let swapped = Date::new(year, day, month).map(Value::Date);
+                candidate = candidate.ambiguous(Ambiguity::DayMonthOrder, swapped);
+            }
+            if two_digit {
+                candidate = candidate.ambiguous(Ambiguity::TwoDigitYear, None);
+            }
+            out.push(candidate);
+        }
+
+        for (rank, regex) in [&*DAY_MONTH_DATE, &*MONTH_DAY_DATE].into_iter().enumerate() {
+            for caps in regex.captures_iter(text) {
+                let whole = caps.get(0).unwrap();
+                let (Some(month), Some(day)) = (month_number(&caps["month"]), number(&caps, "d")) else {
+                    continue;
+                };
+                let year = number(&caps, "y").map(|y| y as i32);
+                // "may 5" or "mar 3" without a year are likelier words than dates
+                if year.is_none() && caps["month"].len() <= 3 && caps["month"].chars().all(|c| c.is_lowercase()) {
+                    continue;
+                }
+                let Some((date, inferred)) = self.date(year, month, day) else {
+                    continue;
+                };
+                let mut candidate = Candidate::new(whole.start(), whole.end(), Value::Date(date), 3 + rank);
+                if inferred {
+                    candidate = candidate.ambiguous(Ambiguity::MissingYear, None);
+                }
+                let weekday = caps.name("wd").or(caps.name("short_wd")).and_then(|w| WEEKDAY_NUMBERS.get(w.as_str().to_lowercase().as_str()));
+                if weekday.is_some_and(|&w| w != date.weekday()) {
+                    candidate = candidate.ambiguous(Ambiguity::WeekdayMismatch, None);
+                }
+                out.push(candidate);
+            }
+        }
+
+        for caps in MONTH_YEAR.captures_iter(text) {
+            let whole = caps.get(0).unwrap();
+            if let (Some(month), Some(year)) = (month_number(&caps["month"]), number(&caps, "y")) {
+                out.push(Candidate::new(whole.start(), whole.end(), Value::Month(year as i32, month), 5));
+            }
+        }
+
+        for caps in CJK_DATE.captures_iter(text) {
+            let whole = caps.get(0).unwrap();
+            let (Some(month), Some(day)) = (number(&caps, "m"), number(&caps, "d")) else {
+                continue;
+            };
+            let Some((date, inferred)) = self.date(number(&caps, "y").map(|y| y as i32), month, day) else {
+                continue;
+            };
+            let mut candidate = Candidate::new(whole.start(), whole.end(), Value::Date(date), 6);
+            if inferred {
+                candidate = candidate.ambiguous(Ambiguity::MissingYear, None);
+            }
+            let weekday = caps.name("wd").and_then(|w| "月火水木金土日".chars().position(|c| w.as_str().starts_with(c)));
+            if weekday.is_some_and(|w| w as u32 != date.weekday()) {
+                candidate = candidate.ambiguous(Ambiguity::WeekdayMismatch, None);
+            }
+            out.push(candidate);
+        }
+
+        for (rank, regex) in [&*DAY_RANGE, &*MONTH_DAY_RANGE].into_iter().enumerate() {
+            for caps in regex.captures_iter(text) {
+                let whole = caps.get(0).unwrap();
+                let (Some(month), Some(d1), Some(d2)) = (month_number(&caps["month"]), number(&caps, "d1"), number(&caps, "d2")) else {
+                    continue;
+                };
+                if caps.name("and").is_some() != caps.name("between").is_some() {
+                    continue;
+                }
+                let year = number(&caps, "y").map(|y| y as i32);
+                let (Some((from, inferred)), Some((to, _))) = (self.date(year, month, d1), self.date(year, month, d2)) else {
+                    continue;
+                };
+                let value = Value::Range(Box::new(Value::Date(from)), Box::new(Value::Date(to)));
+                let mut candidate = Candidate::new(whole.start(), whole.end(), value, 7 + rank);
+                if inferred {
+                    candidate = candidate.ambiguous(Ambiguity::MissingYear, None);
+                }
+                out.push(candidate);
+            }
+        }
+    }
+
+    fn relative_dates(&self, text: &str, out: &mut Vec<Candidate>) {
+        let today = self.reference.date;
+        let known = DAY_WORDS.iter().any(|w| w.0 == self.language);
+        for found in DAY_WORD.find_iter(text) {
+            let phrase = found.as_str().to_lowercase().split_whitespace().collect::<Vec<_>>().join(" ");
+            if means_morning(text, found.start(), found.as_str(), &phrase) {
+                continue;
+            }
+            let word = DAY_WORDS.iter().find(|&&(language, word, _)| {
+                word == phrase && (language == self.language || is_cjk(language) || (!known && language == "en"))
+            });
+            if let Some(date) = word.and_then(|&(_, _, offset)| today.add_days(offset)) {
+                out.push(Candidate::new(found.start(), found.end(), Value::Date(date), 10).relative());
+            }
+        }
+
+        for caps in RELATIVE_WEEKDAY.captures_iter(text) {
+            let whole = caps.get(0).unwrap();
+            let Some(&weekday) = WEEKDAY_NUMBERS.get(caps["wd"].to_lowercase().as_str()) else {
+                continue;
+            };
+            let direction = caps.name("dir").or(caps.name("after")).and_then(|d| direction(d.as_str()));
+            let current = today.weekday();
+            let ahead = i64::from((weekday + 7 - current) % 7);
+            let behind = i64::from((current + 7 - weekday) % 7);
+            // The reading, and the other one when the phrase leaves it open
+            let (days, other) = match direction {
+                Some(0) => (i64::from(weekday) - i64::from(current), None),
+                Some(1) => {
+                    let days = if ahead == 0 { 7 } else { ahead };
+                    (days, (weekday > current).then_some(days + 7))
+                }
+                Some(_) => {
+                    let days = -(if behind == 0 { 7 } else { behind });
+                    (days, (weekday < current).then_some(days - 7))
+                }
+                None => {
+                    let (days, other) = if self.prefer_future { (ahead, 7) } else { (-behind, -7) };
+                    (days, (days == 0).then_some(other))
+                }
+            };
+            let Some(date) = today.add_days(days) else {
+                continue;
+            };
+            let mut candidate = Candidate::new(whole.start(), whole.end(), Value::Date(date), 11).relative();
+            if let Some(other) = other {
+                candidate = candidate.ambiguous(Ambiguity::RelativeWeekday, today.add_days(other).map(Value::Date));
+            }
+            out.push(candidate);
+        }
+
+        for caps in RELATIVE_PERIOD.captures_iter(text) {
+            let whole = caps.get(0).unwrap();
+            let period = caps.name("period").or(caps.name("period2")).map_or("", |m| m.as_str()).to_lowercase();
+            let Some(n) = caps.name("dir").or(caps.name("after")).and_then(|d| direction(d.as_str())) else {
+                continue;
+            };
+            let value = match period.as_str() {
+                "week" | "woche" | "semaine" | "semana" => today.add_days(7 * n).map(|date| {
+                    let (year, week) = date.iso_week();
+                    Value::Week(year, week)
+                }),
+                "month" | "monat" | "mois" | "mes" => today.add_months(n).map(|date| Value::Month(date.year, date.month)),
+                _ => today.add_months(12 * n).map(|date| Value::Year(date.year)),
+            };
+            let Some(value) = value else {
+                continue;
+            };
+            out.push(Candidate::new(whole.start(), whole.end(), value, 12).relative());
+        }
+
+        for (sign, regex) in [(1.0, &*AFTER), (-1.0, &*BEFORE)] {
+            for caps in regex.captures_iter(text) {
+                let whole = caps.get(0).unwrap();
+                let Some(duration) = caps.name("q").or(caps.name("q2")).and_then(|q| quantities(q.as_str())) else {
+                    continue;
+                };
+                let [years, months, weeks, days, hours, minutes, seconds] = duration.0.map(|n| n * sign);
+                // Float to integer casts saturate; the checked arithmetic
+                // drops whatever lands outside the years 1 to 9999
+                let date = today
+                    .add_months((years * 12.0 + months).round() as i64)
+                    .and_then(|date| date.add_days((weeks * 7.0 + days).round() as i64));
+                let Some(date) = date else {
+                    continue;
+                };
+                let value = if hours != 0.0 || minutes != 0.0 || seconds != 0.0 {
+                    let moment = Moment { date, ..self.reference };
+                    let Some((date, time)) = moment.add_seconds((hours * 3600.0 + minutes * 60.0 + seconds).round() as i64)
+                    else {
+                        continue;
+                    };
+                    Value::DateTime(date, time)
+                } else {
+                    Value::Date(date)
+                };
+                out.push(Candidate::new(whole.start(), whole.end(), value, 13).relative());
+            }
+        }
+    }
+
+    fn times(&self, text: &str, out: &mut Vec<Candidate>) {
+        // `open` marks hours that may be either side of noon, read as written
+        let mut push = |start: usize, end: usize, hour: u32, minute: u32, second: u32, open: bool, rank: usize| {
+            let (offset, end, shared) = zone_after(text, end);
+            let time = |hour| Value::Time(Time { hour, minute, second, offset });
+            let mut candidate = Candidate::new(start, end, time(hour), rank);
+            if open && (1..12).contains(&hour) {
+                candidate = candidate.ambiguous(Ambiguity::Meridiem, Some(time(hour + 12)));
+            }
+            if shared {
+                candidate = candidate.ambiguous(Ambiguity::TimeZone, None);
+            }
+            out.push(candidate);
+        };
+
+        for caps in CLOCK.captures_iter(text) {
+            let whole = caps.get(0).unwrap();
+            let (Some(hour), Some(minute)) = (number(&caps, "h"), number(&caps, "min")) else {
+                continue;
+            };
+            let Some(hour) = with_meridiem(hour, caps.name("ampm").map(|m| m.as_str())) else {
+                continue;
+            };
+            push(whole.start(), whole.end(), hour, minute, number(&caps, "s").unwrap_or(0), false, 20);
+        }
+        for caps in HOUR_MERIDIEM.captures_iter(text) {
+            let whole = caps.get(0).unwrap();
+            if let Some(hour) = number(&caps, "h").and_then(|h| with_meridiem(h, Some(&caps["ampm"]))) {
+                push(whole.start(), whole.end(), hour, 0, 0, false, 21);
+            }
+        }
+        for found in NAMED_TIME.find_iter(text) {
+            let midnight = ["midnight", "mitternacht", "minuit", "medianoche"].contains(&found.as_str().to_lowercase().as_str());
+            push(found.start(), found.end(), if midnight { 0 } else { 12 }, 0, 0, false, 22);
+        }
+        for caps in HOUR_WORD.captures_iter(text) {
+            let whole = caps.get(0).unwrap();
+            let Some(hour) = number(&caps, "h") else {
+                continue;
+            };
+            // "uhr" is on the 24-hour clock
+            let open = !caps["word"].eq_ignore_ascii_case("uhr");
+            if !open || hour <= 12 {
+                push(whole.start(), whole.end(), hour, 0, 0, open, 23);
+            }
+        }
+        for caps in AT_HOUR.captures_iter(text) {
+            let hour = caps.name("h").unwrap();
+            let (_, end, _) = zone_after(text, hour.end());
+            if AT_HOUR_FOLLOWER.is_match(&text[end..]) {
+                if let Some(h) = number(&caps, "h") {
+                    push(hour.start(), hour.end(), h, 0, 0, true, 24);
+                }
+            }
+        }
+    }
+}
+
+fn durations(text: &str, out: &mut Vec<Candidate>) {
+    for found in DURATION.find_iter(text) {
+        if let Some(duration) = quantities(found.as_str()) {
+            out.push(Candidate::new(found.start(), found.end(), Value::Duration(duration), 30));
+        }
+    }
+}
+
+/// The sum of the quantities such as "2 hours" in `text`.
+fn quantities(text: &str) -> Option<Duration> {
+    let mut duration = Duration::default();
+    for caps in QUANTITY_PARTS.captures_iter(text) {
+        let n = match (caps.name("n"), caps.name("word")) {
+            (Some(n), _) => n.as_str().replace(',', ".").parse().ok()?,
+            (_, Some(word)) => {
+                let word = word.as_str().to_lowercase().split_whitespace().collect::<Vec<_>>().join(" ");
+                *NUMBER_NAMES.get(word.as_str())?
+            }
+            _ => continue,
+        };
+        let unit = *UNIT_NAMES.get(caps["unit"].to_lowercase().as_str())?;
+        duration.0[unit as usize] += n;
+    }
+    (duration != Duration::default()).then_some(duration)
+}
+
+fn number(caps: &Captures, name: &str) -> Option<u32> {
+    // Full-width digits, as written in CJK dates, are digits too
+    let digits: String = caps
+        .name(name)?
+        .as_str()
+        .chars()
+        .map(|c| match c {
+            '０'..='９' => char::from_u32(c as u32 - '０' as u32 + '0' as u32).unwrap_or(c),
+            c => c,
+        })
+        .collect();
+    digits.parse().ok()
+}
+
+fn month_number(name: &str) -> Option<u32> {
+    MONTH_NUMBERS.get(&name.to_lowercase()).copied()
+}
+
+fn direction(word: &str) -> Option<i64> {
+    let word = word.to_lowercase().split_whitespace().collect::<Vec<_>>().join(" ");
+    DIRECTIONS.iter().find(|d| d.0 == word).map(|d| d.1)
+}
+
+fn is_cjk(language: &str) -> bool {
+    matches!(language, "zh" | "ja")
+}
+
+/// `hour` on the 24-hour clock given an `am`/`pm` marker, if it fits one.
+fn with_meridiem(hour: u32, marker: Option<&str>) -> Option<u32> {
+    let Some(marker) = marker else {
+        return Some(hour);
+    };
+    if !(1..=12).contains(&hour) {
+        return None;
+    }
+    let pm = marker.trim_start().starts_with(['p', 'P']);
+    Some(hour % 12 + if pm { 12 } else { 0 })
+}
+
+/// Minutes east of UTC of an offset such as `+01:00`, `-0500` or `+2`.
+fn parse_offset(text: &str) -> Option<i32> {
+    let text = text.trim();
+    if text == "Z" {
+        return Some(0);
+    }
+    let sign = if text.starts_with('-') { -1 } else { 1 };
+    let digits: String = text[1..].chars().filter(char::is_ascii_digit).collect();
+    let (hours, minutes) = match digits.len() {
+        1 | 2 => (digits.parse::<i32>().ok()?, 0),
+        3 | 4 => (digits[..digits.len() - 2].parse::<i32>().ok()?, digits[digits.len() - 2..].parse::<i32>().ok()?),
+        _ => return None,
+    };
+    (hours <= 14 && minutes < 60).then_some(sign * (hours * 60 + minutes))
+}
+
+/// The zone written right after a time ending at `end`: its offset, the
+/// end of the zone, and whether its abbreviation is shared. Bare negative
+/// offsets are left alone, since "9:00-10:00" is a range.
+fn zone_after(text: &str, end: usize) -> (Option<i32>, usize, bool) {
+    let Some(caps) = ZONE.captures(&text[end..]) else {
+        return (None, end, false);
+    };
+    let zone_end = end + caps.get(0).unwrap().end();
+    if let Some(abbreviation) = caps.name("abbr") {
+        let Some(&(_, base, shared)) = ZONES.iter().find(|z| z.0 == abbreviation.as_str()) else {
+            return (None, end, false);
+        };
+        let shift = match caps.name("shift") {
+            Some(shift) => match parse_offset(shift.as_str()) {
+                Some(shift) => shift,
+                None => return (Some(base), end + abbreviation.end(), shared),
+            },
+            None => 0,
+        };
+        return (Some(base + shift), zone_end, shared);
+    }
+    if let Some(offset) = caps.name("offset") {
+        if offset.as_str().trim_start().starts_with('+') {
+            if let Some(minutes) = parse_offset(offset.as_str()) {
+                return (Some(minutes), zone_end, false);
+            }
+        }
+        return (None, end, false);
+    }
+    (Some(0), zone_end, false)
+}
+
+/// Whether a "Morgen" or "mañana" found at `start` is the noun "morning":
+/// capitalized, or after a word such as "heute" or "esta".
+fn means_morning(text: &str, start: usize, found: &str, phrase: &str) -> bool {
+    let Some((_, determiners)) = MORNING_WORDS.iter().find(|w| w.0 == phrase) else {
+        return false;
+    };
+    let previous = text[..start]
+        .split_whitespace()
+        .next_back()
+        .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase());
+    found.chars().any(char::is_uppercase) || previous.is_some_and(|w| determiners.contains(&w.as_str()))
+}
+
+/// Keeps the longest of overlapping candidates, then the best ranked.
+fn resolve_overlaps(candidates: Vec<Candidate>) -> Vec<Candidate> {
+    sweep_overlaps(candidates, |c| (c.start, c.end), |a, b| {
+        (a.end - a.start).cmp(&(b.end - b.start)).then(b.rank.cmp(&a.rank))
+    })
+}
+
+/// Joins neighbouring candidates with `join` wherever it accepts the text
+/// between them.
+fn fold(text: &str, candidates: Vec<Candidate>, join: impl Fn(&str, &Candidate, &Candidate) -> Option<Candidate>) -> Vec<Candidate> {
+    let mut out: Vec<Candidate> = Vec::new();
+    for candidate in candidates {
+        if let Some(joined) = out.last().and_then(|last| join(text, last, &candidate)) {
+            *out.last_mut().unwrap() = joined;
+        } else {
+            out.push(candidate);
+        }
+    }
+    out
+}
+
+/// Dates with the times written before or after them.
+fn merge(text: &str, candidates: Vec<Candidate>) -> Vec<Candidate> {
+    fold(text, candidates, |text, a, b| {
+        let gap = &text[a.end..b.start];
+        let as_date = |v: &Value| match v {
+            Value::Date(date) => Some(*date),
+            _ => None,
+        };
+        if DATE_TIME_GAP.is_match(gap) && as_date(&a.value).is_some() {
+            return a.clone().join(b.clone(), |date, time| time.on_date(as_date(date)?));
+        }
+        if TIME_DATE_GAP.is_match(gap) && as_date(&b.value).is_some() {
+            let joined = a.clone().join(b.clone(), |time, date| time.on_date(as_date(date)?))?;
+            return Some(joined);
+        }
+        None
+    })
+}
+
+/// Ranges written as two expressions joined by "to", "-", "until" and the
+/// like, taking in a leading "from" or "between".
+fn ranges(text: &str, candidates: Vec<Candidate>) -> Vec<Candidate> {
+    fold(text, candidates, |text, a, b| {
+        let gap = RANGE_GAP.captures(&text[a.end..b.start])?;
+        let mut before = a.start.saturating_sub(16);
+        while !text.is_char_boundary(before) {
+            before -= 1;
+        }
+        let prefix = RANGE_PREFIX.captures(&text[before..a.start]);
+        let between = prefix.as_ref().is_some_and(|p| p.name("between").is_some());
+        if gap.name("and").is_some() != between {
+            return None;
+        }
+        let mut joined = a.clone().join(b.clone(), Value::until)?;
+        if let Some(prefix) = prefix {
+            joined.start = before + prefix.get(0).unwrap().start();
+        }
+        Some(joined)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(text: &str, reference: &str) -> Vec<(String, String)> {
+        let options = TemporalOptions { reference: Some(reference.to_string()), ..Default::default() };
+        extract(text, &options, 0.0).unwrap().into_iter().map(|e| (e.text, e.value)).collect()
+    }
+
+    fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        expected.iter().map(|&(text, value)| (text.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn test_absolute_dates_across_locales() {
+        let text = "Due 03/04/2025 or 4 March 2025, le 4 mars 2025, am 4. März 2025, 2025年3月4日, 15.04.25, on March 9.";
+        assert_eq!(
+            found(text, "2025-03-10"),
+            pairs(&[
+                ("03/04/2025", "2025-03-04"),
+                ("4 March 2025", "2025-03-04"),
+                ("4 mars 2025", "2025-03-04"),
+                ("4. März 2025", "2025-03-04"),
+                ("2025年3月4日", "2025-03-04"),
+                ("15.04.25", "2025-04-15"),
+                ("March 9", "2026-03-09"),
+            ])
+        );
+
+        let options = TemporalOptions { reference: Some("2025-03-10".to_string()), ..Default::default() };
+        let found = extract("03/04/2025, Friday 4 March 2025, 1/2/99", &options, 0.0).unwrap();
+        assert_eq!(found[0].ambiguities, vec![Ambiguity::DayMonthOrder]);
+        assert_eq!(found[0].alternative.as_deref(), Some("2025-04-03"));
+        assert_eq!(found[1].ambiguities, vec![Ambiguity::WeekdayMismatch]);
+        assert_eq!(found[2].value, "1999-01-02");
+        assert_eq!(found[2].ambiguities, vec![Ambiguity::DayMonthOrder, Ambiguity::TwoDigitYear]);
+        let found = extract("We do 4 March 2025 deliveries, Di., 4. März 2025 and Tue 4 March 2025.", &options, 0.0).unwrap();
+        let texts: Vec<&str> = found.iter().map(|e| e.text.as_str()).collect();
+        assert_eq!(texts, vec!["4 March 2025", "Di., 4. März 2025", "Tue 4 March 2025"]);
+        assert!(found.iter().all(|e| e.ambiguities.is_empty()));
+        let day_first = TemporalOptions { day_first: true, ..options };
+        assert_eq!(extract("03/04/2025", &day_first, 0.0).unwrap()[0].value, "2025-04-03");
+    }
+
+    #[test]
+    fn test_relative_expressions() {
+        // 2025-03-05 is a Wednesday
+        let text = "Ship it next Friday, review in 3 weeks, as agreed 2 days ago. Call tomorrow at 3pm. \
+                    Plan next month and next week; it rained last Monday.";
+        assert_eq!(
+            found(text, "2025-03-05T10:00"),
+            pairs(&[
+                ("next Friday", "2025-03-07"),
+                ("in 3 weeks", "2025-03-26"),
+                ("2 days ago", "2025-03-03"),
+                ("tomorrow at 3pm", "2025-03-06T15:00:00"),
+                ("next month", "2025-04"),
+                ("next week", "2025-W11"),
+                ("last Monday", "2025-03-03"),
+            ])
+        );
+        let options = TemporalOptions { reference: Some("2025-03-05T10:00".to_string()), ..Default::default() };
+        let next_friday = &extract("next Friday", &options, 0.0).unwrap()[0];
+        assert!(next_friday.relative);
+        assert_eq!(next_friday.ambiguities, vec![Ambiguity::RelativeWeekday]);
+        assert_eq!(next_friday.alternative.as_deref(), Some("2025-03-14"));
+        assert_eq!(extract("in 90 minutes", &options, 0.0).unwrap()[0].value, "2025-03-05T11:30:00");
+
+        let german = TemporalOptions { language: Some("de".to_string()), ..options.clone() };
+        assert_eq!(found_with("Wir sind hier übermorgen, nicht vor 2 Tagen.", &german), pairs(&[("übermorgen", "2025-03-07"), ("vor 2 Tagen", "2025-03-03")]));
+        // "Morgen" and "mañana" are also the morning
+        assert_eq!(found_with("Wir haben heute Morgen telefoniert.", &german), pairs(&[("heute", "2025-03-05")]));
+        assert_eq!(found_with("Wir telefonieren morgen.", &german), pairs(&[("morgen", "2025-03-06")]));
+        let spanish = TemporalOptions { language: Some("es".to_string()), ..options.clone() };
+        assert_eq!(found_with("Llamé esta mañana.", &spanish), pairs(&[]));
+        assert_eq!(found_with("Llamo mañana por la mañana.", &spanish), pairs(&[("mañana", "2025-03-06")]));
+        // Without a reference the clock decides: 2025-03-05T00:00:00Z
+        let now = Date { year: 2025, month: 3, day: 5 }.days() as f64 * 86_400_000.0;
+        assert_eq!(extract("tomorrow", &TemporalOptions::default(), now).unwrap()[0].value, "2025-03-06");
+
+        // Nothing beyond the years ISO 8601 dates are written in
+        for text in ["in 99999999999999999999 days", "in 3000000000 years", "in 12345678901 months", "8000 years ago"] {
+            assert!(extract(text, &options, 0.0).unwrap().iter().all(|e| e.kind != TemporalKind::Date), "{text}");
+        }
+        assert_eq!(extract("in 7000 years", &options, 0.0).unwrap()[0].value, "9025-03-05");
+    }
+
+    fn found_with(text: &str, options: &TemporalOptions) -> Vec<(String, String)> {
+        extract(text, options, 0.0).unwrap().into_iter().map(|e| (e.text, e.value)).collect()
+    }
+
+    #[test]
+    fn test_times_zones_ranges_and_durations() {
+        let text = "Open from 9:00 to 17:30 on March 4, 2025. Calls at 15:30 CET or 10am PST, \
+                    the fair runs 4–7 June 2025, and the talk lasts 2 hours and 30 minutes. Meet at 5 CST.";
+        assert_eq!(
+            found(text, "2025-01-01"),
+            pairs(&[
+                ("from 9:00 to 17:30 on March 4, 2025", "2025-03-04T09:00:00/2025-03-04T17:30:00"),
+                ("15:30 CET", "15:30:00+01:00"),
+                ("10am PST", "10:00:00-08:00"),
+                ("4–7 June 2025", "2025-06-04/2025-06-07"),
+                ("2 hours and 30 minutes", "PT2H30M"),
+                ("5 CST", "05:00:00-06:00"),
+            ])
+        );
+
+        let options = TemporalOptions { reference: Some("2025-01-01".to_string()), ..Default::default() };
+        let found = extract("at 5 CST, 2025-03-04T09:15:00Z, between 1 and 3 March 2025, for 1.5 weeks", &options, 0.0).unwrap();
+        assert_eq!(found[0].ambiguities, vec![Ambiguity::Meridiem, Ambiguity::TimeZone]);
+        assert_eq!(found[0].alternative.as_deref(), Some("17:00:00-06:00"));
+        assert_eq!((found[1].kind, found[1].value.as_str()), (TemporalKind::DateTime, "2025-03-04T09:15:00Z"));
+        assert_eq!(found[2].kind, TemporalKind::Range);
+        assert_eq!((found[2].start.as_deref(), found[2].end.as_deref()), (Some("2025-03-01"), Some("2025-03-03")));
+        assert_eq!((found[3].kind, found[3].value.as_str()), (TemporalKind::Duration, "P1.5W"));
+        assert!(extract("x", &TemporalOptions { reference: Some("soon".to_string()), ..Default::default() }, 0.0).is_err());
+    }
+}
@@ -0,0 +1,715 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+use thiserror::Error;
+use unicode_normalization::UnicodeNormalization;
+
+const DEFAULT_UNK_TOKEN: &str = "[UNK]";
+const DEFAULT_CONTINUATION_PREFIX: &str = "##";
+const DEFAULT_MAX_CHARS_PER_WORD: usize = 100;
+const METASPACE: char = '\u{2581}';
+/// Words memoized by the BPE merge cache before it is reset.
+const BPE_CACHE_LIMIT: usize = 50_000;
+
+/// A BPE symbol and its byte range within the word.
+type Symbol = (String, usize, usize);
+
+lazy_static! {
+    // GPT-2 pre-tokenizer without the `\s+(?!\S)` lookahead, which the regex
+    // crate does not support; the only difference is how runs of spaces split.
+    static ref BYTE_LEVEL_SPLIT: Regex = Regex::new(
+        r"'(?:[sdmt]|ll|ve|re)| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+"
+    ).unwrap();
+    static ref BYTE_ENCODER: [char; 256] = byte_encoder();
+}
+
+#[derive(Error, Debug)]
+pub enum TokenizerError {
+    #[error("Invalid vocabulary: {0}")]
+    Vocab(String),
+    #[error("Invalid merges: {0}")]
+    Merges(String),
+    #[error("Unsupported tokenizer: {0}")]
+    Unsupported(String),
+    #[error("Malformed tokenizer.json: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenizerFormat {
+    /// `vocab.txt` with one WordPiece token per line.
+    WordPiece,
+    /// `merges.txt` of a byte-level BPE model.
+    #[serde(rename = "bpe")]
+    BpeMerges,
+    /// A HuggingFace `tokenizer.json` with a WordPiece or BPE model.
+    HuggingFace,
+}
+
+impl TryFrom<u32> for TokenizerFormat {
+    type Error = TokenizerError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(TokenizerFormat::WordPiece),
+            1 => Ok(TokenizerFormat::BpeMerges),
+            2 => Ok(TokenizerFormat::HuggingFace),
+            other => Err(TokenizerError::Unsupported(format!("format code {}", other))),
+        }
+    }
+}
+
+/// A token with byte offsets into the text it was produced from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    pub id: u32,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PreTokenizer {
+    /// BERT style: whitespace, then every punctuation and CJK character alone.
+    Bert,
+    Whitespace,
+    ByteLevel { add_prefix_space: bool },
+    Metaspace { add_prefix_space: bool },
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Normalizer {
+    lowercase: bool,
+    strip_accents: bool,
+}
+
+struct WordPieceModel {
+    vocab: HashMap<String, u32>,
+    unk_id: u32,
+    prefix: String,
+    max_chars_per_word: usize,
+}
+
+struct BpeModel {
+    vocab: HashMap<String, u32>,
+    ranks: HashMap<(String, String), usize>,
+    unk_id: Option<u32>,
+    prefix: String,
+    suffix: String,
+    byte_level: bool,
+    cache: RefCell<HashMap<String, Vec<Symbol>>>,
+}
+
+enum Model {
+    WordPiece(WordPieceModel),
+    Bpe(BpeModel),
+}
+
+pub struct Tokenizer {
+    normalizer: Normalizer,
+    pre_tokenizer: PreTokenizer,
+    model: Model,
+}
+
+impl Tokenizer {
+    pub fn load(data: &str, format: TokenizerFormat) -> Result<Self, TokenizerError> {
+        match format {
+            TokenizerFormat::WordPiece => Self::from_wordpiece_vocab(data),
+            TokenizerFormat::BpeMerges => Self::from_bpe_merges(data, None),
+            TokenizerFormat::HuggingFace => Self::from_tokenizer_json(data),
+        }
+    }
+
+    /// Builds a BERT-style tokenizer from a `vocab.txt`. Uncased vocabularies
+    /// (no upper-case ASCII entries) enable lower-casing and accent stripping.
+    pub fn from_wordpiece_vocab(vocab: &str) -> Result<Self, TokenizerError> {
+        let vocab: HashMap<String, u32> = vocab
+            .lines()
+            .map(str::trim_end)
+            .enumerate()
+            .filter(|(_, token)| !token.is_empty())
+            .map(|(id, token)| (token.to_string(), id as u32))
+            .collect();
+        let uncased = !vocab.keys().any(|token| {
+            !token.starts_with('[') && token.chars().any(|c| c.is_ascii_uppercase())
+        });
+
+        Ok(Tokenizer {
+            normalizer: Normalizer {
+                lowercase: uncased,
+                strip_accents: uncased,
+            },
+            pre_tokenizer: PreTokenizer::Bert,
+            model: Model::WordPiece(WordPieceModel::new(
+                vocab,
+                DEFAULT_UNK_TOKEN,
+                DEFAULT_CONTINUATION_PREFIX.to_string(),
+                DEFAULT_MAX_CHARS_PER_WORD,
+            )?),
+        })
+    }
+
+    /// Builds a GPT-2 style byte-level BPE tokenizer. Without a vocabulary,
+    /// ids follow the merge order after the 256 byte symbols.
+    pub fn from_bpe_merges(merges: &str, vocab: Option<HashMap<String, u32>>) -> Result<Self, TokenizerError> {
+        let merges = merges
+            .lines()
+            .filter(|line| !line.starts_with("#version") && !line.trim().is_empty())
+            .map(|line| {
+                let mut parts = line.split(' ');
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(a), Some(b), None) => Ok((a.to_string(), b.to_string())),
+                    _ => Err(TokenizerError::Merges(format!("expected two symbols in `{}`", line))),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let vocab = vocab.unwrap_or_else(|| {
+            let mut vocab: HashMap<String, u32> = BYTE_ENCODER
+                .iter()
+                .enumerate()
+                .map(|(id, c)| (c.to_string(), id as u32))
+                .collect();
+            for (a, b) in &merges {
+                let next = vocab.len() as u32;
+                vocab.entry(format!("{}{}", a, b)).or_insert(next);
+            }
+            vocab
+        });
+
+        Ok(Tokenizer {
+            normalizer: Normalizer::default(),
+            pre_tokenizer: PreTokenizer::ByteLevel { add_prefix_space: false },
+            model: Model::Bpe(BpeModel::new(vocab, merges, None, String::new(), String::new(), true)),
+        })
+    }
+
+    pub fn from_tokenizer_json(json: &str) -> Result<Self, TokenizerError> {
+        let root: Value = serde_json::from_str(json)?;
+        let model = &root["model"];
+        let vocab: HashMap<String, u32> = model["vocab"]
+            .as_object()
+            .ok_or_else(|| TokenizerError::Vocab("model.vocab must be an object".to_string()))?
+            .iter()
+            .filter_map(|(token, id)| Some((token.clone(), id.as_u64()? as u32)))
+            .collect();
+        let prefix = model["continuing_subword_prefix"].as_str();
+        let unk_token = model["unk_token"].as_str();
+        let pre_tokenizer = parse_pre_tokenizer(&root["pre_tokenizer"]);
+        let normalizer = parse_normalizer(&root["normalizer"]);
+
+        let model = match model["type"].as_str() {
+            Some("WordPiece") => Model::WordPiece(WordPieceModel::new(
+                vocab,
+                unk_token.unwrap_or(DEFAULT_UNK_TOKEN),
+                prefix.unwrap_or(DEFAULT_CONTINUATION_PREFIX).to_string(),
+                model["max_input_chars_per_word"]
+                    .as_u64()
+                    .map_or(DEFAULT_MAX_CHARS_PER_WORD, |n| n as usize),
+            )?),
+            Some("BPE") => {
+                let merges = model["merges"]
+                    .as_array()
+                    .ok_or_else(|| TokenizerError::Merges("model.merges must be an array".to_string()))?
+                    .iter()
+                    .map(parse_merge)
+                    .collect::<Result<Vec<_>, _>>()?;
+                let unk_id = unk_token.and_then(|token| vocab.get(token).copied());
+                let byte_level = matches!(pre_tokenizer, Some(PreTokenizer::ByteLevel { .. }));
+                Model::Bpe(BpeModel::new(
+                    vocab,
+                    merges,
+                    unk_id,
+                    prefix.unwrap_or("").to_string(),
+                    model["end_of_word_suffix"].as_str().unwrap_or("").to_string(),
+                    byte_level,
+                ))
+            }
+            other => {
+                return Err(TokenizerError::Unsupported(format!(
+                    "model type {}",
+                    other.unwrap_or("<missing>")
+                )))
+            }
+        };
+
+        let pre_tokenizer = pre_tokenizer.unwrap_or(match model {
+            Model::WordPiece(_) => PreTokenizer::Bert,
+            Model::Bpe(_) => PreTokenizer::Whitespace,
+        });
+
+        Ok(Tokenizer { normalizer, pre_tokenizer, model })
+    }
+
+    pub fn tokenize(&self, text: &str) -> Vec<Token> {
+        let (normalized, offsets) = self.normalizer.apply(text);
+        let mut tokens = Vec::new();
+        for (start, end) in self.pre_tokenizer.split(&normalized) {
+            let word = &normalized[start..end];
+            match &self.model {
+                Model::WordPiece(model) => model.tokenize(word, start, &mut tokens),
+                Model::Bpe(model) => model.tokenize(word, start, self.pre_tokenizer, &mut tokens),
+            }
+        }
+
+        if let Some(offsets) = offsets {
+            for token in &mut tokens {
+                token.start = offsets[token.start];
+                token.end = offsets[token.end];
+            }
+        }
+        tokens
+    }
+
+    pub fn count(&self, text: &str) -> usize {
+        self.tokenize(text).len()
+    }
+}
+
+impl Normalizer {
+    /// Returns the normalized text and, if it differs from the input, a map
+    /// from each normalized byte offset to the original byte offset.
+    fn apply(&self, text: &str) -> (String, Option<Vec<usize>>) {
+        if !self.lowercase && !self.strip_accents {
+            return (text.to_string(), None);
+        }
+
+        let mut normalized = String::with_capacity(text.len());
+        let mut offsets = Vec::with_capacity(text.len() + 1);
+        for (start, c) in text.char_indices() {
+            let before = normalized.len();
+            if self.strip_accents {
+                let lowered: String = if self.lowercase { c.to_lowercase().collect() } else { c.to_string() };
+                normalized.extend(lowered.nfd().filter(|&c| !is_combining_mark(c)));
+            } else {
+                normalized.extend(c.to_lowercase());
+            }
+            offsets.extend(std::iter::repeat_n(start, normalized.len() - before));
+        }
+        offsets.push(text.len());
+        (normalized, Some(offsets))
+    }
+}
+
+impl PreTokenizer {
+    /// Splits `text` into word spans (byte offsets).
+    fn split(self, text: &str) -> Vec<(usize, usize)> {
+        match self {
+            PreTokenizer::Bert => {
+                let mut spans = Vec::new();
+                let mut word_start = None;
+                for (i, c) in text.char_indices() {
+                    if c.is_whitespace() || c.is_control() {
+                        if let Some(start) = word_start.take() {
+                            spans.push((start, i));
+                        }
+                    } else if is_punctuation(c) || is_cjk(c) {
+                        if let Some(start) = word_start.take() {
+                            spans.push((start, i));
+                        }
+                        spans.push((i, i + c.len_utf8()));
+                    } else if word_start.is_none() {
+                        word_start = Some(i);
+                    }
+                }
+                if let Some(start) = word_start {
+                    spans.push((start, text.len()));
+                }
+                spans
+            }
+            PreTokenizer::Whitespace => {
+                let mut spans = Vec::new();
+                let mut word_start = None;
+                let mut word_is_alnum = false;
+                for (i, c) in text.char_indices() {
+                    let alnum = c.is_alphanumeric() || c == '_';
+                    if c.is_whitespace() || (word_start.is_some() && alnum != word_is_alnum) {
+                        if let Some(start) = word_start.take() {
+                            spans.push((start, i));
+                        }
+                    }
+                    if !c.is_whitespace() && word_start.is_none() {
+                        word_start = Some(i);
+                        word_is_alnum = alnum;
+                    }
+                }
+                if let Some(start) = word_start {
+                    spans.push((start, text.len()));
+                }
+                spans
+            }
+            PreTokenizer::ByteLevel { .. } => BYTE_LEVEL_SPLIT
+                .find_iter(text)
+                .map(|m| (m.start(), m.end()))
+                .collect(),
+            PreTokenizer::Metaspace { .. } => {
+                // Each word keeps its leading space, which becomes the metaspace symbol.
+                let mut spans = Vec::new();
+                let mut word_start = 0;
+                for (i, c) in text.char_indices() {
+                    if c == ' ' && i > word_start && !text[word_start..i].trim().is_empty() {
+                        spans.push((word_start, i));
+                        word_start = i;
+                    }
+                }
+                if !text[word_start..].trim().is_empty() {
+                    spans.push((word_start, text.len()));
+                }
+                spans
+            }
+        }
+    }
+}
+
+impl WordPieceModel {
+    fn new(
+        vocab: HashMap<String, u32>,
+        unk_token: &str,
+        prefix: String,
+        max_chars_per_word: usize,
+    ) -> Result<Self, TokenizerError> {
+        let unk_id = *vocab
+            .get(unk_token)
+            .ok_or_else(|| TokenizerError::Vocab(format!("missing unknown token {}", unk_token)))?;
+        Ok(WordPieceModel { vocab, unk_id, prefix, max_chars_per_word })
+    }
+
+    /// Greedy longest-match-first segmentation of a single word.
+    fn tokenize(&self, word: &str, offset: usize, tokens: &mut Vec<Token>) {
+        let unknown = Token { id: self.unk_id, start: offset, end: offset + word.len() };
+        if word.chars().count() > self.max_chars_per_word {
+            tokens.push(unknown);
+            return;
+        }
+
+        let boundaries: Vec<usize> = word
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(word.len()))
+            .collect();
+        let first = tokens.len();
+        let mut start = 0;
+        while start < boundaries.len() - 1 {
+            let mut matched = None;
+            for end in (start + 1..boundaries.len()).rev() {
+                let piece = &word[boundaries[start]..boundaries[end]];
+                let id = if start == 0 {
+                    self.vocab.get(piece)
+                } else {
+                    self.vocab.get(&format!("{}{}", self.prefix, piece))
+                };
+                if let Some(&id) = id {
+                    matched = Some((id, end));
+                    break;
+                }
+            }
+
+            match matched {
+                Some((id, end)) => {
+                    tokens.push(Token {
+                        id,
+                        start: offset + boundaries[start],
+                        end: offset + boundaries[end],
+                    });
+                    start = end;
+                }
+                None => {
+                    tokens.truncate(first);
+                    tokens.push(unknown);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl BpeModel {
+    fn new(
+        vocab: HashMap<String, u32>,
+        merges: Vec<(String, String)>,
+        unk_id: Option<u32>,
+        prefix: String,
+        suffix: String,
+        byte_level: bool,
+    ) -> Self {
+        let ranks = merges.into_iter().enumerate().map(|(rank, pair)| (pair, rank)).collect();
+        BpeModel {
+            vocab,
+            ranks,
+            unk_id,
+            prefix,
+            suffix,
+            byte_level,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn tokenize(&self, word: &str, offset: usize, pre_tokenizer: PreTokenizer, tokens: &mut Vec<Token>) {
+        let prefix_space = matches!(
+            pre_tokenizer,
+            PreTokenizer::ByteLevel { add_prefix_space: true } | PreTokenizer::Metaspace { add_prefix_space: true }
+        ) && offset == 0
+            && !word.starts_with(' ');
+
+        let cache_key = if prefix_space { format!(" {}", word) } else { word.to_string() };
+        if let Some(symbols) = self.cache.borrow().get(&cache_key) {
+            self.push_symbols(symbols, offset, prefix_space, tokens);
+            return;
+        }
+
+        let symbols = self.merge(self.initial_symbols(&cache_key, pre_tokenizer));
+        self.push_symbols(&symbols, offset, prefix_space, tokens);
+
+        let mut cache = self.cache.borrow_mut();
+        if cache.len() >= BPE_CACHE_LIMIT {
+            cache.clear();
+        }
+        cache.insert(cache_key, symbols);
+    }
+
+    /// Splits a word into starting symbols with byte ranges relative to the word.
+    fn initial_symbols(&self, word: &str, pre_tokenizer: PreTokenizer) -> Vec<Symbol> {
+        if self.byte_level {
+            return word
+                .bytes()
+                .enumerate()
+                .map(|(i, b)| (BYTE_ENCODER[b as usize].to_string(), i, i + 1))
+                .collect();
+        }
+
+        let metaspace = matches!(pre_tokenizer, PreTokenizer::Metaspace { .. });
+        let chars: Vec<(usize, char)> = word.char_indices().collect();
+        let last = chars.len().saturating_sub(1);
+        chars
+            .iter()
+            .enumerate()
+            .map(|(n, &(i, c))| {
+                let mut symbol = String::new();
+                if n > 0 {
+                    symbol.push_str(&self.prefix);
+                }
+                symbol.push(if metaspace && c == ' ' { METASPACE } else { c });
+                if n == last {
+                    symbol.push_str(&self.suffix);
+                }
+                (symbol, i, i + c.len_utf8())
+            })
+            .collect()
+    }
+
+    fn merge(&self, mut symbols: Vec<Symbol>) -> Vec<Symbol> {
+        loop {
+            let best = symbols
+                .windows(2)
+                .enumerate()
+                .filter_map(|(i, pair)| {
+                    let right = pair[1].0.strip_prefix(self.prefix.as_str()).unwrap_or(&pair[1].0);
+                    let key = (pair[0].0.clone(), pair[1].0.clone());
+                    self.ranks
+                        .get(&key)
+                        .map(|&rank| (rank, i, right.to_string()))
+                })
+                .min_by_key(|&(rank, i, _)| (rank, i));
+
+            let Some((_, i, right)) = best else {
+                return symbols;
+            };
+            let (_, _, end) = symbols.remove(i + 1);
+            symbols[i].0.push_str(&right);
+            symbols[i].2 = end;
+        }
+    }
+
+    fn push_symbols(&self, symbols: &[Symbol], offset: usize, prefix_space: bool, tokens: &mut Vec<Token>) {
+        // A synthetic prefix space shifts symbol ranges one byte past the word.
+        let shift = usize::from(prefix_space);
+        for (symbol, start, end) in symbols {
+            let Some(id) = self.vocab.get(symbol).copied().or(self.unk_id) else {
+                continue;
+            };
+            tokens.push(Token {
+                id,
+                start: offset + start.saturating_sub(shift),
+                end: offset + end.saturating_sub(shift),
+            });
+        }
+    }
+}
+
+fn parse_merge(merge: &Value) -> Result<(String, String), TokenizerError> {
+    match merge {
+        Value::String(line) => match line.split_once(' ') {
+            Some((a, b)) => Ok((a.to_string(), b.to_string())),
+            None => Err(TokenizerError::Merges(format!("expected two symbols in `{}`", line))),
+        },
+        Value::Array(pair) => match (pair.first().and_then(Value::as_str), pair.get(1).and_then(Value::as_str)) {
+            (Some(a), Some(b)) if pair.len() == 2 => Ok((a.to_string(), b.to_string())),
+            _ => Err(TokenizerError::Merges(format!("expected a pair of symbols, got {}", merge))),
+        },
+        other => Err(TokenizerError::Merges(format!("unexpected merge entry {}", other))),
+    }
+}
+
+fn parse_pre_tokenizer(value: &Value) -> Option<PreTokenizer> {
+    let add_prefix_space = value["add_prefix_space"].as_bool().unwrap_or(false)
+        || value["prepend_scheme"].as_str().is_some_and(|scheme| scheme != "never");
+    match value["type"].as_str()? {
+        "BertPreTokenizer" => Some(PreTokenizer::Bert),
+        "Whitespace" | "WhitespaceSplit" => Some(PreTokenizer::Whitespace),
+        "ByteLevel" => Some(PreTokenizer::ByteLevel { add_prefix_space }),
+        "Metaspace" => Some(PreTokenizer::Metaspace { add_prefix_space }),
+        "Sequence" => value["pretokenizers"].as_array()?.iter().rev().find_map(parse_pre_tokenizer),
+        _ => None,
+    }
+}
+
+fn parse_normalizer(value: &Value) -> Normalizer {
+    match value["type"].as_str() {
+        Some("BertNormalizer") => {
+            let lowercase = value["lowercase"].as_bool().unwrap_or(true);
+            Normalizer {
+                lowercase,
+                strip_accents: value["strip_accents"].as_bool().unwrap_or(lowercase),
+            }
+        }
+        Some("Lowercase") => Normalizer { lowercase: true, strip_accents: false },
+        Some("StripAccents") => Normalizer { lowercase: false, strip_accents: true },
+        Some("Sequence") => value["normalizers"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(parse_normalizer)
+            .fold(Normalizer::default(), |acc, n| Normalizer {
+                lowercase: acc.lowercase || n.lowercase,
+                strip_accents: acc.strip_accents || n.strip_accents,
+            }),
+        _ => Normalizer::default(),
+    }
+}
+
+/// GPT-2's reversible mapping from bytes to printable unicode characters.
+fn byte_encoder() -> [char; 256] {
+    let mut table = ['\0'; 256];
+    let mut next = 256u32;
+    for (byte, slot) in table.iter_mut().enumerate() {
+        let printable = matches!(byte, 0x21..=0x7E | 0xA1..=0xAC | 0xAE..=0xFF);
+        let code = if printable {
+            byte as u32
+        } else {
+            next += 1;
+            next - 1
+        };
+        *slot = char::from_u32(code).unwrap_or('\u{FFFD}');
+    }
+    table
+}
+
+fn is_combining_mark(c: char) -> bool {
+    matches!(c as u32, 0x0300..=0x036F | 0x1AB0..=0x1AFF | 0x1DC0..=0x1DFF | 0x20D0..=0x20FF | 0xFE20..=0xFE2F)
+}
+
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation()
+        || matches!(c as u32, 0x2000..=0x206F | 0x3000..=0x303F | 0xFF00..=0xFF0F | 0xFF1A..=0xFF20)
+        || matches!(c, '¡' | '¿' | '«' | '»' | '§' | '¶')
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32, 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0x20000..=0x2FA1F)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts<'a>(text: &'a str, tokens: &[Token]) -> Vec<&'a str> {
+        tokens.iter().map(|t| &text[t.start..t.end]).collect()
+    }
+
+    #[test]
+    fn test_wordpiece_uncased() {
+        let vocab = "[PAD]\n[UNK]\n[CLS]\nthe\nquick\nbrown\nfox\nun\n##believ\n##able\n.\ncafe\n";
+        let tokenizer = Tokenizer::load(vocab, TokenizerFormat::WordPiece).unwrap();
+        let text = "The unbelievable Café fox.";
+        let tokens = tokenizer.tokenize(text);
+
+        assert_eq!(texts(text, &tokens), vec!["The", "un", "believ", "able", "Café", "fox", "."]);
+        assert_eq!(tokens[1].id, 7);
+        assert_eq!(tokens[2].id, 8);
+        assert_eq!(tokens[4].id, 11);
+        assert_eq!(tokenizer.count("zebra"), 1); // [UNK]
+    }
+
+    #[test]
+    fn test_byte_level_bpe_merges() {
+        let merges = "#version: 0.2\nl o\nlo w\nĠ low\ne r\n";
+        let tokenizer = Tokenizer::load(merges, TokenizerFormat::BpeMerges).unwrap();
+        let text = "low lower lowér";
+        let tokens = tokenizer.tokenize(text);
+
+        assert_eq!(texts(text, &tokens[..3]), vec!["low", " low", "er"]);
+        assert_eq!(tokens[0].id, 257);
+        assert_eq!(tokens[1].id, 258);
+        // Without a merge for it, the non-ASCII character stays as its two UTF-8 bytes.
+        let widths: Vec<usize> = tokens[3..].iter().map(|t| t.end - t.start).collect();
+        assert_eq!(widths, vec![4, 1, 1, 1]);
+        assert_eq!(tokens.last().unwrap().end, text.len());
+    }
+
+    #[test]
+    fn test_tokenizer_json() {
+        let json = r###"{
+            "normalizer": {"type": "BertNormalizer", "lowercase": true},
+            "pre_tokenizer": {"type": "BertPreTokenizer"},
+            "model": {
+                "type": "WordPiece",
+                "unk_token": "[UNK]",
+                "continuing_subword_prefix": "##",
+                "vocab": {"[UNK]": 0, "token": 1, "##izer": 2, "##s": 3, "!": 4}
+            }
+        }"###;
+        let tokenizer = Tokenizer::load(json, TokenizerFormat::HuggingFace).unwrap();
+        let tokens = tokenizer.tokenize("Tokenizers!");
+        assert_eq!(tokens.iter().map(|t| t.id).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+
+        let json = r###"{
+            "pre_tokenizer": {"type": "ByteLevel", "add_prefix_space": false},
+            "model": {"type": "BPE", "vocab": {"a": 0, "b": 1, "ab": 2, "Ġ": 3}, "merges": [["a", "b"]]}
+        }"###;
+        let tokenizer = Tokenizer::load(json, TokenizerFormat::HuggingFace).unwrap();
+        assert_eq!(tokenizer.tokenize("abab").iter().map(|t| t.id).collect::<Vec<_>>(), vec![2, 2]);
+
+        assert!(matches!(
+            Tokenizer::load(r#"{"model": {"type": "Unigram", "vocab": {}}}"#, TokenizerFormat::HuggingFace),
+            Err(TokenizerError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_chunking_by_tokens() {
+        let mut processor = crate::TextProcessor::new();
+        processor.tokenizer = Some(Tokenizer::load("[UNK]\na\nb\nc\n.\n", TokenizerFormat::WordPiece).unwrap());
+        let config = crate::ProcessingConfig {
+            chunk_size: 4,
+            overlap: 1,
+            size_unit: crate::SizeUnit::Tokens,
+            ..Default::default()
+        };
+
+        let chunks = processor.chunk_text_impl("a b c. a b c. a b", &config).unwrap();
+        let texts: Vec<&str> = chunks.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, vec!["a b c.", ". a b c", "c. a b"]);
+        assert!(chunks.iter().all(|c| c.token_count.unwrap() <= 4));
+        assert_eq!(chunks[0].token_count, Some(4));
+        assert_eq!((chunks[1].start, chunks[1].end), (5, 12));
+
+        processor.tokenizer = None;
+        assert!(matches!(
+            processor.chunk_text_impl("a b", &config),
+            Err(crate::TextProcessingError::TokenizerMissing)
+        ));
+    }
+}
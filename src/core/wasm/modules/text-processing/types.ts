@@ -3,29 +3,43 @@ import { WasmModuleConfig, WasmModuleType } from '../../types';
 export interface TextChunk {
   id: string;
   text: string;
-  start: number;
+  start: number; // UTF-16 code units into the processed text, as all positions are
   end: number;
+  tokenCount?: number;
   metadata?: {
     type?: string;
     language?: string;
     confidence?: number;
+    headingPath?: string[];
+    sentiment?: SentimentScore; // when the options asked for sentiment
     [key: string]: unknown;
   };
 }
 
 export interface TextProcessingConfig extends WasmModuleConfig {
   type: WasmModuleType.TextProcessing;
+  bindingsPath?: string; // wasm-bindgen JS glue; defaults to the `.js` file beside `*_bg.wasm`
   options?: {
     chunkSize?: number;
     overlap?: number;
     preserveWhitespace?: boolean;
     preserveNewlines?: boolean;
     trimChunks?: boolean;
-    language?: string;
-    encoding?: string;
+    sizeUnit?: 'graphemes' | 'tokens';
+    strategy?: 'fixed' | 'structural' | 'semantic';
+    language?: string; // picks sentence boundary rules; detected when unset
+    encoding?: string; // WHATWG label of byte input, or 'auto' to detect it
+    html?: HtmlOptions; // input is HTML; chunks then refer to its converted text
+    normalization?: NormalizationOptions; // chunks then refer to the normalized text
+    redaction?: RedactionPolicy; // chunks then refer to the redacted text
+    sentiment?: SentimentOptions; // scores each chunk and the whole text
   };
 }
 
+export type TextProcessingOptions = NonNullable<TextProcessingConfig['options']>;
+
+export type TokenizerFormat = 'wordpiece' | 'bpe' | 'huggingface';
+
 export interface TextProcessingResult {
   chunks: TextChunk[];
   stats: {
@@ -39,8 +53,416 @@ export interface TextProcessingResult {
     language?: string;
     encoding?: string;
     confidence?: number;
+    sentiment?: SentimentScore;
     [key: string]: unknown;
   };
+  html?: HtmlText;
+  normalization?: { offsets: [number, number][] };
+  redaction?: RedactionMapping;
+}
+
+export type EntityType =
+  | 'email'
+  | 'url'
+  | 'phone'
+  | 'money'
+  | 'percentage'
+  | 'iban'
+  | 'creditCard'
+  | 'date'
+  | 'person'
+  | 'organization'
+  | 'location'
+  | 'custom';
+
+export interface EntityExtractionOptions {
+  entityTypes?: EntityType[];
+  minConfidence?: number;
+  defaultCallingCode?: string; // assumed for phone numbers without one, e.g. '44'
+  dayFirst?: boolean; // read 03/04/2024 as 3 April
+}
+
+export interface Entity {
+  id: string;
+  type: EntityType;
+  value: string;
+  confidence: number;
+  position: {
+    start: number;
+    end: number;
+  };
+  metadata: {
+    normalized?: string;
+    currency?: string;
+    amount?: number;
+    brand?: string;
+    country?: string;
+    dictionary?: string; // gazetteer dictionary of a dictionary match
+  };
+}
+
+export interface GazetteerDictionary {
+  name: string;
+  entityType?: EntityType; // defaults to 'custom'
+  terms: (string | { term: string; canonical?: string })[];
+}
+
+export interface GazetteerOptions {
+  caseInsensitive?: boolean;
+  normalize?: boolean; // match after NFKC normalization
+  wholeWords?: boolean;
+  longestMatch?: boolean; // otherwise report overlapping matches too
+}
+
+export type RedactionMode = 'mask' | 'placeholder' | 'pseudonym';
+
+export interface RedactionPolicy extends EntityExtractionOptions {
+  mode?: RedactionMode;
+  maskChar?: string;
+  key?: string; // secret for pseudonyms, required in that mode
+}
+
+export interface RedactionMapping {
+  mode: RedactionMode;
+  replacements: {
+    type: EntityType;
+    original: string;
+    replacement: string;
+    start: number; // in the redacted text
+    end: number;
+    originalStart: number;
+    originalEnd: number;
+  }[];
+}
+
+export interface Redaction {
+  text: string;
+  mapping: RedactionMapping;
+}
+
+export interface KeywordOptions {
+  method?: 'rake' | 'textrank' | 'tfidf';
+  limit?: number;
+  maxWords?: number; // longest phrase, in words
+  window?: number; // TextRank co-occurrence window
+  language?: string; // ISO 639-1 code choosing the stopwords; detected when unset
+}
+
+export interface Keyword {
+  phrase: string;
+  score: number;
+  occurrences: { start: number; end: number }[];
+}
+
+/** Document frequencies for TF-IDF, built with `addToCorpus` and stored as JSON. */
+export interface CorpusStats {
+  documents: number;
+  maxWords: number;
+  documentFrequency: Record<string, number>;
+}
+
+export interface SummaryOptions {
+  algorithm?: 'textrank' | 'lexrank' | 'centroid';
+  maxLength?: number; // in `unit`s
+  unit?: 'words' | 'tokens'; // tokens need a loaded tokenizer
+  diversity?: number; // 1 favours relevance, 0 novelty
+  duplicateThreshold?: number;
+  lexrankThreshold?: number;
+  language?: string; // picks sentence boundary rules; detected when unset
+  html?: HtmlOptions; // input is HTML; positions then refer to its converted text
+}
+
+export interface Summary {
+  summary: string;
+  sentences: {
+    text: string;
+    index: number;
+    score: number;
+    position: { start: number; end: number };
+  }[];
+}
+
+export interface SearchOptions {
+  limit?: number;
+  k1?: number; // BM25 term frequency saturation
+  b?: number; // BM25 length normalization, 0 to 1
+}
+
+export interface SearchHit {
+  chunk: TextChunk;
+  score: number;
+  highlights: { start: number; end: number }[]; // in chunk.text
+}
+
+export interface FuzzyOptions {
+  maxDistance?: number; // edits; 0 up to 2 characters, 1 up to 5, 2 beyond when unset
+  transpositions?: boolean; // swapping adjacent characters is one edit (default true)
+  limit?: number;
+}
+
+// An indexed term close to the one looked up
+export interface FuzzyTerm {
+  term: string;
+  distance: number;
+  frequency: number; // occurrences across indexed chunks
+}
+
+export interface FuzzyHit {
+  chunk: TextChunk;
+  distance: number; // of the closest match
+  matches: { text: string; start: number; end: number; distance: number }[]; // in chunk.text
+}
+
+// "Did you mean": the query with unknown words replaced by indexed terms
+export interface Suggestion {
+  text: string;
+  corrections: {
+    word: string;
+    suggestion: string;
+    distance: number;
+    position: { start: number; end: number }; // in the query
+  }[];
+}
+
+export type VectorMetric = 'cosine' | 'dot' | 'l2';
+
+export interface VectorIndexOptions {
+  metric?: VectorMetric;
+  m?: number; // links per node
+  efConstruction?: number;
+  efSearch?: number;
+}
+
+/** A value to equal (or, for array fields, contain), or operators that must all hold. */
+export type MetadataCondition =
+  | string
+  | number
+  | boolean
+  | null
+  | {
+      eq?: unknown;
+      ne?: unknown;
+      in?: unknown[];
+      gt?: number;
+      gte?: number;
+      lt?: number;
+      lte?: number;
+    };
+
+export interface VectorSearchOptions {
+  k?: number;
+  ef?: number;
+  filter?: Record<string, MetadataCondition>;
+}
+
+export interface VectorHit {
+  id: string;
+  distance: number; // 1 - cosine, negated dot product or Euclidean distance
+  metadata: Record<string, unknown>;
+}
+
+export interface FingerprintOptions {
+  shingleSize?: number; // words per shingle
+  permutations?: number; // MinHash signature length
+}
+
+/** Stable across runs for the same text and options; store it with the document. */
+export interface Fingerprint {
+  simhash: string; // 64 bits as 16 hex digits
+  minhash: number[]; // empty for text without words, which duplicates nothing
+}
+
+export interface DuplicateIndexOptions {
+  threshold?: number; // estimated Jaccard similarity, 0 to 1
+}
+
+export interface Duplicate {
+  id: string;
+  similarity: number;
+}
+
+/** Normalization stages; whitespace follows `preserveWhitespace` and `preserveNewlines`. */
+export interface NormalizationOptions {
+  removeControl?: boolean; // default true
+  repairHyphenation?: boolean; // default true; joins "docu-\nment"
+  collapseWhitespace?: boolean; // default true
+  foldPunctuation?: boolean; // curly quotes and dashes to ASCII
+  stripAccents?: boolean;
+  caseFold?: boolean;
+  form?: 'nfc' | 'nfd' | 'nfkc' | 'nfkd' | null; // default 'nfc'
+}
+
+export interface Normalized {
+  text: string;
+  /** `[normalized, original]` UTF-16 anchors; see `toOriginalOffset`. */
+  offsets: [number, number][];
+}
+
+export interface SentenceOptions {
+  language?: string; // ISO 639-1; picks the abbreviation list, detected when unset
+}
+
+export interface Sentence {
+  text: string; // without surrounding whitespace
+  start: number;
+  end: number;
+}
+
+export interface AnalyzerOptions {
+  language?: string; // ISO 639-1, e.g. a chunk's metadata.language; detected when unset
+  lowercase?: boolean;
+  removeStopwords?: boolean;
+  lemmatize?: boolean; // irregular English forms only
+  stem?: boolean; // Snowball stemmers expect lowercase input
+}
+
+export interface Term {
+  term: string; // the analyzed form
+  text: string; // the original word
+  position: { start: number; end: number };
+}
+
+export interface ReadabilityOptions {
+  language?: string; // picks sentence and syllable rules; detected when unset
+  wordsPerMinute?: number;
+}
+
+/** Readability formulas are calibrated on English text. */
+export interface TextStatistics {
+  wordCount: number;
+  sentenceCount: number;
+  paragraphCount: number;
+  syllableCount: number;
+  polysyllableCount: number; // words of three or more syllables
+  fleschReadingEase: number;
+  fleschKincaidGrade: number;
+  gunningFog: number;
+  smog: number;
+  averageSentenceLength: number; // words per sentence
+  lexicalDiversity: number; // type-token ratio
+  readingTimeMinutes: number;
+}
+
+export interface DiffOptions {
+  granularity?: 'word' | 'sentence';
+  algorithm?: 'myers' | 'patience';
+  detectMoves?: boolean; // report reordered blocks as moves
+  minMoveWords?: number;
+  language?: string; // picks sentence boundary rules; detected when unset
+}
+
+export interface TextChange {
+  kind: 'insert' | 'delete' | 'replace' | 'move';
+  old: { start: number; end: number }; // empty for insertions
+  new: { start: number; end: number }; // empty for deletions
+  oldText: string;
+  newText: string;
+}
+
+export interface TextDiff {
+  similarity: number; // 0..1
+  changes: TextChange[]; // in the order of the old text
+}
+
+export interface DecodedText {
+  text: string;
+  encoding: string; // lowercased WHATWG name, e.g. 'windows-1252'
+  hadErrors: boolean; // malformed bytes were replaced by U+FFFD
+}
+
+export interface HtmlOptions {
+  removeBoilerplate?: boolean; // drop navigation, footers, sidebars and cookie banners
+}
+
+export interface HtmlBlock {
+  tag: string; // element the block came from, e.g. 'h2', 'li', 'tr'
+  start: number; // in the converted text
+  end: number;
+  sourceStart: number; // in the HTML
+  sourceEnd: number;
+}
+
+export interface HtmlText {
+  text: string; // Markdown-style, ready for the structural strategy
+  title?: string;
+  blocks: HtmlBlock[];
+}
+
+export interface TemporalOptions {
+  reference?: string; // 'YYYY-MM-DD' or 'YYYY-MM-DDTHH:MM'; defaults to now (UTC)
+  dayFirst?: boolean; // read '03/04/2025' as 3 April
+  preferFuture?: boolean; // defaults to true
+  language?: string; // picks relative day words; detected when omitted
+}
+
+export type TemporalKind = 'date' | 'time' | 'dateTime' | 'range' | 'duration';
+
+export type TemporalAmbiguity =
+  | 'dayMonthOrder'
+  | 'twoDigitYear'
+  | 'missingYear'
+  | 'meridiem'
+  | 'relativeWeekday'
+  | 'timeZone'
+  | 'weekdayMismatch';
+
+export interface TemporalExpression {
+  kind: TemporalKind;
+  text: string;
+  position: { start: number; end: number };
+  value: string; // ISO 8601: '2025-03-04', '2025-W11', '15:30:00+01:00', 'PT2H30M', 'a/b' for ranges
+  start?: string; // range ends
+  end?: string;
+  relative: boolean; // resolved against the reference
+  ambiguities?: TemporalAmbiguity[];
+  alternative?: string; // the other reading, where there is one
+}
+
+export interface ClassifierOptions {
+  algorithm?: 'naiveBayes' | 'logisticRegression'; // defaults to 'naiveBayes'
+  features?: number; // hash buckets, rounded up to a power of two; defaults to 2^18
+  wordNgrams?: number; // longest word n-gram; defaults to 2
+  charNgrams?: number; // in-word character n-gram length; 0 (default) for none
+  smoothing?: number; // Naive Bayes
+  epochs?: number; // logistic regression
+  learningRate?: number;
+  regularization?: number;
+  calibrationSplit?: number; // share of each label held out to calibrate; defaults to 0.2
+}
+
+export interface TrainingExample {
+  text: string;
+  label: string;
+}
+
+export interface TrainingReport {
+  labels: { label: string; examples: number }[];
+  temperature: number; // applied to the scores before the softmax
+  validationAccuracy?: number; // on the held-out examples
+}
+
+export interface Classification {
+  label: string;
+  probability: number;
+  probabilities: { label: string; probability: number }[]; // most likely first
+}
+
+export interface SentimentOptions {
+  lexicon?: Record<string, number>; // lowercase word -> valence from -4 to 4; 0 removes a built-in word
+  language?: string; // picks sentence boundary rules; detected when omitted
+}
+
+export interface SentimentScore {
+  compound: number; // -1 (most negative) to 1 (most positive)
+  positive: number; // shares of the text, summing to 1
+  negative: number;
+  neutral: number;
+  tone: 'positive' | 'negative' | 'mixed' | 'neutral';
+}
+
+export interface Sentiment {
+  document: SentimentScore; // mean of the sentence scores
+  sentences: { text: string; start: number; end: number; score: SentimentScore }[];
 }
 
 export interface NLPResult {
@@ -54,6 +476,7 @@ export interface NLPResult {
     end: number;
     confidence: number;
   }[];
+  statistics: TextStatistics;
   metadata: {
     language: string;
     confidence: number;